@@ -0,0 +1,403 @@
+use crate::pgn_parser::{GameTermination, PgnGame, Piece};
+use crate::polyglot::polyglot_key;
+use crate::position::{Color, Position, Variant};
+use crate::replay::Mainline;
+use crate::Result;
+use std::fmt::{Display, Formatter};
+
+// How the final position of a game ended it, or allows it to be ended.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Outcome {
+    Checkmate { winner: Color },
+    Stalemate,
+    InsufficientMaterial,
+    // The fifty-move rule and threefold repetition only let a player claim a draw.
+    FiftyMoves,
+    Repetition,
+    // Wins by the rules of a variant.
+    KingOfTheHill { winner: Color },
+    ThreeChecks { winner: Color },
+    ExplodedKing { winner: Color },
+    HordeCaptured,
+    // In Antichess a player with no pieces or no moves left wins.
+    NoMovesLeft { winner: Color },
+}
+
+impl Outcome {
+    // The result the outcome gives.
+    pub fn result(self) -> GameTermination {
+        match self.winner() {
+            Some(Color::White) => GameTermination::WhiteWins,
+            Some(Color::Black) => GameTermination::BlackWins,
+            None => GameTermination::Tie,
+        }
+    }
+
+    // The player the outcome wins the game for, if it isn't a draw.
+    pub fn winner(self) -> Option<Color> {
+        match self {
+            Outcome::Checkmate { winner }
+            | Outcome::KingOfTheHill { winner }
+            | Outcome::ThreeChecks { winner }
+            | Outcome::ExplodedKing { winner }
+            | Outcome::NoMovesLeft { winner } => Some(winner),
+            Outcome::HordeCaptured => Some(Color::Black),
+            Outcome::Stalemate
+            | Outcome::InsufficientMaterial
+            | Outcome::FiftyMoves
+            | Outcome::Repetition => None,
+        }
+    }
+
+    // True if the outcome ends the game by itself, rather than needing a claim.
+    pub fn is_forced(self) -> bool {
+        !matches!(self, Outcome::FiftyMoves | Outcome::Repetition)
+    }
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Checkmate { .. } => write!(f, "checkmate"),
+            Outcome::Stalemate => write!(f, "stalemate"),
+            Outcome::InsufficientMaterial => write!(f, "insufficient material"),
+            Outcome::FiftyMoves => write!(f, "the fifty-move rule"),
+            Outcome::Repetition => write!(f, "threefold repetition"),
+            Outcome::KingOfTheHill { .. } => write!(f, "a king reaching the centre"),
+            Outcome::ThreeChecks { .. } => write!(f, "a third check"),
+            Outcome::ExplodedKing { .. } => write!(f, "a king exploding"),
+            Outcome::HordeCaptured => write!(f, "the horde being captured"),
+            Outcome::NoMovesLeft { .. } => write!(f, "a player having no moves left"),
+        }
+    }
+}
+
+// True if neither side can possibly checkmate: bare kings, a single minor piece, or only bishops
+// which are all on squares of the same color.
+pub fn is_insufficient_material(position: &Position) -> bool {
+    let others: Vec<_> = position
+        .pieces()
+        .filter(|(_, piece)| piece.piece != Piece::King)
+        .collect();
+
+    match others.as_slice() {
+        [] => true,
+        [(_, piece)] => matches!(piece.piece, Piece::Bishop | Piece::Knight),
+        [(first, _), ..] => others.iter().all(|(square, piece)| {
+            piece.piece == Piece::Bishop
+                && (square.file.0 + square.rank.0) % 2 == (first.file.0 + first.rank.0) % 2
+        }),
+    }
+}
+
+// Returns how the position ends the game by a rule of its variant, if it does.
+fn variant_outcome(position: &Position) -> Option<Outcome> {
+    let colors = [Color::White, Color::Black];
+    match position.variant() {
+        Variant::KingOfTheHill => colors
+            .iter()
+            .find(|&&color| position.king_on_hill(color))
+            .map(|&winner| Outcome::KingOfTheHill { winner }),
+        Variant::ThreeCheck => colors
+            .iter()
+            .find(|&&color| position.checks_given(color) >= 3)
+            .map(|&winner| Outcome::ThreeChecks { winner }),
+        Variant::Atomic => colors
+            .iter()
+            .find(|&&color| position.king_square(color).is_none())
+            .map(|&loser| Outcome::ExplodedKing {
+                winner: loser.opponent(),
+            }),
+        Variant::Horde => {
+            let white = position
+                .pieces()
+                .any(|(_, piece)| piece.color == Color::White);
+            if white {
+                None
+            } else {
+                Some(Outcome::HordeCaptured)
+            }
+        }
+        // Having no pieces left means having no moves left.
+        Variant::Antichess if position.legal_moves().is_empty() => Some(Outcome::NoMovesLeft {
+            winner: position.side_to_move(),
+        }),
+        _ => None,
+    }
+}
+
+// Whether two positions are the same for the threefold repetition rule. The Polyglot key leaves
+// out the Crazyhouse pockets and the Three-check counts, so in the variants the positions are
+// compared in full, apart from the move counters.
+fn is_repetition(earlier: &Position, position: &Position) -> bool {
+    if position.variant().has_standard_rules() {
+        return polyglot_key(earlier) == polyglot_key(position);
+    }
+    let without_counters = |position: &Position| {
+        position
+            .clone()
+            .with_halfmove_clock(0)
+            .with_fullmove_number(1)
+    };
+    without_counters(earlier) == without_counters(position)
+}
+
+// Works out how the final position of the mainline ended the game, if it did.
+pub fn final_outcome(mainline: &Mainline) -> Option<Outcome> {
+    let position = mainline.final_position();
+    if let Some(outcome) = variant_outcome(position) {
+        return Some(outcome);
+    }
+    if position.legal_moves().is_empty() {
+        return Some(if position.is_check() {
+            Outcome::Checkmate {
+                winner: position.side_to_move().opponent(),
+            }
+        } else {
+            Outcome::Stalemate
+        });
+    }
+    // In the other variants a lone king can still win, or pieces can still be dropped.
+    if position.variant().has_standard_rules() && is_insufficient_material(position) {
+        return Some(Outcome::InsufficientMaterial);
+    }
+
+    let repetitions = mainline
+        .positions()
+        .iter()
+        .filter(|earlier| is_repetition(earlier, position))
+        .count();
+    if repetitions >= 3 {
+        Some(Outcome::Repetition)
+    } else if position.halfmove_clock() >= 100 {
+        Some(Outcome::FiftyMoves)
+    } else {
+        None
+    }
+}
+
+// Compares the result of a game with its Result and Termination tags and with how the final
+// position ended it, returning a warning for each disagreement.
+pub fn check_result(game: &PgnGame) -> Result<Vec<String>> {
+    let mut warnings = vec![];
+    let termination = game.movetext().game_termination();
+
+    if let Some(tag) = game.tag("Result") {
+        if tag != termination.to_string() {
+            warnings.push(format!(
+                "Result tag {} doesn't match the game termination {}",
+                tag, termination
+            ));
+        }
+    }
+
+    let outcome = match final_outcome(&Mainline::replay(game)?) {
+        Some(outcome) if outcome.is_forced() => outcome,
+        _ => return Ok(warnings),
+    };
+    if termination != outcome.result() {
+        warnings.push(format!(
+            "the game ends in {}, so the result should be {}, not {}",
+            outcome,
+            outcome.result(),
+            termination
+        ));
+    }
+    if let Some(tag) = game.tag("Termination") {
+        if !tag.eq_ignore_ascii_case("normal") {
+            warnings.push(format!(
+                "the game ends in {}, but the Termination tag is {}",
+                outcome, tag
+            ));
+        }
+    }
+    Ok(warnings)
+}
+
+// Sets the result of an unfinished game which actually ended in checkmate, stalemate or a variant
+// win, returning the outcome if the game was changed.
+pub fn fix_result(game: &mut PgnGame) -> Result<Option<Outcome>> {
+    if game.movetext().game_termination() != GameTermination::Unfinished {
+        return Ok(None);
+    }
+
+    match final_outcome(&Mainline::replay(game)?) {
+        Some(outcome) if outcome.winner().is_some() || outcome == Outcome::Stalemate => {
+            let result = outcome.result();
+            game.movetext_mut().set_game_termination(result);
+            game.tags_mut().set("Result", result.to_string());
+            Ok(Some(outcome))
+        }
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse_pgn;
+
+    fn outcome(pgn: &str) -> Option<Outcome> {
+        let database = parse_pgn(pgn).unwrap();
+        final_outcome(&Mainline::replay(&database.games()[0]).unwrap())
+    }
+
+    #[test]
+    fn test_final_outcome() {
+        assert_eq!(
+            Some(Outcome::Checkmate {
+                winner: Color::Black
+            }),
+            outcome("1. f3 e5 2. g4 Qh4# 0-1")
+        );
+        assert_eq!(
+            Some(Outcome::Stalemate),
+            outcome("[FEN \"7k/8/6K1/5Q2/8/8/8/8 w - - 0 1\"]\n\n1. Qf7 *")
+        );
+        assert_eq!(
+            Some(Outcome::InsufficientMaterial),
+            outcome("[FEN \"4k3/8/8/8/8/8/3q4/4KB2 w - - 0 1\"]\n\n1. Kxd2 *")
+        );
+        assert_eq!(
+            Some(Outcome::Repetition),
+            outcome("1. Nf3 Nf6 2. Ng1 Ng8 3. Nf3 Nf6 4. Ng1 Ng8 *")
+        );
+        assert_eq!(
+            Some(Outcome::FiftyMoves),
+            outcome("[FEN \"4k3/8/8/8/8/8/8/R3K3 w - - 99 80\"]\n\n80. Ra2 *")
+        );
+        assert_eq!(None, outcome("1. e4 e5 *"));
+    }
+
+    #[test]
+    fn test_variant_outcomes() {
+        assert_eq!(
+            Some(Outcome::KingOfTheHill {
+                winner: Color::White
+            }),
+            outcome(
+                "[Variant \"King of the Hill\"]\n\
+                 [FEN \"4k3/8/8/8/8/4K3/8/8 w - - 0 1\"]\n\n1. Kd4 *"
+            )
+        );
+        assert_eq!(
+            Some(Outcome::ThreeChecks {
+                winner: Color::White
+            }),
+            outcome(
+                "[Variant \"Three-check\"]\n\n\
+                 1. e4 e5 2. Bc4 Nc6 3. Bxf7+ Kxf7 4. Qh5+ Ke7 5. Qxe5+ *"
+            )
+        );
+        assert_eq!(
+            Some(Outcome::ExplodedKing {
+                winner: Color::White
+            }),
+            outcome(
+                "[Variant \"Atomic\"]\n\
+                 [FEN \"4k3/5p2/8/6N1/8/8/8/4K3 w - - 0 1\"]\n\n1. Nxf7 *"
+            )
+        );
+        assert_eq!(
+            Some(Outcome::HordeCaptured),
+            outcome(
+                "[Variant \"Horde\"]\n\
+                 [FEN \"4k3/8/8/8/8/8/2P5/2r5 b - - 0 1\"]\n\n1... Rxc2 *"
+            )
+        );
+        // Black has lost every piece, which wins Antichess.
+        assert_eq!(
+            Some(Outcome::NoMovesLeft {
+                winner: Color::Black
+            }),
+            outcome(
+                "[Variant \"Antichess\"]\n\
+                 [FEN \"8/8/8/3p4/4P3/8/8/8 w - - 0 1\"]\n\n1. exd5 *"
+            )
+        );
+        // The checks given differ each time the rook comes back, so the position doesn't repeat.
+        assert_eq!(
+            None,
+            outcome(
+                "[Variant \"Three-check\"]\n\
+                 [FEN \"4k3/8/8/8/8/8/8/R3K3 w - - 0 1\"]\n\n\
+                 1. Ra8+ Ke7 2. Ra1 Ke8 3. Ra8+ Ke7 4. Ra1 Ke8 *"
+            )
+        );
+        // A lone king can still win King of the Hill.
+        assert_eq!(
+            None,
+            outcome(
+                "[Variant \"King of the Hill\"]\n\
+                 [FEN \"4k3/8/8/8/8/8/8/4K3 w - - 0 1\"]\n\n1. Ke2 *"
+            )
+        );
+    }
+
+    #[test]
+    fn test_insufficient_material() {
+        let check = |fen: &str| is_insufficient_material(&fen.parse().unwrap());
+        assert!(check("4k3/8/8/8/8/8/8/4K3 w - - 0 1"));
+        assert!(check("4k3/8/8/8/8/8/8/4KN2 w - - 0 1"));
+        // Bishops on c1 and f8 are both on dark squares.
+        assert!(check("4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+        assert!(!check("4k1b1/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+        assert!(!check("4k3/8/8/8/8/8/8/3NKN2 w - - 0 1"));
+        assert!(!check("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"));
+    }
+
+    #[test]
+    fn test_check_result() {
+        let database = parse_pgn(
+            "[Result \"1-0\"]\n[Termination \"time forfeit\"]\n\n1. f3 e5 2. g4 Qh4# 1-0",
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                "the game ends in checkmate, so the result should be 0-1, not 1-0",
+                "the game ends in checkmate, but the Termination tag is time forfeit",
+            ],
+            check_result(&database.games()[0]).unwrap()
+        );
+
+        let database = parse_pgn("[Result \"*\"]\n\n1. e4 1-0").unwrap();
+        assert_eq!(
+            vec!["Result tag * doesn't match the game termination 1-0"],
+            check_result(&database.games()[0]).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_fix_result() {
+        let mut database = parse_pgn("[Result \"*\"]\n\n1. f3 e5 2. g4 Qh4# *\n\n1. e4 *").unwrap();
+        let games = database.games_mut();
+        assert_eq!(
+            Some(Outcome::Checkmate {
+                winner: Color::Black
+            }),
+            fix_result(&mut games[0]).unwrap()
+        );
+        assert_eq!(Some("0-1"), games[0].tag("Result"));
+        assert_eq!(
+            GameTermination::BlackWins,
+            games[0].movetext().game_termination()
+        );
+        assert_eq!(None, fix_result(&mut games[1]).unwrap());
+
+        let mut database = parse_pgn(
+            "[Variant \"Three-check\"]\n\n1. e4 f6 2. Qh5+ g6 3. Qxg6+ hxg6 4. d4 a6 5. Bd3 a5 6. e5 a4 7. Bxg6# *",
+        )
+        .unwrap();
+        let game = &mut database.games_mut()[0];
+        assert_eq!(
+            Some(Outcome::ThreeChecks {
+                winner: Color::White
+            }),
+            fix_result(game).unwrap()
+        );
+        assert_eq!(
+            GameTermination::WhiteWins,
+            game.movetext().game_termination()
+        );
+    }
+}
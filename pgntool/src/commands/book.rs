@@ -0,0 +1,104 @@
+use crate::commands::read_database;
+use crate::Result;
+use argh::FromArgs;
+use pgntool::polyglot::{BookBuilder, PolyglotBook};
+use pgntool::position::{Position, STARTING_FEN};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+
+#[derive(FromArgs)]
+/// Build and query Polyglot opening books.
+#[argh(subcommand, name = "book")]
+pub struct BookArgs {
+    #[argh(subcommand)]
+    command: BookCommand,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum BookCommand {
+    Build(BuildArgs),
+    Probe(ProbeArgs),
+}
+
+#[derive(FromArgs)]
+/// Build a Polyglot book from the mainlines of PGN games.
+#[argh(subcommand, name = "build")]
+struct BuildArgs {
+    /// the book file to write
+    #[argh(option, short = 'o')]
+    output: String,
+
+    /// the number of plies from the start of each game to include (default 20)
+    #[argh(option, default = "20")]
+    plies: usize,
+
+    /// PGN files to read games from
+    #[argh(positional)]
+    pgn_files: Vec<String>,
+}
+
+#[derive(FromArgs)]
+/// List the book moves for a position.
+#[argh(subcommand, name = "probe")]
+struct ProbeArgs {
+    /// the position to look up (default is the starting position)
+    #[argh(option, default = "STARTING_FEN.to_string()")]
+    fen: String,
+
+    /// the book file to read
+    #[argh(positional)]
+    book: String,
+}
+
+pub fn run(args: BookArgs) -> Result<()> {
+    match args.command {
+        BookCommand::Build(args) => build(args),
+        BookCommand::Probe(args) => probe(args),
+    }
+}
+
+fn build(args: BuildArgs) -> Result<()> {
+    let mut builder = BookBuilder::new(args.plies);
+    for file in &args.pgn_files {
+        let database = read_database(file)?;
+        for (index, game) in database.games().iter().enumerate() {
+            // A bad game shouldn't spoil the whole book.
+            if let Err(err) = builder.add_game(game) {
+                eprintln!("{}: skipping game {}: {}", file, index + 1, err);
+            }
+        }
+    }
+
+    let book = builder.build();
+    book.write(BufWriter::new(File::create(&args.output)?))?;
+    println!("Wrote {} entries to {}", book.entries().len(), args.output);
+    Ok(())
+}
+
+fn probe(args: ProbeArgs) -> Result<()> {
+    let position: Position = args.fen.parse()?;
+    let book = PolyglotBook::read(BufReader::new(File::open(&args.book)?))?;
+
+    let moves = book.probe(&position);
+    if moves.is_empty() {
+        println!("No book moves.");
+        return Ok(());
+    }
+
+    let total: u64 = moves.iter().map(|book_move| book_move.weight as u64).sum();
+    for book_move in moves {
+        let percent = if total > 0 {
+            100.0 * book_move.weight as f64 / total as f64
+        } else {
+            0.0
+        };
+        println!(
+            "{:<8} {:>6} {:>6.1}%",
+            book_move.mv.to_string(),
+            book_move.weight,
+            percent
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use crate::commands::{create_output, open_reader};
+use crate::Result;
+use argh::FromArgs;
+use pgntool::encoding::Encoding;
+use pgntool::replay::normalize_moves;
+use pgntool::{ParseOptions, PieceLetters};
+use std::io::Write;
+
+#[derive(FromArgs)]
+/// Rewrite games in standard PGN, or with the piece letters of another language.
+#[argh(subcommand, name = "convert")]
+pub struct ConvertArgs {
+    /// the piece letters the input is written with: en, de, es, figurine, or six letters for
+    /// K Q R B N P (default en)
+    #[argh(option, default = "PieceLetters::ENGLISH", from_str_fn(parse_letters))]
+    lang: PieceLetters,
+
+    /// the piece letters to write, as for --lang (default en, as the PGN standard requires)
+    #[argh(option, default = "PieceLetters::ENGLISH", from_str_fn(parse_letters))]
+    to: PieceLetters,
+
+    /// accept non-standard notation, such as long algebraic and coordinate moves, and write it
+    /// in standard SAN
+    #[argh(switch)]
+    lenient: bool,
+
+    /// keep escape lines (lines starting with '%') rather than dropping them
+    #[argh(switch)]
+    keep_escapes: bool,
+
+    /// the encoding to write: utf-8 (the default), latin-1 as the PGN standard specifies,
+    /// windows-1252, utf-16le or utf-16be
+    #[argh(option, default = "Encoding::Utf8")]
+    output_encoding: Encoding,
+
+    /// the file to write the games to (default is stdout)
+    #[argh(option, short = 'o')]
+    output: Option<String>,
+
+    /// PGN files to read games from
+    #[argh(positional)]
+    pgn_files: Vec<String>,
+}
+
+pub fn parse_letters(value: &str) -> std::result::Result<PieceLetters, String> {
+    value.parse().map_err(|_| {
+        format!(
+            "unknown language {}: expected en, de, es, figurine or six piece letters",
+            value
+        )
+    })
+}
+
+pub fn run(args: ConvertArgs) -> Result<()> {
+    let mut writer = create_output(args.output.as_deref())?;
+    let options = ParseOptions {
+        letters: args.lang,
+        keep_escape_lines: args.keep_escapes,
+        lenient: args.lenient,
+    };
+
+    writer.write_all(args.output_encoding.byte_order_mark())?;
+    let mut first = true;
+    for file in &args.pgn_files {
+        let mut reader = open_reader(file)?.with_options(options);
+        let mut index = 0;
+        while let Some(game) = reader.next() {
+            // The notation was rewritten, so the warnings about it are not reported.
+            reader.take_warnings();
+            match game {
+                Ok(mut game) => {
+                    if args.lenient {
+                        if let Err(err) = normalize_moves(&mut game) {
+                            eprintln!("{}: game {}: moves not rewritten: {}", file, index + 1, err);
+                        }
+                    }
+                    let separator = if first { "" } else { "\n" };
+                    let text = format!("{}{}", separator, args.to.localize(&game));
+                    match args.output_encoding.encode(&text) {
+                        Ok(bytes) => {
+                            writer.write_all(&bytes)?;
+                            first = false;
+                        }
+                        Err(err) => eprintln!("{}: skipping game {}: {}", file, index + 1, err),
+                    }
+                }
+                Err(err) => eprintln!("{}: skipping game {}: {}", file, index + 1, err),
+            }
+            index += 1;
+        }
+    }
+    writer.finish()
+}
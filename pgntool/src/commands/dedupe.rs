@@ -0,0 +1,67 @@
+use crate::commands::{create_output, read_database};
+use crate::Result;
+use argh::FromArgs;
+use pgntool::dedupe::find_duplicates;
+use std::io::Write;
+
+#[derive(FromArgs)]
+/// Remove duplicate games, keeping the best copy of each.
+#[argh(subcommand, name = "dedupe")]
+pub struct DedupeArgs {
+    /// a tag which must also match for games to be duplicates, e.g. White (may be repeated)
+    #[argh(option)]
+    key: Vec<String>,
+
+    /// the file to write the unique games to (default is stdout)
+    #[argh(option, short = 'o')]
+    output: Option<String>,
+
+    /// PGN files to read games from
+    #[argh(positional)]
+    pgn_files: Vec<String>,
+}
+
+pub fn run(args: DedupeArgs) -> Result<()> {
+    // (file, game number within the file) for each game, for the report.
+    let mut sources = vec![];
+    let mut databases = vec![];
+    for file in &args.pgn_files {
+        let database = read_database(file)?;
+        sources.extend((1..=database.games().len()).map(|number| (file.as_str(), number)));
+        databases.push(database);
+    }
+    let games: Vec<_> = databases
+        .iter()
+        .flat_map(|database| database.games())
+        .collect();
+
+    let groups = find_duplicates(games.iter().copied(), &args.key);
+
+    let mut writer = create_output(args.output.as_deref())?;
+    let mut dropped_count = 0;
+    for (index, group) in groups.iter().enumerate() {
+        if index > 0 {
+            writeln!(writer)?;
+        }
+        write!(writer, "{}", games[group.kept])?;
+
+        let (kept_file, kept_number) = sources[group.kept];
+        for &dropped in &group.dropped {
+            let (file, number) = sources[dropped];
+            eprintln!(
+                "{}: dropped game {}, a duplicate of {} game {}",
+                file, number, kept_file, kept_number
+            );
+        }
+        dropped_count += group.dropped.len();
+    }
+    writer.finish()?;
+
+    eprintln!(
+        "Kept {} of {} games, dropped {} duplicates.",
+        groups.len(),
+        games.len(),
+        dropped_count
+    );
+    Ok(())
+}
@@ -0,0 +1,161 @@
+use crate::commands::read_database;
+use crate::{Err, Result};
+use argh::FromArgs;
+use pgntool::replay::Mainline;
+use pgntool::svg::{is_color, Marks, SvgDiagram};
+use pgntool::{Comment, Element, PgnGame, SequenceMember};
+use std::fs;
+use std::path::Path;
+
+#[derive(FromArgs)]
+/// Write SVG diagrams of positions from games, with their [%csl] and [%cal] marks.
+#[argh(subcommand, name = "diagram")]
+pub struct DiagramArgs {
+    /// the number of the game to draw, counting from 1 (default is every game)
+    #[argh(option)]
+    game: Option<usize>,
+
+    /// draw the position after this many plies (may be repeated)
+    #[argh(option)]
+    ply: Vec<usize>,
+
+    /// draw the position after every move with this NAG, e.g. 3 for "!!"
+    #[argh(option)]
+    nag: Option<u8>,
+
+    /// the directory to write the diagrams to (default is the current directory)
+    #[argh(option, short = 'd', default = "String::from(\".\")")]
+    directory: String,
+
+    /// show the board from Black's side
+    #[argh(switch)]
+    flip: bool,
+
+    /// leave out the file and rank labels
+    #[argh(switch)]
+    no_coordinates: bool,
+
+    /// the color of the light squares, as #rrggbb or a color name (default #f0d9b5)
+    #[argh(
+        option,
+        default = "String::from(\"#f0d9b5\")",
+        from_str_fn(parse_color)
+    )]
+    light: String,
+
+    /// the color of the dark squares, as #rrggbb or a color name (default #b58863)
+    #[argh(
+        option,
+        default = "String::from(\"#b58863\")",
+        from_str_fn(parse_color)
+    )]
+    dark: String,
+
+    /// the width and height of the diagrams in pixels (default 360)
+    #[argh(option, default = "360")]
+    size: u32,
+
+    /// the PGN file to read games from
+    #[argh(positional)]
+    pgn_file: String,
+}
+
+fn parse_color(value: &str) -> std::result::Result<String, String> {
+    if is_color(value) {
+        Ok(value.to_string())
+    } else {
+        Err(format!(
+            "invalid color {}: expected #rrggbb, #rgb or a color name",
+            value
+        ))
+    }
+}
+
+// The NAGs and comments after each mainline move. The first entry is for anything before the
+// first move.
+fn move_annotations(game: &PgnGame) -> Vec<(Vec<u8>, Vec<&Comment>)> {
+    let mut annotations = vec![(vec![], vec![])];
+    for member in game.movetext().element_sequence().members() {
+        // unwrap: there is always at least the first entry.
+        let (nags, comments) = annotations.last_mut().unwrap();
+        match member {
+            SequenceMember::Move(Element::Move(_)) => annotations.push((vec![], vec![])),
+            SequenceMember::Move(Element::Annotation(nag)) => nags.push(nag.0),
+            SequenceMember::Comment(comment) => comments.push(comment),
+            SequenceMember::Move(Element::MoveNumber(_)) | SequenceMember::Variation(_) => {}
+        }
+    }
+    annotations
+}
+
+fn draw_game(args: &DiagramArgs, number: usize, game: &PgnGame) -> Result<usize> {
+    let mainline = Mainline::replay(game)?;
+    let annotations = move_annotations(game);
+
+    let mut plies: Vec<usize> = args.ply.clone();
+    if let Some(nag) = args.nag {
+        plies.extend((1..annotations.len()).filter(|&ply| annotations[ply].0.contains(&nag)));
+    }
+    if args.ply.is_empty() && args.nag.is_none() {
+        plies.push(mainline.moves().len());
+    }
+
+    let mut written = 0;
+    for ply in plies {
+        let position = match mainline.positions().get(ply) {
+            Some(position) => position,
+            None => {
+                eprintln!(
+                    "{}: game {} only has {} plies",
+                    args.pgn_file,
+                    number,
+                    mainline.moves().len()
+                );
+                continue;
+            }
+        };
+        let svg = SvgDiagram::new(position)
+            .marks(Marks::from_comments(annotations[ply].1.iter().copied()))
+            .flipped(args.flip)
+            .coordinates(!args.no_coordinates)
+            .colors(args.light.as_str(), args.dark.as_str())
+            .size(args.size);
+
+        let path = Path::new(&args.directory).join(format!("game{}-ply{}.svg", number, ply));
+        fs::write(&path, svg.to_string())?;
+        eprintln!("Wrote {}", path.display());
+        written += 1;
+    }
+    Ok(written)
+}
+
+pub fn run(args: DiagramArgs) -> Result<()> {
+    let database = read_database(&args.pgn_file)?;
+    fs::create_dir_all(&args.directory)?;
+
+    let games: Vec<(usize, &PgnGame)> = match args.game {
+        Some(number) => {
+            let game = number
+                .checked_sub(1)
+                .and_then(|index| database.games().get(index))
+                .ok_or_else(|| Err::Usage(format!("{} has no game {}", args.pgn_file, number)))?;
+            vec![(number, game)]
+        }
+        None => database
+            .games()
+            .iter()
+            .enumerate()
+            .map(|(index, game)| (index + 1, game))
+            .collect(),
+    };
+
+    let mut written = 0;
+    for (number, game) in games {
+        match draw_game(&args, number, game) {
+            Ok(count) => written += count,
+            Err(err) => eprintln!("{}: game {}: {}", args.pgn_file, number, err),
+        }
+    }
+    eprintln!("Wrote {} diagrams.", written);
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use crate::commands::{create_output, read_database};
+use crate::Result;
+use argh::FromArgs;
+use pgntool::eco::{apply_entry, EcoClassifier};
+use std::io::Write;
+
+#[derive(FromArgs)]
+/// Classify games by opening and set their ECO, Opening and Variation tags.
+#[argh(subcommand, name = "eco")]
+pub struct EcoArgs {
+    /// report games whose ECO tag is missing or wrong instead of writing PGN
+    #[argh(switch)]
+    check: bool,
+
+    /// the file to write the tagged games to (default is stdout)
+    #[argh(option, short = 'o')]
+    output: Option<String>,
+
+    /// PGN files to read games from
+    #[argh(positional)]
+    pgn_files: Vec<String>,
+}
+
+pub fn run(args: EcoArgs) -> Result<()> {
+    let classifier = EcoClassifier::new();
+    let mut writer = create_output(args.output.as_deref())?;
+
+    for file in &args.pgn_files {
+        let mut database = read_database(file)?;
+        for (index, game) in database.games_mut().iter_mut().enumerate() {
+            let entry = match classifier.classify(game) {
+                Ok(Some(entry)) => entry,
+                Ok(None) => {
+                    eprintln!("{}: game {}: no matching opening", file, index + 1);
+                    continue;
+                }
+                Err(err) => {
+                    eprintln!("{}: game {}: {}", file, index + 1, err);
+                    continue;
+                }
+            };
+
+            if args.check {
+                if game.tag("ECO") != Some(entry.code) {
+                    writeln!(
+                        writer,
+                        "{}: game {}: ECO {} should be {} ({})",
+                        file,
+                        index + 1,
+                        game.tag("ECO").unwrap_or("missing"),
+                        entry.code,
+                        entry.opening
+                    )?;
+                }
+            } else {
+                apply_entry(entry, game);
+            }
+        }
+
+        if !args.check {
+            write!(writer, "{}", database)?;
+        }
+    }
+
+    writer.finish()
+}
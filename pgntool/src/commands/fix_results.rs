@@ -0,0 +1,56 @@
+use crate::commands::{create_output, read_database};
+use crate::Result;
+use argh::FromArgs;
+use pgntool::adjudicate::fix_result;
+use std::io::Write;
+
+#[derive(FromArgs)]
+/// Set the result of unfinished games which actually ended in checkmate, stalemate or a win
+/// by the rules of their variant.
+#[argh(subcommand, name = "fix-results")]
+pub struct FixResultsArgs {
+    /// the file to write the games to (default is stdout)
+    #[argh(option, short = 'o')]
+    output: Option<String>,
+
+    /// PGN files to read games from
+    #[argh(positional)]
+    pgn_files: Vec<String>,
+}
+
+pub fn run(args: FixResultsArgs) -> Result<()> {
+    let mut writer = create_output(args.output.as_deref())?;
+
+    let mut fixed = 0;
+    let mut first = true;
+    for file in &args.pgn_files {
+        let mut database = read_database(file)?;
+        for (index, game) in database.games_mut().iter_mut().enumerate() {
+            match fix_result(game) {
+                Ok(Some(outcome)) => {
+                    eprintln!(
+                        "{}: game {}: set the result to {} ({})",
+                        file,
+                        index + 1,
+                        outcome.result(),
+                        outcome
+                    );
+                    fixed += 1;
+                }
+                Ok(None) => {}
+                Err(err) => eprintln!("{}: game {}: {}", file, index + 1, err),
+            }
+        }
+        if !database.games().is_empty() {
+            if !first {
+                writeln!(writer)?;
+            }
+            write!(writer, "{}", database)?;
+            first = false;
+        }
+    }
+    writer.finish()?;
+
+    eprintln!("Fixed {} results.", fixed);
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use crate::commands::open_reader;
+use crate::{Err, Result};
+use argh::FromArgs;
+use pgntool::adjudicate::check_result;
+use pgntool::move_numbers::check_move_numbers;
+use pgntool::ParseOptions;
+
+#[derive(FromArgs)]
+/// Check PGN files, reporting errors, non-standard notation, wrong move numbers and results.
+#[argh(subcommand, name = "lint")]
+pub struct LintArgs {
+    /// reject non-standard notation, rather than reporting how it was normalized
+    #[argh(switch)]
+    strict: bool,
+
+    /// PGN files to check
+    #[argh(positional)]
+    pgn_files: Vec<String>,
+}
+
+pub fn run(args: LintArgs) -> Result<()> {
+    let options = if args.strict {
+        ParseOptions::default()
+    } else {
+        ParseOptions::lenient()
+    };
+
+    let (mut games, mut warning_count, mut error_count) = (0, 0, 0);
+    for file in &args.pgn_files {
+        let mut reader = open_reader(file)?.with_options(options);
+        let mut number = 0;
+        while let Some(game) = reader.next() {
+            number += 1;
+            let mut warnings = reader.take_warnings();
+            let checked = game.and_then(|game| {
+                let mut game_warnings = check_move_numbers(&game)?;
+                game_warnings.extend(check_result(&game)?);
+                Ok(game_warnings)
+            });
+            match checked {
+                Ok(game_warnings) => warnings.extend(game_warnings),
+                Err(err) => {
+                    println!("{}: game {}: error: {}", file, number, err);
+                    error_count += 1;
+                }
+            }
+            for warning in warnings {
+                println!("{}: game {}: {}", file, number, warning);
+                warning_count += 1;
+            }
+        }
+        games += number;
+    }
+
+    eprintln!(
+        "Checked {} games: {} errors, {} warnings.",
+        games, error_count, warning_count
+    );
+    if error_count > 0 {
+        return Err(Err::Lint(error_count));
+    }
+    Ok(())
+}
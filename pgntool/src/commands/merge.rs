@@ -0,0 +1,195 @@
+use crate::commands::{create_output, open_reader};
+use crate::Result;
+use argh::FromArgs;
+use pgntool::compression;
+use pgntool::encoding::{decode_with, Encoding};
+use pgntool::sort::{sort_key, TagKey};
+use pgntool::{parse_pgn_with, ParseOptions};
+use std::fs::{self, File};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+#[derive(FromArgs)]
+/// Combine PGN files into one, optionally sorting the games.
+#[argh(subcommand, name = "merge")]
+pub struct MergeArgs {
+    /// comma-separated tags to sort by, e.g. date,event,round (default is to keep input order)
+    #[argh(option)]
+    sort: Option<String>,
+
+    /// the file to write the games to (default is stdout)
+    #[argh(option, short = 'o')]
+    output: Option<String>,
+
+    /// PGN files to read games from
+    #[argh(positional)]
+    pgn_files: Vec<String>,
+}
+
+// Tag names are capitalized, so that "date" means the Date tag.
+fn tag_name(name: &str) -> String {
+    let mut chars = name.trim().chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+// Where a game's text is: in one of the input files, or in the spill file for games from
+// compressed files.
+struct GameLocation {
+    // None for a game whose tags couldn't be read, which is sorted last.
+    key: Option<Vec<TagKey>>,
+    file: usize,
+    offset: u64,
+    length: u64,
+}
+
+// Compressed files can't be read from an offset, so the text of their games is copied to a
+// temporary file, which is read from instead. It is created when the first game is copied, and
+// removed when dropped.
+struct SpillFile {
+    path: PathBuf,
+    writer: Option<BufWriter<File>>,
+    length: u64,
+    created: bool,
+}
+
+impl SpillFile {
+    fn new() -> Self {
+        SpillFile {
+            path: std::env::temp_dir().join(format!("pgntool-merge-{}.pgn", std::process::id())),
+            writer: None,
+            length: 0,
+            created: false,
+        }
+    }
+
+    // Copies the text to the file, returning its offset.
+    fn write(&mut self, text: &str) -> Result<u64> {
+        let writer = match &mut self.writer {
+            Some(writer) => writer,
+            None => {
+                let file = File::create(&self.path)?;
+                self.created = true;
+                self.writer.insert(BufWriter::new(file))
+            }
+        };
+        writer.write_all(text.as_bytes())?;
+        let offset = self.length;
+        self.length += text.len() as u64;
+        Ok(offset)
+    }
+
+    // Finishes writing, and opens the file to read the games back, if any were copied.
+    fn open(&mut self) -> Result<Option<File>> {
+        match self.writer.take() {
+            Some(mut writer) => {
+                writer.flush()?;
+                Ok(Some(File::open(&self.path)?))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        if self.created {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn write_game(writer: &mut dyn Write, text: &str, first: bool) -> Result<()> {
+    if !first {
+        writeln!(writer)?;
+    }
+    writeln!(writer, "{}", text.trim_end())?;
+    Ok(())
+}
+
+pub fn run(args: MergeArgs) -> Result<()> {
+    let mut writer = create_output(args.output.as_deref())?;
+
+    let tags: Vec<String> = match &args.sort {
+        Some(sort) => sort.split(',').map(tag_name).collect(),
+        None => {
+            // Without sorting, the games can be copied straight through.
+            let mut first = true;
+            for file in &args.pgn_files {
+                for raw in open_reader(file)?.into_raw() {
+                    write_game(&mut writer, &raw?.text, first)?;
+                    first = false;
+                }
+            }
+            return writer.finish();
+        }
+    };
+
+    // Only the sort keys and locations are kept in memory. The games are read again, in order,
+    // once they are sorted.
+    let mut locations = vec![];
+    // The encoding of each file, if it has a byte order mark or one was given.
+    let mut encodings = vec![];
+    let mut spill = SpillFile::new();
+    // The spill file comes after the input files.
+    let spill_index = args.pgn_files.len();
+    for (file_index, file) in args.pgn_files.iter().enumerate() {
+        let compressed = compression::detect(file)?.is_some();
+        let mut reader = open_reader(file)?;
+        let mut index = 0;
+        while let Some(raw) = reader.next_raw() {
+            let raw = raw?;
+            index += 1;
+            // The games are copied unchanged, as they are without sorting, so the parse is only
+            // for the tags, and is lenient. A game which still can't be parsed is kept, at the end.
+            let key = match parse_pgn_with(&raw.text, ParseOptions::lenient()) {
+                Ok((database, _)) => database.games().first().map(|game| sort_key(game, &tags)),
+                Err(err) => {
+                    eprintln!("{}: game {}: sorted last: {}", file, index, err);
+                    None
+                }
+            };
+            let location = if compressed {
+                GameLocation {
+                    key,
+                    file: spill_index,
+                    offset: spill.write(&raw.text)?,
+                    length: raw.text.len() as u64,
+                }
+            } else {
+                GameLocation {
+                    key,
+                    file: file_index,
+                    offset: raw.offset,
+                    length: raw.length,
+                }
+            };
+            locations.push(location);
+        }
+        encodings.push(reader.encoding());
+    }
+    // The sort is stable, so games with equal keys stay in input order.
+    locations.sort_by(|a, b| (a.key.is_none(), &a.key).cmp(&(b.key.is_none(), &b.key)));
+
+    let mut files = args
+        .pgn_files
+        .iter()
+        .map(File::open)
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    if let Some(file) = spill.open()? {
+        // The text was copied as UTF-8, whatever the encoding of the file it came from.
+        files.push(file);
+        encodings.push(Some(Encoding::Utf8));
+    }
+    for (index, location) in locations.iter().enumerate() {
+        let file = &mut files[location.file];
+        file.seek(SeekFrom::Start(location.offset))?;
+        let mut bytes = vec![0; location.length as usize];
+        file.read_exact(&mut bytes)?;
+        let text = decode_with(&bytes, encodings[location.file])?;
+        write_game(&mut writer, &text, index == 0)?;
+    }
+    writer.finish()
+}
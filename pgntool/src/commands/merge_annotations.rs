@@ -0,0 +1,137 @@
+use crate::commands::{create_output, read_database};
+use crate::Result;
+use argh::FromArgs;
+use pgntool::dedupe::game_key;
+use pgntool::game_tree::GameTree;
+use pgntool::PgnGame;
+use std::collections::HashMap;
+use std::io::Write;
+
+#[derive(FromArgs)]
+/// Combine separately annotated copies of the same games into one annotated copy of each.
+#[argh(subcommand, name = "merge-annotations")]
+pub struct MergeAnnotationsArgs {
+    /// a tag which must match for games to be copies of each other, as well as the moves (may
+    /// be repeated; default White, Black, Date and Round)
+    #[argh(option)]
+    key: Vec<String>,
+
+    /// prefix each comment with its author: the Annotator tag of its copy, or else its file
+    #[argh(switch)]
+    authors: bool,
+
+    /// the file to write the games to (default is stdout)
+    #[argh(option, short = 'o')]
+    output: Option<String>,
+
+    /// PGN files to read games from
+    #[argh(positional)]
+    pgn_files: Vec<String>,
+}
+
+// A copy of a game, with where it came from for messages and the author.
+struct GameCopy {
+    game: PgnGame,
+    file: String,
+    number: usize,
+}
+
+impl GameCopy {
+    fn author(&self) -> &str {
+        self.game.tag("Annotator").unwrap_or(&self.file)
+    }
+}
+
+// The tags which identify a game, unless others are given with --key.
+const KEY_TAGS: [&str; 4] = ["White", "Black", "Date", "Round"];
+
+// Merges the other copies into the first one, returning the indexes of the copies which couldn't
+// be merged. Those are reported, and written out as they are.
+fn merge_copies(copies: &mut [GameCopy], authors: bool) -> Result<Vec<usize>> {
+    let mut tree = GameTree::from_game(&copies[0].game)?;
+    if authors {
+        tree.credit_comments(copies[0].author());
+    }
+    let mut unmerged = vec![];
+    for (index, copy) in copies.iter().enumerate().skip(1) {
+        let author = Some(copy.author()).filter(|_| authors);
+        if let Err(err) =
+            GameTree::from_game(&copy.game).and_then(|other| tree.merge(&other, author))
+        {
+            eprintln!("{}: game {}: not merged: {}", copy.file, copy.number, err);
+            unmerged.push(index);
+        }
+    }
+    tree.write_to(&mut copies[0].game);
+    Ok(unmerged)
+}
+
+pub fn run(args: MergeAnnotationsArgs) -> Result<()> {
+    let mut writer = create_output(args.output.as_deref())?;
+
+    // Copies of the same game have the same moves and the same key tags. Different games can
+    // share their moves, so the tags are needed as well. A game without moves is never taken as
+    // a copy of another.
+    let key_tags = if args.key.is_empty() {
+        KEY_TAGS.iter().map(|tag| tag.to_string()).collect()
+    } else {
+        args.key.clone()
+    };
+    let mut groups: Vec<Vec<GameCopy>> = vec![];
+    let mut group_index = HashMap::new();
+    for file in &args.pgn_files {
+        let database = read_database(file)?;
+        for (index, game) in database.into_games().into_iter().enumerate() {
+            let group = if game.movetext().element_sequence().moves().next().is_none() {
+                groups.push(vec![]);
+                groups.len() - 1
+            } else {
+                *group_index
+                    .entry(game_key(&game, &key_tags))
+                    .or_insert_with(|| {
+                        groups.push(vec![]);
+                        groups.len() - 1
+                    })
+            };
+            groups[group].push(GameCopy {
+                game,
+                file: file.clone(),
+                number: index + 1,
+            });
+        }
+    }
+
+    let mut first = true;
+    for group in groups.iter_mut() {
+        // The copies written separately, since they weren't merged into the first.
+        let mut unmerged = vec![];
+        if group.len() > 1 {
+            match merge_copies(group, args.authors) {
+                Ok(failed) => {
+                    eprintln!(
+                        "{}: game {}: merged {} copies",
+                        group[0].file,
+                        group[0].number,
+                        group.len() - failed.len()
+                    );
+                    unmerged = failed;
+                }
+                Err(err) => {
+                    eprintln!(
+                        "{}: game {}: not merged: {}",
+                        group[0].file, group[0].number, err
+                    );
+                    unmerged = (1..group.len()).collect();
+                }
+            }
+        }
+        for index in std::iter::once(0).chain(unmerged) {
+            if !first {
+                writeln!(writer)?;
+            }
+            write!(writer, "{}", group[index].game)?;
+            first = false;
+        }
+    }
+    writer.finish()
+}
@@ -0,0 +1,98 @@
+use crate::Result;
+use pgntool::compression::{self, OutputFile};
+use pgntool::encoding::{read_file, Encoding};
+use pgntool::reader::GameReader;
+use pgntool::{ParseOptions, PgnDatabase};
+use std::cell::Cell;
+use std::io::{stdout, BufRead, Stdout, Write};
+
+pub mod book;
+pub mod convert;
+pub mod dedupe;
+pub mod diagram;
+pub mod eco;
+pub mod fix_results;
+pub mod lint;
+pub mod merge;
+pub mod merge_annotations;
+pub mod show;
+pub mod split;
+pub mod strip;
+pub mod tree;
+pub mod view;
+
+// The encoding given with --encoding, for every file read. Without it, the encoding of each file
+// is detected.
+thread_local! {
+    static INPUT_ENCODING: Cell<Option<Encoding>> = const { Cell::new(None) };
+}
+
+pub fn set_input_encoding(encoding: Option<Encoding>) {
+    INPUT_ENCODING.with(|input| input.set(encoding));
+}
+
+pub fn input_encoding() -> Option<Encoding> {
+    INPUT_ENCODING.with(|input| input.get())
+}
+
+pub fn open_reader(path: &str) -> Result<GameReader<Box<dyn BufRead>>> {
+    let reader = GameReader::open(path)?;
+    Ok(match input_encoding() {
+        Some(encoding) => reader.with_encoding(encoding),
+        None => reader,
+    })
+}
+
+pub fn read_database(path: &str) -> Result<PgnDatabase> {
+    let pgn_string = read_file(path, input_encoding())?;
+    Ok(pgntool::parse_pgn(pgn_string)?)
+}
+
+pub fn read_database_with(path: &str, options: ParseOptions) -> Result<PgnDatabase> {
+    let pgn_string = read_file(path, input_encoding())?;
+    let (database, warnings) = pgntool::parse_pgn_with(pgn_string, options)?;
+    for warning in warnings {
+        eprintln!("{}: {}", path, warning);
+    }
+    Ok(database)
+}
+
+// Where a command writes its games: the file given with --output, or else stdout.
+pub enum Output {
+    File(OutputFile),
+    Stdout(Stdout),
+}
+
+pub fn create_output(path: Option<&str>) -> Result<Output> {
+    Ok(match path {
+        Some(path) => Output::File(compression::create(path)?),
+        None => Output::Stdout(stdout()),
+    })
+}
+
+impl Output {
+    // Finishes writing, reporting any error. A compressed file is incomplete until this is done.
+    pub fn finish(self) -> Result<()> {
+        match self {
+            Output::File(file) => file.finish()?,
+            Output::Stdout(mut stdout) => stdout.flush()?,
+        }
+        Ok(())
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Output::File(file) => file.write(buf),
+            Output::Stdout(stdout) => stdout.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Output::File(file) => file.flush(),
+            Output::Stdout(stdout) => stdout.flush(),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use crate::commands::read_database;
+use crate::{Err, Result};
+use argh::FromArgs;
+use pgntool::position::Color;
+use pgntool::replay::Mainline;
+
+#[derive(FromArgs)]
+/// Show the board after a move of a game.
+#[argh(subcommand, name = "show")]
+pub struct ShowArgs {
+    /// the number of the game in the file, counting from 1 (default 1)
+    #[argh(option, default = "1")]
+    game: usize,
+
+    /// the number of plies to play, 0 for the starting position (default is the whole game)
+    #[argh(option)]
+    ply: Option<usize>,
+
+    /// draw the pieces with chess glyphs rather than letters
+    #[argh(switch)]
+    unicode: bool,
+
+    /// the PGN file to read the game from
+    #[argh(positional)]
+    pgn_file: String,
+}
+
+pub fn run(args: ShowArgs) -> Result<()> {
+    let database = read_database(&args.pgn_file)?;
+    let game = args
+        .game
+        .checked_sub(1)
+        .and_then(|index| database.games().get(index))
+        .ok_or_else(|| {
+            Err::Usage(format!(
+                "{} has {} games, so there is no game {}",
+                args.pgn_file,
+                database.games().len(),
+                args.game
+            ))
+        })?;
+
+    let plies = args.ply.unwrap_or(usize::MAX);
+    let mainline = Mainline::replay_plies(game, plies)?;
+    if args.ply.is_some_and(|ply| ply > mainline.moves().len()) {
+        return Err(Err::Usage(format!(
+            "game {} only has {} plies",
+            args.game,
+            mainline.moves().len()
+        )));
+    }
+
+    let last_move = mainline.plies().last().map(|(position, mv)| {
+        let number = position.fullmove_number();
+        let san = position.san(mv);
+        match position.side_to_move() {
+            Color::White => format!("{}. {}", number, san),
+            Color::Black => format!("{}... {}", number, san),
+        }
+    });
+    match last_move {
+        Some(last_move) => println!("After {}:", last_move),
+        None => println!("Starting position:"),
+    }
+    println!(
+        "{}",
+        mainline
+            .final_position()
+            .diagram()
+            .last_move(mainline.moves().last().copied())
+            .unicode(args.unicode)
+    );
+    Ok(())
+}
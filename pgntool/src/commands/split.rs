@@ -0,0 +1,306 @@
+use crate::commands::open_reader;
+use crate::{Err, Result};
+use argh::FromArgs;
+use pgntool::PgnGame;
+use std::collections::{HashMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(FromArgs)]
+/// Split PGN files into several files. Exactly one way of splitting must be chosen.
+#[argh(subcommand, name = "split")]
+pub struct SplitArgs {
+    /// put this many games in each file
+    #[argh(option)]
+    count: Option<usize>,
+
+    /// start a new file before one would grow past this many bytes
+    #[argh(option)]
+    size: Option<u64>,
+
+    /// put the games for each value of this tag in their own file, e.g. Event
+    #[argh(option)]
+    tag: Option<String>,
+
+    /// put each player's games in their own file (each game goes in two files)
+    #[argh(switch)]
+    player: bool,
+
+    /// put each year's games in their own file
+    #[argh(switch)]
+    year: bool,
+
+    /// the directory to write the files to (default is the current directory)
+    #[argh(option, short = 'd', default = "\".\".to_string()")]
+    dir: String,
+
+    /// the start of each output file name (default "split")
+    #[argh(option, default = "\"split\".to_string()")]
+    prefix: String,
+
+    /// PGN files to read games from
+    #[argh(positional)]
+    pgn_files: Vec<String>,
+}
+
+enum Mode {
+    Count(usize),
+    Size(u64),
+    Tag(String),
+    Player,
+    Year,
+}
+
+impl SplitArgs {
+    fn mode(&self) -> Result<Mode> {
+        let mut modes = vec![];
+        modes.extend(self.count.map(Mode::Count));
+        modes.extend(self.size.map(Mode::Size));
+        modes.extend(self.tag.clone().map(Mode::Tag));
+        if self.player {
+            modes.push(Mode::Player);
+        }
+        if self.year {
+            modes.push(Mode::Year);
+        }
+
+        match (modes.pop(), modes.is_empty()) {
+            (Some(mode), true) => Ok(mode),
+            _ => Err(Err::Usage(
+                "choose one of --count, --size, --tag, --player or --year".to_string(),
+            )),
+        }
+    }
+}
+
+// Keeps only characters which are safe in file names.
+fn file_name_part(value: &str) -> String {
+    let part: String = value
+        .trim()
+        .chars()
+        .map(|ch| {
+            if ch.is_alphanumeric() || ch == '-' || ch == '_' {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if part.is_empty() {
+        "unknown".to_string()
+    } else {
+        part
+    }
+}
+
+// The file names for the values of a tag. Different values can clean up to the same name, as
+// "A/B" and "A:B" do, so each value after the first gets a numbered suffix.
+#[derive(Default)]
+struct TagFileNames {
+    names: HashMap<String, String>,
+    // The names given out, in lower case, since some file systems ignore case.
+    used: HashSet<String>,
+}
+
+impl TagFileNames {
+    fn name(&mut self, value: &str) -> String {
+        let value = value.trim();
+        if let Some(name) = self.names.get(value) {
+            return name.clone();
+        }
+        let base = file_name_part(value);
+        let mut name = base.clone();
+        let mut suffix = 1;
+        while !self.used.insert(name.to_lowercase()) {
+            suffix += 1;
+            name = format!("{}-{}", base, suffix);
+        }
+        if name != base {
+            eprintln!(
+                "\"{}\" has the same file name as another value, so its games go in {}",
+                value, name
+            );
+        }
+        self.names.insert(value.to_string(), name.clone());
+        name
+    }
+}
+
+fn year(game: &PgnGame) -> String {
+    game.tag("Date")
+        .and_then(|date| date.get(..4))
+        .filter(|year| year.chars().all(|ch| ch.is_ascii_digit()))
+        .unwrap_or("unknown")
+        .to_string()
+}
+
+// The most output files kept open at once. --tag and --player can write thousands of files, so
+// when another one is needed the least recently used is closed, and it is reopened to append to
+// if it gets another game.
+const MAX_OPEN_FILES: usize = 128;
+
+struct OpenFile {
+    writer: BufWriter<File>,
+    last_used: u64,
+}
+
+// The output files, opened as games are assigned to them.
+struct Outputs {
+    dir: PathBuf,
+    prefix: String,
+    // The number of games written to each file, whether it is still open or not.
+    games: HashMap<String, usize>,
+    open: HashMap<String, OpenFile>,
+    writes: u64,
+}
+
+impl Outputs {
+    fn open(&mut self, name: &str) -> Result<()> {
+        if self.open.len() >= MAX_OPEN_FILES {
+            let least_recent = self
+                .open
+                .iter()
+                .min_by_key(|(_, file)| file.last_used)
+                .map(|(name, _)| name.clone());
+            if let Some(least_recent) = least_recent {
+                self.close(&least_recent)?;
+            }
+        }
+
+        let path = self.dir.join(format!("{}-{}.pgn", self.prefix, name));
+        let file = if self.games.contains_key(name) {
+            OpenOptions::new().append(true).open(path)?
+        } else {
+            File::create(path)?
+        };
+        let file = OpenFile {
+            writer: BufWriter::new(file),
+            last_used: 0,
+        };
+        self.open.insert(name.to_string(), file);
+        Ok(())
+    }
+
+    fn write(&mut self, name: &str, text: &str) -> Result<()> {
+        if !self.open.contains_key(name) {
+            self.open(name)?;
+        }
+
+        self.writes += 1;
+        // unwrap: the file was opened above.
+        let file = self.open.get_mut(name).unwrap();
+        file.last_used = self.writes;
+        let games = self.games.entry(name.to_string()).or_insert(0);
+        if *games > 0 {
+            writeln!(file.writer)?;
+        }
+        write!(file.writer, "{}", text)?;
+        *games += 1;
+        Ok(())
+    }
+
+    fn close(&mut self, name: &str) -> Result<()> {
+        if let Some(mut file) = self.open.remove(name) {
+            file.writer.flush()?;
+        }
+        Ok(())
+    }
+
+    fn close_all(&mut self) -> Result<()> {
+        for (_, mut file) in self.open.drain() {
+            file.writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+// For --count and --size: the number of the current file and what has been written to it.
+struct NumberedFiles {
+    number: usize,
+    games: usize,
+    bytes: u64,
+}
+
+impl NumberedFiles {
+    fn name(&self) -> String {
+        format!("{:04}", self.number)
+    }
+
+    // Returns the name of the file the game goes in, starting a new file if the current one is
+    // full.
+    fn next(&mut self, full: bool, text: &str, outputs: &mut Outputs) -> Result<String> {
+        if full {
+            outputs.close(&self.name())?;
+            self.number += 1;
+            self.games = 0;
+            self.bytes = 0;
+        }
+        if self.games > 0 {
+            self.bytes += 1;
+        }
+        self.games += 1;
+        self.bytes += text.len() as u64;
+        Ok(self.name())
+    }
+}
+
+pub fn run(args: SplitArgs) -> Result<()> {
+    let mode = args.mode()?;
+    let mut outputs = Outputs {
+        dir: Path::new(&args.dir).to_path_buf(),
+        prefix: args.prefix.clone(),
+        games: HashMap::new(),
+        open: HashMap::new(),
+        writes: 0,
+    };
+
+    let mut tag_names = TagFileNames::default();
+    let mut numbered = NumberedFiles {
+        number: 1,
+        games: 0,
+        bytes: 0,
+    };
+
+    for file in &args.pgn_files {
+        for (index, game) in open_reader(file)?.enumerate() {
+            let game = match game {
+                Ok(game) => game,
+                Err(err) => {
+                    eprintln!("{}: skipping game {}: {}", file, index + 1, err);
+                    continue;
+                }
+            };
+            let text = game.to_string();
+
+            let names = match &mode {
+                Mode::Count(count) => {
+                    let full = numbered.games >= *count;
+                    vec![numbered.next(full, &text, &mut outputs)?]
+                }
+                Mode::Size(size) => {
+                    // A game bigger than the limit still gets a file to itself.
+                    let full = numbered.games > 0 && numbered.bytes + 1 + text.len() as u64 > *size;
+                    vec![numbered.next(full, &text, &mut outputs)?]
+                }
+                Mode::Tag(tag) => vec![tag_names.name(game.tag(tag).unwrap_or(""))],
+                Mode::Player => vec![
+                    tag_names.name(game.tag("White").unwrap_or("")),
+                    tag_names.name(game.tag("Black").unwrap_or("")),
+                ],
+                Mode::Year => vec![year(&game)],
+            };
+
+            let mut written: Vec<&String> = vec![];
+            for name in &names {
+                // A player's game against themself goes in their file once.
+                if !written.contains(&name) {
+                    outputs.write(name, &text)?;
+                    written.push(name);
+                }
+            }
+        }
+    }
+
+    outputs.close_all()
+}
@@ -0,0 +1,71 @@
+use crate::commands::{create_output, open_reader};
+use crate::Result;
+use argh::FromArgs;
+use pgntool::strip::{strip_game, StripOptions};
+use std::io::Write;
+
+#[derive(FromArgs)]
+/// Remove variations, annotations or tags from games, e.g. for handouts.
+#[argh(subcommand, name = "strip")]
+pub struct StripArgs {
+    /// remove all variations
+    #[argh(switch)]
+    variations: bool,
+
+    /// keep variations only to this depth: 1 keeps variations but not the variations inside them
+    #[argh(option)]
+    depth: Option<usize>,
+
+    /// remove comments
+    #[argh(switch)]
+    comments: bool,
+
+    /// remove NAGs
+    #[argh(switch)]
+    nags: bool,
+
+    /// remove commands such as [%clk 0:03:00] from comments, keeping their text
+    #[argh(switch)]
+    commands: bool,
+
+    /// remove tags other than the Seven Tag Roster, FEN, SetUp and Variant
+    #[argh(switch)]
+    tags: bool,
+
+    /// the file to write the games to (default is stdout)
+    #[argh(option, short = 'o')]
+    output: Option<String>,
+
+    /// PGN files to read games from
+    #[argh(positional)]
+    pgn_files: Vec<String>,
+}
+
+pub fn run(args: StripArgs) -> Result<()> {
+    let mut writer = create_output(args.output.as_deref())?;
+    let options = StripOptions {
+        variation_depth: if args.variations { Some(0) } else { args.depth },
+        comments: args.comments,
+        nags: args.nags,
+        commands: args.commands,
+        tags: args.tags,
+    };
+
+    let mut first = true;
+    for file in &args.pgn_files {
+        for (index, game) in open_reader(file)?.enumerate() {
+            match game {
+                Ok(mut game) => {
+                    strip_game(&mut game, &options);
+                    if !first {
+                        writeln!(writer)?;
+                    }
+                    write!(writer, "{}", game)?;
+                    first = false;
+                }
+                Err(err) => eprintln!("{}: skipping game {}: {}", file, index + 1, err),
+            }
+        }
+    }
+    writer.finish()
+}
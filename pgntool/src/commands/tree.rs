@@ -0,0 +1,100 @@
+use crate::commands::convert::parse_letters;
+use crate::commands::read_database_with;
+use crate::Result;
+use argh::FromArgs;
+use pgntool::opening_tree::{MoveStats, OpeningTree};
+use pgntool::position::{Position, STARTING_FEN};
+use pgntool::{ParseOptions, PieceLetters};
+
+#[derive(FromArgs)]
+/// Show the moves played from a position, like an opening explorer.
+#[argh(subcommand, name = "tree")]
+pub struct TreeArgs {
+    /// the number of plies from the start of each game to include (default 12)
+    #[argh(option, default = "12")]
+    depth: usize,
+
+    /// the position to show (default is the starting position)
+    #[argh(option, default = "STARTING_FEN.to_string()")]
+    fen: String,
+
+    /// the piece letters the games are written with, and to show the moves with: en, de, es,
+    /// or six letters for K Q R B N P (default en)
+    #[argh(option, default = "PieceLetters::ENGLISH", from_str_fn(parse_letters))]
+    lang: PieceLetters,
+
+    /// show the moves in figurine notation
+    #[argh(switch)]
+    figurine: bool,
+
+    /// PGN files to read games from
+    #[argh(positional)]
+    pgn_files: Vec<String>,
+}
+
+fn format_elo(stats: &MoveStats) -> String {
+    stats
+        .average_elo()
+        .map(|elo| elo.to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn print_row(label: &str, stats: &MoveStats, total_games: u64) {
+    let share = if total_games > 0 {
+        100.0 * stats.games as f64 / total_games as f64
+    } else {
+        0.0
+    };
+    println!(
+        "{:<8} {:>7} {:>5.1}%  {:>5.1}% {:>5.1}% {:>5.1}%  {:>7}",
+        label,
+        stats.games,
+        share,
+        stats.white_percent(),
+        stats.draw_percent(),
+        stats.black_percent(),
+        format_elo(stats)
+    );
+}
+
+pub fn run(args: TreeArgs) -> Result<()> {
+    let position: Position = args.fen.parse()?;
+
+    let options = ParseOptions {
+        letters: args.lang,
+        ..ParseOptions::default()
+    };
+    let letters = if args.figurine {
+        PieceLetters::FIGURINE
+    } else {
+        args.lang
+    };
+
+    let mut tree = OpeningTree::new(args.depth);
+    for file in &args.pgn_files {
+        let database = read_database_with(file, options)?;
+        for (index, game) in database.games().iter().enumerate() {
+            if let Err(err) = tree.add_game(game) {
+                eprintln!("{}: skipping game {}: {}", file, index + 1, err);
+            }
+        }
+    }
+
+    let moves = tree.moves(&position);
+    if moves.is_empty() {
+        println!("No games reach this position.");
+        return Ok(());
+    }
+
+    let total = tree.total(&position);
+    println!(
+        "{:<8} {:>7} {:>6}  {:>6} {:>6} {:>6}  {:>7}",
+        "Move", "Games", "", "White", "Draw", "Black", "Avg Elo"
+    );
+    for tree_move in moves {
+        let san = letters.localize(&tree_move.san).to_string();
+        print_row(&san, &tree_move.stats, total.games);
+    }
+    print_row("Total", &total, total.games);
+    Ok(())
+}
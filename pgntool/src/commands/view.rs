@@ -0,0 +1,92 @@
+use crate::commands::read_database;
+use crate::Result;
+use argh::FromArgs;
+use pgntool::viewer::{Key, Viewer};
+use std::fs::File;
+use std::io::{stdin, stdout, Read, Write};
+use std::process::{Command, Stdio};
+
+#[derive(FromArgs)]
+/// Step through the games of a file in the terminal.
+#[argh(subcommand, name = "view")]
+pub struct ViewArgs {
+    /// draw the pieces with chess glyphs rather than letters
+    #[argh(switch)]
+    unicode: bool,
+
+    /// press these keys and print the screen, rather than reading keys from the terminal
+    #[argh(option)]
+    keys: Option<String>,
+
+    /// the PGN file to view
+    #[argh(positional)]
+    pgn_file: String,
+}
+
+// Puts the terminal into raw mode, so keys are read as they are pressed and aren't echoed, and
+// restores the previous settings when dropped.
+struct RawTerminal {
+    saved: String,
+}
+
+fn stty(args: &[&str]) -> Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(File::open("/dev/tty")?)
+        .stderr(Stdio::inherit())
+        .output()?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+impl RawTerminal {
+    fn new() -> Result<Self> {
+        let saved = stty(&["-g"])?;
+        stty(&["-icanon", "-echo", "min", "1"])?;
+        Ok(RawTerminal { saved })
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        // Nothing more can be done if restoring fails.
+        let _ = stty(&[self.saved.as_str()]);
+    }
+}
+
+fn draw(viewer: &Viewer) -> Result<()> {
+    // Clear the screen and move to the top left corner.
+    let mut out = stdout();
+    write!(out, "\x1b[2J\x1b[H{}", viewer.screen())?;
+    out.flush()?;
+    Ok(())
+}
+
+pub fn run(args: ViewArgs) -> Result<()> {
+    let database = read_database(&args.pgn_file)?;
+    let mut viewer = Viewer::new(database).unicode(args.unicode);
+
+    if let Some(keys) = &args.keys {
+        for key in Key::decode(keys.as_bytes()) {
+            viewer.press(key);
+        }
+        println!("{}", viewer.screen());
+        return Ok(());
+    }
+
+    let _terminal = RawTerminal::new()?;
+    let mut input = stdin();
+    let mut buffer = [0; 16];
+    draw(&viewer)?;
+    while !viewer.is_done() {
+        let read = input.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        for key in Key::decode(&buffer[..read]) {
+            viewer.press(key);
+        }
+        draw(&viewer)?;
+    }
+    println!();
+    Ok(())
+}
@@ -0,0 +1,277 @@
+use crate::{PgnError, Result};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+// The compression formats PGN databases are distributed in. Each one is only supported if
+// pgntool is built with its cargo feature: gzip, bzip2 or zstd.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Compression {
+    Gzip,
+    Bzip2,
+    Zstd,
+}
+
+impl Compression {
+    pub fn name(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Bzip2 => "bzip2",
+            Compression::Zstd => "zstd",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+            Compression::Bzip2 => "bz2",
+            Compression::Zstd => "zst",
+        }
+    }
+
+    // Whether pgntool was built with support for the format.
+    pub fn is_supported(self) -> bool {
+        match self {
+            Compression::Gzip => cfg!(feature = "gzip"),
+            Compression::Bzip2 => cfg!(feature = "bzip2"),
+            Compression::Zstd => cfg!(feature = "zstd"),
+        }
+    }
+
+    // Returns the compression the bytes at the start of a file are the magic number of.
+    pub fn from_magic(bytes: &[u8]) -> Option<Compression> {
+        if bytes.starts_with(&[0x1f, 0x8b]) {
+            Some(Compression::Gzip)
+        } else if bytes.starts_with(b"BZh") {
+            Some(Compression::Bzip2)
+        } else if bytes.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+
+    // Returns the compression the extension of the path is for, e.g. gzip for "games.pgn.gz".
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Compression> {
+        let extension = path.as_ref().extension()?.to_str()?;
+        [Compression::Gzip, Compression::Bzip2, Compression::Zstd]
+            .iter()
+            .copied()
+            .find(|compression| extension.eq_ignore_ascii_case(compression.extension()))
+    }
+
+    fn unsupported(self) -> PgnError {
+        PgnError::Compression(format!(
+            "{} files need pgntool built with the {} feature",
+            self.name(),
+            self.name()
+        ))
+    }
+}
+
+// Only the arms for the features built in are compiled, so the fallback arm is unreachable with
+// every feature, and it is the only arm with none.
+#[allow(unreachable_code, unreachable_patterns, unused_variables)]
+fn decoder(compression: Compression, reader: BufReader<File>) -> Result<Box<dyn BufRead>> {
+    Ok(match compression {
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => Box::new(BufReader::new(flate2::bufread::MultiGzDecoder::new(reader))),
+        #[cfg(feature = "bzip2")]
+        Compression::Bzip2 => Box::new(BufReader::new(bzip2::bufread::MultiBzDecoder::new(reader))),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Box::new(BufReader::new(zstd::Decoder::with_buffer(reader)?)),
+        _ => return Err(compression.unsupported()),
+    })
+}
+
+// The stream a created file is written through, compressed or not.
+enum Encoder {
+    Plain(File),
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::write::GzEncoder<File>),
+    #[cfg(feature = "bzip2")]
+    Bzip2(bzip2::write::BzEncoder<File>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::Encoder<'static, File>),
+}
+
+impl Encoder {
+    fn writer(&mut self) -> &mut dyn Write {
+        match self {
+            Encoder::Plain(file) => file,
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(encoder) => encoder,
+            #[cfg(feature = "bzip2")]
+            Encoder::Bzip2(encoder) => encoder,
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(encoder) => encoder,
+        }
+    }
+
+    // Writes the end of the compressed stream, so that errors writing it are reported rather than
+    // lost when the encoder is dropped.
+    fn finish(self) -> Result<()> {
+        match self {
+            Encoder::Plain(mut file) => file.flush()?,
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(encoder) => encoder.finish()?.flush()?,
+            #[cfg(feature = "bzip2")]
+            Encoder::Bzip2(encoder) => encoder.finish()?.flush()?,
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(encoder) => encoder.finish()?.flush()?,
+        }
+        Ok(())
+    }
+}
+
+impl Write for Encoder {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.writer().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer().flush()
+    }
+}
+
+#[allow(unreachable_code, unreachable_patterns, unused_variables)]
+fn encoder(compression: Compression, file: File) -> Result<Encoder> {
+    Ok(match compression {
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => Encoder::Gzip(flate2::write::GzEncoder::new(
+            file,
+            flate2::Compression::default(),
+        )),
+        #[cfg(feature = "bzip2")]
+        Compression::Bzip2 => Encoder::Bzip2(bzip2::write::BzEncoder::new(
+            file,
+            bzip2::Compression::default(),
+        )),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Encoder::Zstd(zstd::Encoder::new(file, 0)?),
+        _ => return Err(compression.unsupported()),
+    })
+}
+
+// A file opened by create(). finish() must be called once everything is written: a compressed
+// file is incomplete until then.
+pub struct OutputFile {
+    writer: BufWriter<Encoder>,
+}
+
+impl OutputFile {
+    pub fn finish(self) -> Result<()> {
+        let encoder = self.writer.into_inner().map_err(|err| err.into_error())?;
+        encoder.finish()
+    }
+}
+
+impl Write for OutputFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.writer.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+// Returns the compression of a file, from its magic number, or from its extension if it is too
+// short to have one.
+pub fn detect(path: impl AsRef<Path>) -> Result<Option<Compression>> {
+    let mut reader = BufReader::new(File::open(path.as_ref())?);
+    detect_in(&mut reader, path)
+}
+
+fn detect_in(reader: &mut BufReader<File>, path: impl AsRef<Path>) -> Result<Option<Compression>> {
+    let start = reader.fill_buf()?;
+    if start.len() < 4 {
+        return Ok(Compression::from_magic(start).or_else(|| Compression::from_path(path)));
+    }
+    Ok(Compression::from_magic(start))
+}
+
+// Opens a file for reading, decompressing it if it is compressed.
+pub fn open(path: impl AsRef<Path>) -> Result<Box<dyn BufRead>> {
+    let mut reader = BufReader::new(File::open(path.as_ref())?);
+    match detect_in(&mut reader, path)? {
+        Some(compression) => decoder(compression, reader),
+        None => Ok(Box::new(reader)),
+    }
+}
+
+// Creates a file for writing, compressing what is written if its extension is for a compression
+// format.
+pub fn create(path: impl AsRef<Path>) -> Result<OutputFile> {
+    let compression = Compression::from_path(path.as_ref());
+    if let Some(compression) = compression.filter(|compression| !compression.is_supported()) {
+        // Checked first, so that an empty file isn't left behind.
+        return Err(compression.unsupported());
+    }
+    let file = File::create(path)?;
+    let encoder = match compression {
+        Some(compression) => encoder(compression, file)?,
+        None => Encoder::Plain(file),
+    };
+    Ok(OutputFile {
+        writer: BufWriter::new(encoder),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            Some(Compression::Gzip),
+            Compression::from_magic(&[0x1f, 0x8b, 8, 0])
+        );
+        assert_eq!(
+            Some(Compression::Bzip2),
+            Compression::from_magic(b"BZh91AY")
+        );
+        assert_eq!(
+            Some(Compression::Zstd),
+            Compression::from_magic(&[0x28, 0xb5, 0x2f, 0xfd, 0])
+        );
+        assert_eq!(None, Compression::from_magic(b"[Event \"?\"]"));
+
+        assert_eq!(
+            Some(Compression::Zstd),
+            Compression::from_path("lichess_db.pgn.zst")
+        );
+        assert_eq!(
+            Some(Compression::Gzip),
+            Compression::from_path("old.PGN.GZ")
+        );
+        assert_eq!(None, Compression::from_path("games.pgn"));
+        assert_eq!(None, Compression::from_path("gz"));
+    }
+
+    // Writes compressed files for each format built in, and reads them back.
+    #[test]
+    fn test_round_trip() {
+        let pgn = "[Event \"One\"]\n\n1. e4 e5 *\n";
+        let dir = std::env::temp_dir().join(format!("pgntool-compression-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for compression in &[Compression::Gzip, Compression::Bzip2, Compression::Zstd] {
+            let path = dir.join(format!("games.pgn.{}", compression.extension()));
+            if !compression.is_supported() {
+                assert!(create(&path).is_err());
+                continue;
+            }
+            let mut writer = create(&path).unwrap();
+            writer.write_all(pgn.as_bytes()).unwrap();
+            writer.finish().unwrap();
+            assert_eq!(Some(*compression), detect(&path).unwrap());
+
+            let mut text = String::new();
+            open(&path).unwrap().read_to_string(&mut text).unwrap();
+            assert_eq!(pgn, text);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
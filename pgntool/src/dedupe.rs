@@ -0,0 +1,172 @@
+use crate::pgn_parser::PgnGame;
+use crate::replay::Mainline;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+// Identifies a game for duplicate detection: its moves, plus the values of any key tags.
+#[derive(Debug, Eq, PartialEq, Hash, Clone)]
+pub struct GameKey {
+    moves_hash: u64,
+    tags: Vec<String>,
+}
+
+// Tag values differ in spacing, case and punctuation between sources, e.g. "Carlsen, Magnus" and
+// "carlsen,magnus", so only letters and digits are compared.
+fn normalize_tag(value: &str) -> String {
+    value
+        .chars()
+        .filter(|ch| ch.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+// Hashes the mainline moves as resolved against the position, so that differences in notation
+// (e.g. "Nxe4" and "Ne4", or a missing "+") don't matter. Games which can't be replayed fall back
+// to hashing the moves as written.
+fn moves_hash(game: &PgnGame) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    match Mainline::replay(game) {
+        Ok(mainline) => {
+            // The FEN doesn't give the variant, and the same moves make a different game in one.
+            mainline.positions()[0].variant().hash(&mut hasher);
+            mainline.positions()[0].to_fen().hash(&mut hasher);
+            mainline.moves().hash(&mut hasher);
+        }
+        Err(_) => {
+            for san_move in game.movetext().element_sequence().moves() {
+                san_move.to_string().hash(&mut hasher);
+            }
+        }
+    }
+    hasher.finish()
+}
+
+pub fn game_key(game: &PgnGame, key_tags: &[String]) -> GameKey {
+    GameKey {
+        moves_hash: moves_hash(game),
+        tags: key_tags
+            .iter()
+            .map(|tag| game.tag(tag).map(normalize_tag).unwrap_or_default())
+            .collect(),
+    }
+}
+
+// Copies with more tags are preferred, then copies with comments.
+fn quality(game: &PgnGame) -> (usize, bool) {
+    (
+        game.tags().pairs().len(),
+        game.movetext().element_sequence().has_comments(),
+    )
+}
+
+// A set of copies of the same game, as indexes into the games searched.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct DuplicateGroup {
+    pub kept: usize,
+    pub dropped: Vec<usize>,
+}
+
+// Groups the games by key, choosing the best copy in each group to keep. Groups are returned in
+// the order their first copy appears, including games with no duplicates.
+pub fn find_duplicates<'a>(
+    games: impl IntoIterator<Item = &'a PgnGame>,
+    key_tags: &[String],
+) -> Vec<DuplicateGroup> {
+    let mut group_indexes: HashMap<GameKey, usize> = HashMap::new();
+    let mut groups: Vec<(Vec<usize>, (usize, bool))> = vec![];
+
+    for (index, game) in games.into_iter().enumerate() {
+        let key = game_key(game, key_tags);
+        let game_quality = quality(game);
+        match group_indexes.get(&key).copied() {
+            Some(group) => {
+                let (members, best) = &mut groups[group];
+                if game_quality > *best {
+                    // Keep the best copy at the front.
+                    members.insert(0, index);
+                    *best = game_quality;
+                } else {
+                    members.push(index);
+                }
+            }
+            None => {
+                group_indexes.insert(key, groups.len());
+                groups.push((vec![index], game_quality));
+            }
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(mut members, _)| {
+            let kept = members.remove(0);
+            members.sort_unstable();
+            DuplicateGroup {
+                kept,
+                dropped: members,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse_pgn;
+
+    #[test]
+    fn test_find_duplicates() {
+        let database = parse_pgn(
+            "[White \"Carlsen, Magnus\"]\n\n1. e4 e5 2. Nf3 *\n\n\
+             [White \"carlsen,magnus\"]\n[Date \"2021.01.01\"]\n\n1. e4 e5 2. Nf3 *\n\n\
+             [White \"Carlsen, Magnus\"]\n\n1. e4 e5 2. Nf3 {Solid.} *\n\n\
+             1. e4 e5 2. Nf3 *\n\n\
+             [White \"Carlsen, Magnus\"]\n\n1. d4 *",
+        )
+        .unwrap();
+
+        let groups = find_duplicates(database.games(), &["White".to_string()]);
+        assert_eq!(
+            vec![
+                DuplicateGroup {
+                    kept: 1,
+                    dropped: vec![0, 2]
+                },
+                DuplicateGroup {
+                    kept: 3,
+                    dropped: vec![]
+                },
+                DuplicateGroup {
+                    kept: 4,
+                    dropped: vec![]
+                },
+            ],
+            groups
+        );
+
+        // Without key tags only the moves count.
+        let groups = find_duplicates(database.games(), &[]);
+        assert_eq!(2, groups.len());
+        assert_eq!(vec![0, 2, 3], groups[0].dropped);
+    }
+
+    #[test]
+    fn test_quality() {
+        let database =
+            parse_pgn("[White \"A\"]\n\n1. e4 *\n\n[White \"A\"]\n\n1. e4 {Comment} *").unwrap();
+        let groups = find_duplicates(database.games(), &[]);
+        assert_eq!(1, groups[0].kept);
+    }
+
+    #[test]
+    fn test_notation_differences() {
+        let database = parse_pgn("1. e4 e5 2. Nf3 *\n\n1. e4 e5 2. Ngf3 *").unwrap();
+        let games = database.games();
+        assert_eq!(game_key(&games[0], &[]), game_key(&games[1], &[]));
+
+        let database = parse_pgn("1. e4 e5 *\n\n[Variant \"Crazyhouse\"]\n\n1. e4 e5 *").unwrap();
+        let games = database.games();
+        assert_ne!(game_key(&games[0], &[]), game_key(&games[1], &[]));
+    }
+}
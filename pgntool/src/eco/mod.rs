@@ -0,0 +1,183 @@
+use crate::parse_pgn;
+use crate::pgn_parser::PgnGame;
+use crate::polyglot::polyglot_key;
+use crate::replay::{require_standard_rules, Mainline};
+use crate::Result;
+use std::collections::HashMap;
+
+mod table;
+
+// An opening from the ECO (Encyclopaedia of Chess Openings) classification.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct EcoEntry {
+    pub code: &'static str,
+    pub opening: &'static str,
+    pub variation: Option<&'static str>,
+    // The moves leading to the opening, in SAN with move numbers, e.g. "1. e4 c5".
+    pub moves: &'static str,
+}
+
+// Classifies games by the deepest opening position they reach.
+//
+// Openings are keyed by position rather than by move order, so games which transpose into an
+// opening are classified the same way as games which reach it directly.
+#[derive(Debug)]
+pub struct EcoClassifier {
+    entries: Vec<EcoEntry>,
+    by_key: HashMap<u64, usize>,
+    max_plies: usize,
+}
+
+impl EcoClassifier {
+    // Creates a classifier from the built-in ECO table.
+    pub fn new() -> Self {
+        let entries = table::ECO_TABLE
+            .iter()
+            .map(|&(code, opening, variation, moves)| EcoEntry {
+                code,
+                opening,
+                variation: if variation.is_empty() {
+                    None
+                } else {
+                    Some(variation)
+                },
+                moves,
+            })
+            .collect();
+
+        // unwrap: every line of the built-in table is checked by test_table.
+        EcoClassifier::with_entries(entries).unwrap()
+    }
+
+    // Creates a classifier from a custom set of openings. Where two entries reach the same
+    // position, the earlier one wins.
+    pub fn with_entries(entries: Vec<EcoEntry>) -> Result<Self> {
+        let mut by_key = HashMap::new();
+        let mut max_plies = 0;
+
+        for (index, entry) in entries.iter().enumerate() {
+            let database = parse_pgn(format!("{} *", entry.moves))?;
+            let mainline = Mainline::replay(&database.games()[0])?;
+            max_plies = max_plies.max(mainline.moves().len());
+            by_key
+                .entry(polyglot_key(mainline.final_position()))
+                .or_insert(index);
+        }
+
+        Ok(EcoClassifier {
+            entries,
+            by_key,
+            max_plies,
+        })
+    }
+
+    pub fn entries(&self) -> &[EcoEntry] {
+        &self.entries
+    }
+
+    // Returns the opening of the deepest position in the mainline which has one. The openings are
+    // those of standard chess, so games of other variants have none.
+    pub fn classify_mainline(&self, mainline: &Mainline) -> Option<&EcoEntry> {
+        if !mainline.positions()[0].variant().has_standard_rules() {
+            return None;
+        }
+        mainline
+            .positions()
+            .iter()
+            .rev()
+            .find_map(|position| self.by_key.get(&polyglot_key(position)))
+            .map(|&index| &self.entries[index])
+    }
+
+    // Classifies a game. Only the opening moves are replayed, so errors later in the game do not
+    // matter. Games of variants other than Chess960 are rejected.
+    pub fn classify(&self, game: &PgnGame) -> Result<Option<&EcoEntry>> {
+        require_standard_rules(game)?;
+        let mainline = Mainline::replay_plies(game, self.max_plies)?;
+        Ok(self.classify_mainline(&mainline))
+    }
+}
+
+impl Default for EcoClassifier {
+    fn default() -> Self {
+        EcoClassifier::new()
+    }
+}
+
+// Sets the ECO, Opening and Variation tags of the game from the entry. A Variation tag is removed
+// if the entry has no variation, since it can only belong to a different opening.
+pub fn apply_entry(entry: &EcoEntry, game: &mut PgnGame) {
+    let tags = game.tags_mut();
+    tags.set("ECO", entry.code);
+    tags.set("Opening", entry.opening);
+    match entry.variation {
+        Some(variation) => tags.set("Variation", variation),
+        None => tags.remove("Variation"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn classify(moves: &str) -> Option<EcoEntry> {
+        let database = parse_pgn(moves).unwrap();
+        EcoClassifier::new()
+            .classify(&database.games()[0])
+            .unwrap()
+            .copied()
+    }
+
+    #[test]
+    fn test_table() {
+        // Every line must replay, or EcoClassifier::new() would panic.
+        let classifier = EcoClassifier::new();
+        assert_eq!(table::ECO_TABLE.len(), classifier.entries().len());
+
+        for entry in classifier.entries() {
+            assert_eq!(3, entry.code.len(), "{}", entry.code);
+            assert!(entry.code.starts_with(|ch| ('A'..='E').contains(&ch)));
+        }
+    }
+
+    #[test]
+    fn test_classify() {
+        let sicilian = classify("1. e4 c5 2. Nf3 d6 3. d4 cxd4 4. Nxd4 Nf6 5. Nc3 a6 *").unwrap();
+        assert_eq!("B90", sicilian.code);
+        assert_eq!("Sicilian Defense", sicilian.opening);
+        assert_eq!(Some("Najdorf Variation"), sicilian.variation);
+
+        // Moves past the end of the opening don't change the classification.
+        let french = classify("1. e4 e6 2. d4 d5 3. e5 c5 4. c3 Nc6 1-0").unwrap();
+        assert_eq!("C02", french.code);
+
+        // The Barnes Opening is not in the table.
+        assert_eq!(None, classify("1. f3 e5 *"));
+
+        let database = parse_pgn("[Variant \"Crazyhouse\"]\n\n1. e4 c5 *").unwrap();
+        assert!(EcoClassifier::new().classify(&database.games()[0]).is_err());
+    }
+
+    #[test]
+    fn test_transposition() {
+        // The Queen's Gambit Declined, reached from an English Opening move order.
+        let qgd = classify("1. c4 e6 2. Nc3 d5 3. d4 *").unwrap();
+        assert_eq!("D31", qgd.code);
+    }
+
+    #[test]
+    fn test_apply_entry() {
+        let mut database = parse_pgn("[Variation \"Wrong\"]\n\n1. e4 e5 *").unwrap();
+        let game = &mut database.games_mut()[0];
+        let entry = EcoClassifier::new()
+            .classify(game)
+            .unwrap()
+            .copied()
+            .unwrap();
+        apply_entry(&entry, game);
+
+        assert_eq!(Some("C20"), game.tag("ECO"));
+        assert_eq!(Some("King's Pawn Game"), game.tag("Opening"));
+        assert_eq!(None, game.tag("Variation"));
+    }
+}
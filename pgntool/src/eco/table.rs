@@ -0,0 +1,316 @@
+// The built-in ECO table: (code, opening, variation, moves). An empty variation means the entry
+// names the opening as a whole.
+//
+// Entries are matched by the position at the end of their moves, so the order of the moves only
+// needs to reach the right position. Where two entries reach the same position, the first one
+// wins.
+pub(crate) const ECO_TABLE: &[(&str, &str, &str, &str)] = &[
+    ("A00", "Polish Opening", "", "1. b4"),
+    ("A00", "Grob Opening", "", "1. g4"),
+    ("A00", "Van't Kruijs Opening", "", "1. e3"),
+    ("A00", "Mieses Opening", "", "1. d3"),
+    ("A00", "Saragossa Opening", "", "1. c3"),
+    ("A00", "Anderssen's Opening", "", "1. a3"),
+    ("A00", "Clemenz Opening", "", "1. h3"),
+    ("A00", "Ware Opening", "", "1. a4"),
+    ("A00", "Kadas Opening", "", "1. h4"),
+    ("A00", "Amar Opening", "", "1. Nh3"),
+    ("A00", "Durkin Opening", "", "1. Na3"),
+    ("A00", "Dunst Opening", "", "1. Nc3"),
+    ("A00", "Hungarian Opening", "", "1. g3"),
+    ("A01", "Nimzo-Larsen Attack", "", "1. b3"),
+    ("A02", "Bird's Opening", "", "1. f4"),
+    ("A02", "Bird's Opening", "From's Gambit", "1. f4 e5"),
+    ("A03", "Bird's Opening", "Dutch Variation", "1. f4 d5"),
+    ("A04", "Zukertort Opening", "", "1. Nf3"),
+    ("A04", "Zukertort Opening", "Sicilian Invitation", "1. Nf3 c5"),
+    ("A05", "Zukertort Opening", "Quiet System", "1. Nf3 Nf6"),
+    ("A06", "Zukertort Opening", "Queen's Gambit Invitation", "1. Nf3 d5"),
+    ("A07", "King's Indian Attack", "", "1. Nf3 d5 2. g3"),
+    ("A09", "Réti Opening", "", "1. Nf3 d5 2. c4"),
+    ("A09", "Réti Opening", "Réti Accepted", "1. Nf3 d5 2. c4 dxc4"),
+    ("A10", "English Opening", "", "1. c4"),
+    ("A13", "English Opening", "Agincourt Defense", "1. c4 e6"),
+    ("A15", "English Opening", "Anglo-Indian Defense", "1. c4 Nf6"),
+    ("A16", "English Opening", "Anglo-Indian Defense, Queen's Knight Variation", "1. c4 Nf6 2. Nc3"),
+    ("A20", "English Opening", "King's English Variation", "1. c4 e5"),
+    ("A21", "English Opening", "King's English Variation, Reversed Sicilian", "1. c4 e5 2. Nc3"),
+    ("A22", "English Opening", "King's English Variation, Two Knights Variation", "1. c4 e5 2. Nc3 Nf6"),
+    ("A25", "English Opening", "King's English Variation, Closed System", "1. c4 e5 2. Nc3 Nc6 3. g3"),
+    ("A30", "English Opening", "Symmetrical Variation", "1. c4 c5"),
+    ("A40", "Queen's Pawn Game", "", "1. d4"),
+    ("A40", "Englund Gambit", "", "1. d4 e5"),
+    ("A40", "Horwitz Defense", "", "1. d4 e6"),
+    ("A40", "Modern Defense", "", "1. d4 g6"),
+    ("A41", "Queen's Pawn Game", "Wade Defense", "1. d4 d6"),
+    ("A43", "Benoni Defense", "Old Benoni", "1. d4 c5"),
+    ("A45", "Indian Defense", "", "1. d4 Nf6"),
+    ("A45", "Trompowsky Attack", "", "1. d4 Nf6 2. Bg5"),
+    ("A46", "Indian Defense", "Knights Variation", "1. d4 Nf6 2. Nf3"),
+    ("A46", "Torre Attack", "", "1. d4 Nf6 2. Nf3 e6 3. Bg5"),
+    ("A48", "Indian Defense", "East Indian Defense", "1. d4 Nf6 2. Nf3 g6"),
+    ("A48", "London System", "", "1. d4 Nf6 2. Nf3 g6 3. Bf4"),
+    ("A50", "Indian Defense", "Normal Variation", "1. d4 Nf6 2. c4"),
+    ("A51", "Budapest Defense", "", "1. d4 Nf6 2. c4 e5"),
+    ("A52", "Budapest Defense", "Adler Variation", "1. d4 Nf6 2. c4 e5 3. dxe5 Ng4 4. Nf3"),
+    ("A53", "Old Indian Defense", "", "1. d4 Nf6 2. c4 d6"),
+    ("A56", "Benoni Defense", "", "1. d4 Nf6 2. c4 c5"),
+    ("A57", "Benko Gambit", "", "1. d4 Nf6 2. c4 c5 3. d5 b5"),
+    ("A58", "Benko Gambit", "Accepted", "1. d4 Nf6 2. c4 c5 3. d5 b5 4. cxb5 a6 5. bxa6"),
+    ("A60", "Benoni Defense", "Modern Variation", "1. d4 Nf6 2. c4 c5 3. d5 e6"),
+    ("A80", "Dutch Defense", "", "1. d4 f5"),
+    ("A81", "Dutch Defense", "Fianchetto Attack", "1. d4 f5 2. g3"),
+    ("A83", "Dutch Defense", "Staunton Gambit", "1. d4 f5 2. e4"),
+    ("A84", "Dutch Defense", "Normal Variation", "1. d4 f5 2. c4"),
+    ("A87", "Dutch Defense", "Leningrad Variation", "1. d4 f5 2. c4 Nf6 3. g3 g6 4. Bg2 Bg7 5. Nf3"),
+    ("A90", "Dutch Defense", "Stonewall Variation", "1. d4 f5 2. c4 Nf6 3. g3 e6 4. Bg2 d5"),
+    ("B00", "King's Pawn Game", "", "1. e4"),
+    ("B00", "Nimzowitsch Defense", "", "1. e4 Nc6"),
+    ("B00", "Owen Defense", "", "1. e4 b6"),
+    ("B00", "St. George Defense", "", "1. e4 a6"),
+    ("B01", "Scandinavian Defense", "", "1. e4 d5"),
+    ("B01", "Scandinavian Defense", "Modern Variation", "1. e4 d5 2. exd5 Nf6"),
+    ("B01", "Scandinavian Defense", "Mieses-Kotroc Variation", "1. e4 d5 2. exd5 Qxd5"),
+    ("B01", "Scandinavian Defense", "Main Line", "1. e4 d5 2. exd5 Qxd5 3. Nc3 Qa5"),
+    ("B01", "Scandinavian Defense", "Valencian Variation", "1. e4 d5 2. exd5 Qxd5 3. Nc3 Qd8"),
+    ("B02", "Alekhine Defense", "", "1. e4 Nf6"),
+    ("B03", "Alekhine Defense", "", "1. e4 Nf6 2. e5 Nd5 3. d4"),
+    ("B03", "Alekhine Defense", "Four Pawns Attack", "1. e4 Nf6 2. e5 Nd5 3. d4 d6 4. c4 Nb6 5. f4"),
+    ("B04", "Alekhine Defense", "Modern Variation", "1. e4 Nf6 2. e5 Nd5 3. d4 d6 4. Nf3"),
+    ("B06", "Modern Defense", "", "1. e4 g6"),
+    ("B06", "Modern Defense", "Standard Line", "1. e4 g6 2. d4 Bg7 3. Nc3"),
+    ("B07", "Pirc Defense", "", "1. e4 d6 2. d4 Nf6 3. Nc3 g6"),
+    ("B08", "Pirc Defense", "Classical Variation", "1. e4 d6 2. d4 Nf6 3. Nc3 g6 4. Nf3"),
+    ("B09", "Pirc Defense", "Austrian Attack", "1. e4 d6 2. d4 Nf6 3. Nc3 g6 4. f4"),
+    ("B10", "Caro-Kann Defense", "", "1. e4 c6"),
+    ("B10", "Caro-Kann Defense", "Two Knights Attack", "1. e4 c6 2. Nc3 d5 3. Nf3"),
+    ("B12", "Caro-Kann Defense", "Advance Variation", "1. e4 c6 2. d4 d5 3. e5"),
+    ("B12", "Caro-Kann Defense", "Advance Variation, Short Variation", "1. e4 c6 2. d4 d5 3. e5 Bf5 4. Nf3 e6 5. Be2"),
+    ("B13", "Caro-Kann Defense", "Exchange Variation", "1. e4 c6 2. d4 d5 3. exd5 cxd5"),
+    ("B13", "Caro-Kann Defense", "Panov Attack", "1. e4 c6 2. d4 d5 3. exd5 cxd5 4. c4"),
+    ("B15", "Caro-Kann Defense", "", "1. e4 c6 2. d4 d5 3. Nc3"),
+    ("B15", "Caro-Kann Defense", "Main Line", "1. e4 c6 2. d4 d5 3. Nc3 dxe4 4. Nxe4"),
+    ("B15", "Caro-Kann Defense", "Forgacs Variation", "1. e4 c6 2. d4 d5 3. Nc3 dxe4 4. Nxe4 Nf6"),
+    ("B15", "Caro-Kann Defense", "Tartakower Variation", "1. e4 c6 2. d4 d5 3. Nc3 dxe4 4. Nxe4 Nf6 5. Nxf6+ exf6"),
+    ("B16", "Caro-Kann Defense", "Bronstein-Larsen Variation", "1. e4 c6 2. d4 d5 3. Nc3 dxe4 4. Nxe4 Nf6 5. Nxf6+ gxf6"),
+    ("B17", "Caro-Kann Defense", "Karpov Variation", "1. e4 c6 2. d4 d5 3. Nc3 dxe4 4. Nxe4 Nd7"),
+    ("B17", "Caro-Kann Defense", "Karpov Variation, Modern Main Line", "1. e4 c6 2. d4 d5 3. Nc3 dxe4 4. Nxe4 Nd7 5. Ng5 Ngf6 6. Bd3 e6 7. N1f3"),
+    ("B17", "Caro-Kann Defense", "Karpov Variation, Tiviakov-Fischer Attack", "1. e4 c6 2. d4 d5 3. Nc3 dxe4 4. Nxe4 Nd7 5. Bc4"),
+    ("B18", "Caro-Kann Defense", "Classical Variation", "1. e4 c6 2. d4 d5 3. Nc3 dxe4 4. Nxe4 Bf5"),
+    ("B19", "Caro-Kann Defense", "Classical Variation, Spassky Variation", "1. e4 c6 2. d4 d5 3. Nc3 dxe4 4. Nxe4 Bf5 5. Ng3 Bg6 6. h4 h6 7. Nf3 Nd7 8. h5"),
+    ("B20", "Sicilian Defense", "", "1. e4 c5"),
+    ("B20", "Sicilian Defense", "Wing Gambit", "1. e4 c5 2. b4"),
+    ("B21", "Sicilian Defense", "Smith-Morra Gambit", "1. e4 c5 2. d4 cxd4 3. c3"),
+    ("B21", "Sicilian Defense", "Grand Prix Attack", "1. e4 c5 2. f4"),
+    ("B22", "Sicilian Defense", "Alapin Variation", "1. e4 c5 2. c3"),
+    ("B23", "Sicilian Defense", "Closed", "1. e4 c5 2. Nc3"),
+    ("B23", "Sicilian Defense", "Closed, Traditional", "1. e4 c5 2. Nc3 Nc6"),
+    ("B24", "Sicilian Defense", "Closed", "1. e4 c5 2. Nc3 Nc6 3. g3"),
+    ("B27", "Sicilian Defense", "", "1. e4 c5 2. Nf3"),
+    ("B27", "Sicilian Defense", "Hyperaccelerated Dragon", "1. e4 c5 2. Nf3 g6"),
+    ("B28", "Sicilian Defense", "O'Kelly Variation", "1. e4 c5 2. Nf3 a6"),
+    ("B29", "Sicilian Defense", "Nimzowitsch Variation", "1. e4 c5 2. Nf3 Nf6"),
+    ("B30", "Sicilian Defense", "Old Sicilian", "1. e4 c5 2. Nf3 Nc6"),
+    ("B30", "Sicilian Defense", "Nyezhmetdinov-Rossolimo Attack", "1. e4 c5 2. Nf3 Nc6 3. Bb5"),
+    ("B32", "Sicilian Defense", "Open", "1. e4 c5 2. Nf3 Nc6 3. d4 cxd4 4. Nxd4"),
+    ("B32", "Sicilian Defense", "Löwenthal Variation", "1. e4 c5 2. Nf3 Nc6 3. d4 cxd4 4. Nxd4 e5"),
+    ("B33", "Sicilian Defense", "Open", "1. e4 c5 2. Nf3 Nc6 3. d4 cxd4 4. Nxd4 Nf6"),
+    ("B33", "Sicilian Defense", "Lasker-Pelikan Variation", "1. e4 c5 2. Nf3 Nc6 3. d4 cxd4 4. Nxd4 Nf6 5. Nc3 e5"),
+    ("B33", "Sicilian Defense", "Sveshnikov Variation", "1. e4 c5 2. Nf3 Nc6 3. d4 cxd4 4. Nxd4 Nf6 5. Nc3 e5 6. Ndb5 d6 7. Bg5 a6 8. Na3 b5"),
+    ("B34", "Sicilian Defense", "Accelerated Dragon", "1. e4 c5 2. Nf3 Nc6 3. d4 cxd4 4. Nxd4 g6"),
+    ("B36", "Sicilian Defense", "Accelerated Dragon, Maróczy Bind", "1. e4 c5 2. Nf3 Nc6 3. d4 cxd4 4. Nxd4 g6 5. c4"),
+    ("B40", "Sicilian Defense", "French Variation", "1. e4 c5 2. Nf3 e6"),
+    ("B41", "Sicilian Defense", "Kan Variation", "1. e4 c5 2. Nf3 e6 3. d4 cxd4 4. Nxd4 a6"),
+    ("B44", "Sicilian Defense", "Taimanov Variation", "1. e4 c5 2. Nf3 e6 3. d4 cxd4 4. Nxd4 Nc6"),
+    ("B45", "Sicilian Defense", "Four Knights Variation", "1. e4 c5 2. Nf3 e6 3. d4 cxd4 4. Nxd4 Nf6 5. Nc3 Nc6"),
+    ("B50", "Sicilian Defense", "Modern Variations", "1. e4 c5 2. Nf3 d6"),
+    ("B51", "Sicilian Defense", "Moscow Variation", "1. e4 c5 2. Nf3 d6 3. Bb5+"),
+    ("B53", "Sicilian Defense", "Chekhover Variation", "1. e4 c5 2. Nf3 d6 3. d4 cxd4 4. Qxd4"),
+    ("B54", "Sicilian Defense", "Modern Variations", "1. e4 c5 2. Nf3 d6 3. d4 cxd4 4. Nxd4"),
+    ("B55", "Sicilian Defense", "Prins Variation", "1. e4 c5 2. Nf3 d6 3. d4 cxd4 4. Nxd4 Nf6 5. f3"),
+    ("B56", "Sicilian Defense", "Open", "1. e4 c5 2. Nf3 d6 3. d4 cxd4 4. Nxd4 Nf6 5. Nc3"),
+    ("B56", "Sicilian Defense", "Classical Variation", "1. e4 c5 2. Nf3 d6 3. d4 cxd4 4. Nxd4 Nf6 5. Nc3 Nc6"),
+    ("B57", "Sicilian Defense", "Sozin Attack", "1. e4 c5 2. Nf3 d6 3. d4 cxd4 4. Nxd4 Nf6 5. Nc3 Nc6 6. Bc4"),
+    ("B60", "Sicilian Defense", "Richter-Rauzer Variation", "1. e4 c5 2. Nf3 d6 3. d4 cxd4 4. Nxd4 Nf6 5. Nc3 Nc6 6. Bg5"),
+    ("B70", "Sicilian Defense", "Dragon Variation", "1. e4 c5 2. Nf3 d6 3. d4 cxd4 4. Nxd4 Nf6 5. Nc3 g6"),
+    ("B72", "Sicilian Defense", "Dragon Variation, Classical Variation", "1. e4 c5 2. Nf3 d6 3. d4 cxd4 4. Nxd4 Nf6 5. Nc3 g6 6. Be3"),
+    ("B76", "Sicilian Defense", "Dragon Variation, Yugoslav Attack", "1. e4 c5 2. Nf3 d6 3. d4 cxd4 4. Nxd4 Nf6 5. Nc3 g6 6. Be3 Bg7 7. f3 O-O 8. Qd2 Nc6"),
+    ("B80", "Sicilian Defense", "Scheveningen Variation", "1. e4 c5 2. Nf3 d6 3. d4 cxd4 4. Nxd4 Nf6 5. Nc3 e6"),
+    ("B81", "Sicilian Defense", "Scheveningen Variation, Keres Attack", "1. e4 c5 2. Nf3 d6 3. d4 cxd4 4. Nxd4 Nf6 5. Nc3 e6 6. g4"),
+    ("B90", "Sicilian Defense", "Najdorf Variation", "1. e4 c5 2. Nf3 d6 3. d4 cxd4 4. Nxd4 Nf6 5. Nc3 a6"),
+    ("B90", "Sicilian Defense", "Najdorf Variation, English Attack", "1. e4 c5 2. Nf3 d6 3. d4 cxd4 4. Nxd4 Nf6 5. Nc3 a6 6. Be3"),
+    ("B92", "Sicilian Defense", "Najdorf Variation, Opocensky Variation", "1. e4 c5 2. Nf3 d6 3. d4 cxd4 4. Nxd4 Nf6 5. Nc3 a6 6. Be2"),
+    ("B94", "Sicilian Defense", "Najdorf Variation", "1. e4 c5 2. Nf3 d6 3. d4 cxd4 4. Nxd4 Nf6 5. Nc3 a6 6. Bg5"),
+    ("B97", "Sicilian Defense", "Najdorf Variation, Poisoned Pawn Variation", "1. e4 c5 2. Nf3 d6 3. d4 cxd4 4. Nxd4 Nf6 5. Nc3 a6 6. Bg5 e6 7. f4 Qb6"),
+    ("C00", "French Defense", "", "1. e4 e6"),
+    ("C00", "French Defense", "Normal Variation", "1. e4 e6 2. d4"),
+    ("C00", "French Defense", "King's Indian Attack", "1. e4 e6 2. d3"),
+    ("C01", "French Defense", "Exchange Variation", "1. e4 e6 2. d4 d5 3. exd5 exd5"),
+    ("C02", "French Defense", "Advance Variation", "1. e4 e6 2. d4 d5 3. e5"),
+    ("C03", "French Defense", "Tarrasch Variation", "1. e4 e6 2. d4 d5 3. Nd2"),
+    ("C05", "French Defense", "Tarrasch Variation, Closed Variation", "1. e4 e6 2. d4 d5 3. Nd2 Nf6"),
+    ("C07", "French Defense", "Tarrasch Variation, Open System", "1. e4 e6 2. d4 d5 3. Nd2 c5"),
+    ("C10", "French Defense", "Paulsen Variation", "1. e4 e6 2. d4 d5 3. Nc3"),
+    ("C10", "French Defense", "Rubinstein Variation", "1. e4 e6 2. d4 d5 3. Nc3 dxe4"),
+    ("C11", "French Defense", "Classical Variation", "1. e4 e6 2. d4 d5 3. Nc3 Nf6"),
+    ("C11", "French Defense", "Steinitz Variation", "1. e4 e6 2. d4 d5 3. Nc3 Nf6 4. e5"),
+    ("C13", "French Defense", "Classical Variation", "1. e4 e6 2. d4 d5 3. Nc3 Nf6 4. Bg5"),
+    ("C15", "French Defense", "Winawer Variation", "1. e4 e6 2. d4 d5 3. Nc3 Bb4"),
+    ("C16", "French Defense", "Winawer Variation, Advance Variation", "1. e4 e6 2. d4 d5 3. Nc3 Bb4 4. e5"),
+    ("C18", "French Defense", "Winawer Variation, Classical Variation", "1. e4 e6 2. d4 d5 3. Nc3 Bb4 4. e5 c5 5. a3 Bxc3+ 6. bxc3"),
+    ("C20", "King's Pawn Game", "", "1. e4 e5"),
+    ("C20", "Bongcloud Attack", "", "1. e4 e5 2. Ke2"),
+    ("C20", "King's Pawn Game", "Wayward Queen Attack", "1. e4 e5 2. Qh5"),
+    ("C20", "King's Pawn Game", "Napoleon Attack", "1. e4 e5 2. Qf3"),
+    ("C20", "Alapin's Opening", "", "1. e4 e5 2. Ne2"),
+    ("C21", "Center Game", "", "1. e4 e5 2. d4 exd4"),
+    ("C21", "Danish Gambit", "", "1. e4 e5 2. d4 exd4 3. c3"),
+    ("C22", "Center Game", "Normal Variation", "1. e4 e5 2. d4 exd4 3. Qxd4 Nc6"),
+    ("C23", "Bishop's Opening", "", "1. e4 e5 2. Bc4"),
+    ("C24", "Bishop's Opening", "Berlin Defense", "1. e4 e5 2. Bc4 Nf6"),
+    ("C25", "Vienna Game", "", "1. e4 e5 2. Nc3"),
+    ("C25", "Vienna Game", "Max Lange Defense", "1. e4 e5 2. Nc3 Nc6"),
+    ("C26", "Vienna Game", "Falkbeer Variation", "1. e4 e5 2. Nc3 Nf6"),
+    ("C29", "Vienna Game", "Vienna Gambit", "1. e4 e5 2. Nc3 Nf6 3. f4"),
+    ("C30", "King's Gambit", "", "1. e4 e5 2. f4"),
+    ("C30", "King's Gambit Declined", "Classical Variation", "1. e4 e5 2. f4 Bc5"),
+    ("C31", "King's Gambit Declined", "Falkbeer Countergambit", "1. e4 e5 2. f4 d5"),
+    ("C33", "King's Gambit Accepted", "", "1. e4 e5 2. f4 exf4"),
+    ("C33", "King's Gambit Accepted", "Bishop's Gambit", "1. e4 e5 2. f4 exf4 3. Bc4"),
+    ("C34", "King's Gambit Accepted", "King's Knight's Gambit", "1. e4 e5 2. f4 exf4 3. Nf3"),
+    ("C37", "King's Gambit Accepted", "Traditional Variation", "1. e4 e5 2. f4 exf4 3. Nf3 g5"),
+    ("C39", "King's Gambit Accepted", "Kieseritzky Gambit", "1. e4 e5 2. f4 exf4 3. Nf3 g5 4. h4 g4 5. Ne5"),
+    ("C40", "King's Knight Opening", "", "1. e4 e5 2. Nf3"),
+    ("C40", "Latvian Gambit", "", "1. e4 e5 2. Nf3 f5"),
+    ("C40", "Elephant Gambit", "", "1. e4 e5 2. Nf3 d5"),
+    ("C40", "Gunderam Defense", "", "1. e4 e5 2. Nf3 Qe7"),
+    ("C41", "Philidor Defense", "", "1. e4 e5 2. Nf3 d6"),
+    ("C41", "Philidor Defense", "Exchange Variation", "1. e4 e5 2. Nf3 d6 3. d4 exd4"),
+    ("C42", "Petrov's Defense", "", "1. e4 e5 2. Nf3 Nf6"),
+    ("C42", "Petrov's Defense", "Three Knights Game", "1. e4 e5 2. Nf3 Nf6 3. Nc3"),
+    ("C42", "Petrov's Defense", "Classical Attack", "1. e4 e5 2. Nf3 Nf6 3. Nxe5 d6 4. Nf3 Nxe4 5. d4"),
+    ("C43", "Petrov's Defense", "Modern Attack", "1. e4 e5 2. Nf3 Nf6 3. d4"),
+    ("C44", "King's Knight Opening", "Normal Variation", "1. e4 e5 2. Nf3 Nc6"),
+    ("C44", "Ponziani Opening", "", "1. e4 e5 2. Nf3 Nc6 3. c3"),
+    ("C44", "Scotch Game", "", "1. e4 e5 2. Nf3 Nc6 3. d4"),
+    ("C44", "Scotch Game", "Scotch Gambit", "1. e4 e5 2. Nf3 Nc6 3. d4 exd4 4. Bc4"),
+    ("C44", "Scotch Game", "Göring Gambit", "1. e4 e5 2. Nf3 Nc6 3. d4 exd4 4. c3"),
+    ("C45", "Scotch Game", "", "1. e4 e5 2. Nf3 Nc6 3. d4 exd4 4. Nxd4"),
+    ("C45", "Scotch Game", "Classical Variation", "1. e4 e5 2. Nf3 Nc6 3. d4 exd4 4. Nxd4 Bc5"),
+    ("C45", "Scotch Game", "Schmidt Variation", "1. e4 e5 2. Nf3 Nc6 3. d4 exd4 4. Nxd4 Nf6"),
+    ("C46", "Three Knights Opening", "", "1. e4 e5 2. Nf3 Nc6 3. Nc3"),
+    ("C47", "Four Knights Game", "", "1. e4 e5 2. Nf3 Nc6 3. Nc3 Nf6"),
+    ("C47", "Four Knights Game", "Scotch Variation", "1. e4 e5 2. Nf3 Nc6 3. Nc3 Nf6 4. d4"),
+    ("C47", "Four Knights Game", "Italian Variation", "1. e4 e5 2. Nf3 Nc6 3. Nc3 Nf6 4. Bc4"),
+    ("C48", "Four Knights Game", "Spanish Variation", "1. e4 e5 2. Nf3 Nc6 3. Nc3 Nf6 4. Bb5"),
+    ("C50", "Italian Game", "", "1. e4 e5 2. Nf3 Nc6 3. Bc4"),
+    ("C50", "Italian Game", "Hungarian Defense", "1. e4 e5 2. Nf3 Nc6 3. Bc4 Be7"),
+    ("C50", "Italian Game", "Giuoco Piano", "1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5"),
+    ("C50", "Italian Game", "Giuoco Pianissimo", "1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5 4. d3"),
+    ("C51", "Italian Game", "Evans Gambit", "1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5 4. b4"),
+    ("C52", "Italian Game", "Evans Gambit Accepted", "1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5 4. b4 Bxb4"),
+    ("C53", "Italian Game", "Classical Variation", "1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5 4. c3"),
+    ("C54", "Italian Game", "Classical Variation, Center Attack", "1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5 4. c3 Nf6 5. d4"),
+    ("C55", "Italian Game", "Two Knights Defense", "1. e4 e5 2. Nf3 Nc6 3. Bc4 Nf6"),
+    ("C55", "Italian Game", "Two Knights Defense, Modern Bishop's Opening", "1. e4 e5 2. Nf3 Nc6 3. Bc4 Nf6 4. d3"),
+    ("C57", "Italian Game", "Two Knights Defense, Knight Attack", "1. e4 e5 2. Nf3 Nc6 3. Bc4 Nf6 4. Ng5"),
+    ("C57", "Italian Game", "Two Knights Defense, Traxler Counterattack", "1. e4 e5 2. Nf3 Nc6 3. Bc4 Nf6 4. Ng5 Bc5"),
+    ("C57", "Italian Game", "Two Knights Defense, Fried Liver Attack", "1. e4 e5 2. Nf3 Nc6 3. Bc4 Nf6 4. Ng5 d5 5. exd5 Nxd5 6. Nxf7"),
+    ("C58", "Italian Game", "Two Knights Defense, Polerio Defense", "1. e4 e5 2. Nf3 Nc6 3. Bc4 Nf6 4. Ng5 d5 5. exd5 Na5"),
+    ("C60", "Ruy Lopez", "", "1. e4 e5 2. Nf3 Nc6 3. Bb5"),
+    ("C60", "Ruy Lopez", "Cozio Defense", "1. e4 e5 2. Nf3 Nc6 3. Bb5 Nge7"),
+    ("C61", "Ruy Lopez", "Bird Variation", "1. e4 e5 2. Nf3 Nc6 3. Bb5 Nd4"),
+    ("C62", "Ruy Lopez", "Steinitz Defense", "1. e4 e5 2. Nf3 Nc6 3. Bb5 d6"),
+    ("C63", "Ruy Lopez", "Schliemann Defense", "1. e4 e5 2. Nf3 Nc6 3. Bb5 f5"),
+    ("C64", "Ruy Lopez", "Classical Variation", "1. e4 e5 2. Nf3 Nc6 3. Bb5 Bc5"),
+    ("C65", "Ruy Lopez", "Berlin Defense", "1. e4 e5 2. Nf3 Nc6 3. Bb5 Nf6"),
+    ("C67", "Ruy Lopez", "Berlin Defense, Rio Gambit Accepted", "1. e4 e5 2. Nf3 Nc6 3. Bb5 Nf6 4. O-O Nxe4"),
+    ("C67", "Ruy Lopez", "Berlin Defense, Berlin Wall", "1. e4 e5 2. Nf3 Nc6 3. Bb5 Nf6 4. O-O Nxe4 5. d4 Nd6 6. Bxc6 dxc6 7. dxe5 Nf5 8. Qxd8+ Kxd8"),
+    ("C68", "Ruy Lopez", "Exchange Variation", "1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Bxc6"),
+    ("C70", "Ruy Lopez", "Morphy Defense", "1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4"),
+    ("C77", "Ruy Lopez", "Morphy Defense", "1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4 Nf6"),
+    ("C78", "Ruy Lopez", "Morphy Defense, Castling Line", "1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4 Nf6 5. O-O"),
+    ("C80", "Ruy Lopez", "Open Variation", "1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4 Nf6 5. O-O Nxe4"),
+    ("C84", "Ruy Lopez", "Closed", "1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4 Nf6 5. O-O Be7"),
+    ("C88", "Ruy Lopez", "Closed", "1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3"),
+    ("C88", "Ruy Lopez", "Closed, Anti-Marshall", "1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 O-O 8. a4"),
+    ("C89", "Ruy Lopez", "Marshall Attack", "1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 O-O 8. c3 d5"),
+    ("C90", "Ruy Lopez", "Closed", "1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6"),
+    ("C92", "Ruy Lopez", "Closed", "1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6 8. c3 O-O 9. h3"),
+    ("C92", "Ruy Lopez", "Closed, Zaitsev System", "1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6 8. c3 O-O 9. h3 Bb7"),
+    ("C95", "Ruy Lopez", "Closed, Breyer Defense", "1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6 8. c3 O-O 9. h3 Nb8"),
+    ("C96", "Ruy Lopez", "Closed, Chigorin Defense", "1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6 8. c3 O-O 9. h3 Na5 10. Bc2"),
+    ("D00", "Queen's Pawn Game", "", "1. d4 d5"),
+    ("D00", "Blackmar-Diemer Gambit", "", "1. d4 d5 2. e4"),
+    ("D00", "Queen's Pawn Game", "Accelerated London System", "1. d4 d5 2. Bf4"),
+    ("D00", "Queen's Pawn Game", "Levitsky Attack", "1. d4 d5 2. Bg5"),
+    ("D01", "Richter-Veresov Attack", "", "1. d4 d5 2. Nc3 Nf6 3. Bg5"),
+    ("D02", "Queen's Pawn Game", "Zukertort Variation", "1. d4 d5 2. Nf3"),
+    ("D02", "Queen's Pawn Game", "London System", "1. d4 d5 2. Nf3 Nf6 3. Bf4"),
+    ("D03", "Queen's Pawn Game", "Torre Attack", "1. d4 d5 2. Nf3 Nf6 3. Bg5"),
+    ("D04", "Queen's Pawn Game", "Colle System", "1. d4 d5 2. Nf3 Nf6 3. e3"),
+    ("D06", "Queen's Gambit", "", "1. d4 d5 2. c4"),
+    ("D06", "Queen's Gambit Declined", "Baltic Defense", "1. d4 d5 2. c4 Bf5"),
+    ("D06", "Queen's Gambit Declined", "Marshall Defense", "1. d4 d5 2. c4 Nf6"),
+    ("D07", "Queen's Gambit Declined", "Chigorin Defense", "1. d4 d5 2. c4 Nc6"),
+    ("D08", "Queen's Gambit Declined", "Albin Countergambit", "1. d4 d5 2. c4 e5"),
+    ("D10", "Slav Defense", "", "1. d4 d5 2. c4 c6"),
+    ("D10", "Slav Defense", "Exchange Variation", "1. d4 d5 2. c4 c6 3. cxd5 cxd5"),
+    ("D11", "Slav Defense", "Modern Line", "1. d4 d5 2. c4 c6 3. Nf3"),
+    ("D15", "Slav Defense", "Three Knights Variation", "1. d4 d5 2. c4 c6 3. Nf3 Nf6 4. Nc3"),
+    ("D16", "Slav Defense", "Alapin Variation", "1. d4 d5 2. c4 c6 3. Nf3 Nf6 4. Nc3 dxc4 5. a4"),
+    ("D17", "Slav Defense", "Czech Variation", "1. d4 d5 2. c4 c6 3. Nf3 Nf6 4. Nc3 dxc4 5. a4 Bf5"),
+    ("D20", "Queen's Gambit Accepted", "", "1. d4 d5 2. c4 dxc4"),
+    ("D20", "Queen's Gambit Accepted", "Central Variation", "1. d4 d5 2. c4 dxc4 3. e4"),
+    ("D27", "Queen's Gambit Accepted", "Classical Variation", "1. d4 d5 2. c4 dxc4 3. Nf3 Nf6 4. e3 e6 5. Bxc4 c5 6. O-O a6"),
+    ("D30", "Queen's Gambit Declined", "", "1. d4 d5 2. c4 e6"),
+    ("D31", "Queen's Gambit Declined", "Queen's Knight Variation", "1. d4 d5 2. c4 e6 3. Nc3"),
+    ("D32", "Tarrasch Defense", "", "1. d4 d5 2. c4 e6 3. Nc3 c5"),
+    ("D35", "Queen's Gambit Declined", "Normal Defense", "1. d4 d5 2. c4 e6 3. Nc3 Nf6"),
+    ("D35", "Queen's Gambit Declined", "Exchange Variation", "1. d4 d5 2. c4 e6 3. Nc3 Nf6 4. cxd5 exd5"),
+    ("D37", "Queen's Gambit Declined", "Three Knights Variation", "1. d4 d5 2. c4 e6 3. Nc3 Nf6 4. Nf3"),
+    ("D37", "Queen's Gambit Declined", "Harrwitz Attack", "1. d4 d5 2. c4 e6 3. Nc3 Nf6 4. Nf3 Be7 5. Bf4"),
+    ("D38", "Queen's Gambit Declined", "Ragozin Defense", "1. d4 d5 2. c4 e6 3. Nc3 Nf6 4. Nf3 Bb4"),
+    ("D43", "Semi-Slav Defense", "", "1. d4 d5 2. c4 c6 3. Nf3 Nf6 4. Nc3 e6"),
+    ("D43", "Semi-Slav Defense", "Anti-Moscow Gambit", "1. d4 d5 2. c4 c6 3. Nf3 Nf6 4. Nc3 e6 5. Bg5 h6 6. Bh4"),
+    ("D44", "Semi-Slav Defense", "Botvinnik System", "1. d4 d5 2. c4 c6 3. Nf3 Nf6 4. Nc3 e6 5. Bg5 dxc4"),
+    ("D45", "Semi-Slav Defense", "Normal Variation", "1. d4 d5 2. c4 c6 3. Nf3 Nf6 4. Nc3 e6 5. e3"),
+    ("D47", "Semi-Slav Defense", "Meran Variation", "1. d4 d5 2. c4 c6 3. Nf3 Nf6 4. Nc3 e6 5. e3 Nbd7 6. Bd3 dxc4 7. Bxc4 b5"),
+    ("D50", "Queen's Gambit Declined", "Modern Variation", "1. d4 d5 2. c4 e6 3. Nc3 Nf6 4. Bg5"),
+    ("D53", "Queen's Gambit Declined", "Modern Variation, Normal Line", "1. d4 d5 2. c4 e6 3. Nc3 Nf6 4. Bg5 Be7"),
+    ("D58", "Queen's Gambit Declined", "Tartakower Defense", "1. d4 d5 2. c4 e6 3. Nc3 Nf6 4. Bg5 Be7 5. e3 O-O 6. Nf3 h6 7. Bh4 b6"),
+    ("D70", "Neo-Grünfeld Defense", "", "1. d4 Nf6 2. c4 g6 3. f3 d5"),
+    ("D80", "Grünfeld Defense", "", "1. d4 Nf6 2. c4 g6 3. Nc3 d5"),
+    ("D85", "Grünfeld Defense", "Exchange Variation", "1. d4 Nf6 2. c4 g6 3. Nc3 d5 4. cxd5 Nxd5"),
+    ("D90", "Grünfeld Defense", "Three Knights Variation", "1. d4 Nf6 2. c4 g6 3. Nc3 d5 4. Nf3"),
+    ("D93", "Grünfeld Defense", "Three Knights Variation, Hungarian Attack", "1. d4 Nf6 2. c4 g6 3. Nc3 d5 4. Nf3 Bg7 5. Bf4"),
+    ("D96", "Grünfeld Defense", "Russian Variation", "1. d4 Nf6 2. c4 g6 3. Nc3 d5 4. Nf3 Bg7 5. Qb3"),
+    ("E00", "Indian Defense", "", "1. d4 Nf6 2. c4 e6"),
+    ("E00", "Indian Defense", "Seirawan Attack", "1. d4 Nf6 2. c4 e6 3. Bg5"),
+    ("E01", "Catalan Opening", "", "1. d4 Nf6 2. c4 e6 3. g3"),
+    ("E04", "Catalan Opening", "Open Defense", "1. d4 Nf6 2. c4 e6 3. g3 d5 4. Bg2 dxc4 5. Nf3"),
+    ("E06", "Catalan Opening", "Closed", "1. d4 Nf6 2. c4 e6 3. g3 d5 4. Bg2 Be7 5. Nf3"),
+    ("E10", "Indian Defense", "Anti-Nimzo-Indian", "1. d4 Nf6 2. c4 e6 3. Nf3"),
+    ("E10", "Blumenfeld Countergambit", "", "1. d4 Nf6 2. c4 e6 3. Nf3 c5 4. d5 b5"),
+    ("E11", "Bogo-Indian Defense", "", "1. d4 Nf6 2. c4 e6 3. Nf3 Bb4+"),
+    ("E12", "Queen's Indian Defense", "", "1. d4 Nf6 2. c4 e6 3. Nf3 b6"),
+    ("E15", "Queen's Indian Defense", "Fianchetto Variation", "1. d4 Nf6 2. c4 e6 3. Nf3 b6 4. g3"),
+    ("E20", "Nimzo-Indian Defense", "", "1. d4 Nf6 2. c4 e6 3. Nc3 Bb4"),
+    ("E21", "Nimzo-Indian Defense", "Three Knights Variation", "1. d4 Nf6 2. c4 e6 3. Nc3 Bb4 4. Nf3"),
+    ("E24", "Nimzo-Indian Defense", "Sämisch Variation", "1. d4 Nf6 2. c4 e6 3. Nc3 Bb4 4. a3 Bxc3+ 5. bxc3"),
+    ("E32", "Nimzo-Indian Defense", "Classical Variation", "1. d4 Nf6 2. c4 e6 3. Nc3 Bb4 4. Qc2"),
+    ("E40", "Nimzo-Indian Defense", "Normal Variation", "1. d4 Nf6 2. c4 e6 3. Nc3 Bb4 4. e3"),
+    ("E41", "Nimzo-Indian Defense", "Hübner Variation", "1. d4 Nf6 2. c4 e6 3. Nc3 Bb4 4. e3 c5"),
+    ("E60", "King's Indian Defense", "", "1. d4 Nf6 2. c4 g6"),
+    ("E61", "King's Indian Defense", "", "1. d4 Nf6 2. c4 g6 3. Nc3 Bg7"),
+    ("E62", "King's Indian Defense", "Fianchetto Variation", "1. d4 Nf6 2. c4 g6 3. Nc3 Bg7 4. Nf3 d6 5. g3"),
+    ("E70", "King's Indian Defense", "Normal Variation", "1. d4 Nf6 2. c4 g6 3. Nc3 Bg7 4. e4"),
+    ("E73", "King's Indian Defense", "Averbakh Variation", "1. d4 Nf6 2. c4 g6 3. Nc3 Bg7 4. e4 d6 5. Be2 O-O 6. Bg5"),
+    ("E76", "King's Indian Defense", "Four Pawns Attack", "1. d4 Nf6 2. c4 g6 3. Nc3 Bg7 4. e4 d6 5. f4"),
+    ("E80", "King's Indian Defense", "Sämisch Variation", "1. d4 Nf6 2. c4 g6 3. Nc3 Bg7 4. e4 d6 5. f3"),
+    ("E90", "King's Indian Defense", "Normal Variation", "1. d4 Nf6 2. c4 g6 3. Nc3 Bg7 4. e4 d6 5. Nf3"),
+    ("E92", "King's Indian Defense", "Orthodox Variation", "1. d4 Nf6 2. c4 g6 3. Nc3 Bg7 4. e4 d6 5. Nf3 O-O 6. Be2 e5"),
+    ("E94", "King's Indian Defense", "Orthodox Variation", "1. d4 Nf6 2. c4 g6 3. Nc3 Bg7 4. e4 d6 5. Nf3 O-O 6. Be2 e5 7. O-O"),
+    ("E97", "King's Indian Defense", "Orthodox Variation, Aronin-Taimanov Defense", "1. d4 Nf6 2. c4 g6 3. Nc3 Bg7 4. e4 d6 5. Nf3 O-O 6. Be2 e5 7. O-O Nc6"),
+    ("E99", "King's Indian Defense", "Orthodox Variation, Classical System, Main Line", "1. d4 Nf6 2. c4 g6 3. Nc3 Bg7 4. e4 d6 5. Nf3 O-O 6. Be2 e5 7. O-O Nc6 8. d5 Ne7 9. Ne1 Nd7 10. f3 f5"),
+];
@@ -0,0 +1,247 @@
+use crate::{compression, PgnError, Result};
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+
+// The encodings PGN files are found in. The standard specifies Latin-1, but most newer files are
+// UTF-8, and older ones written on Windows are usually Windows-1252, which is Latin-1 with
+// punctuation and a few letters in place of the control characters 0x80 to 0x9f.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Encoding {
+    Utf8,
+    Latin1,
+    Windows1252,
+    Utf16Le,
+    Utf16Be,
+}
+
+const NAMES: &[(&str, Encoding)] = &[
+    ("utf-8", Encoding::Utf8),
+    ("utf8", Encoding::Utf8),
+    ("latin-1", Encoding::Latin1),
+    ("latin1", Encoding::Latin1),
+    ("iso-8859-1", Encoding::Latin1),
+    ("windows-1252", Encoding::Windows1252),
+    ("cp1252", Encoding::Windows1252),
+    ("utf-16le", Encoding::Utf16Le),
+    ("utf-16be", Encoding::Utf16Be),
+];
+
+// The characters for the bytes 0x80 to 0x9f in Windows-1252. The five bytes it leaves undefined
+// are decoded as the Latin-1 control characters.
+const WINDOWS_1252: [char; 32] = [
+    '\u{20ac}', '\u{81}', '\u{201a}', '\u{192}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2c6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8d}', '\u{17d}', '\u{8f}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2dc}', '\u{2122}', '\u{161}', '\u{203a}', '\u{153}', '\u{9d}', '\u{17e}', '\u{178}',
+];
+
+impl Encoding {
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Latin1 => "Latin-1",
+            Encoding::Windows1252 => "Windows-1252",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+        }
+    }
+
+    // Returns the encoding given by a byte order mark at the start of the bytes, and the length
+    // of the mark.
+    pub fn from_byte_order_mark(bytes: &[u8]) -> Option<(Encoding, usize)> {
+        if bytes.starts_with(&[0xef, 0xbb, 0xbf]) {
+            Some((Encoding::Utf8, 3))
+        } else if bytes.starts_with(&[0xff, 0xfe]) {
+            Some((Encoding::Utf16Le, 2))
+        } else if bytes.starts_with(&[0xfe, 0xff]) {
+            Some((Encoding::Utf16Be, 2))
+        } else {
+            None
+        }
+    }
+
+    // Guesses the encoding: a byte order mark decides it, and otherwise text which is valid
+    // UTF-8 is taken to be UTF-8, and anything else Windows-1252. (Latin-1 text is almost never
+    // valid UTF-8, and decodes the same as Windows-1252 apart from control characters.)
+    pub fn detect(bytes: &[u8]) -> Encoding {
+        match Encoding::from_byte_order_mark(bytes) {
+            Some((encoding, _)) => encoding,
+            None if std::str::from_utf8(bytes).is_ok() => Encoding::Utf8,
+            None => Encoding::Windows1252,
+        }
+    }
+
+    // Decodes the bytes, which shouldn't include a byte order mark. Only UTF-8 can fail: every
+    // byte is a Latin-1 and Windows-1252 character, and unpaired UTF-16 surrogates are replaced.
+    pub fn decode(self, bytes: &[u8]) -> Result<String> {
+        match self {
+            Encoding::Utf8 => String::from_utf8(bytes.to_vec())
+                .map_err(|err| PgnError::Encoding(format!("invalid UTF-8: {}", err))),
+            Encoding::Latin1 => Ok(bytes.iter().map(|&byte| char::from(byte)).collect()),
+            Encoding::Windows1252 => Ok(bytes
+                .iter()
+                .map(|&byte| match byte {
+                    0x80..=0x9f => WINDOWS_1252[usize::from(byte - 0x80)],
+                    _ => char::from(byte),
+                })
+                .collect()),
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                let units = bytes.chunks_exact(2).map(|pair| match self {
+                    Encoding::Utf16Le => u16::from_le_bytes([pair[0], pair[1]]),
+                    _ => u16::from_be_bytes([pair[0], pair[1]]),
+                });
+                Ok(char::decode_utf16(units)
+                    .map(|ch| ch.unwrap_or(char::REPLACEMENT_CHARACTER))
+                    .collect())
+            }
+        }
+    }
+
+    // The byte order mark to start a file with: UTF-16 can't be read without one.
+    pub fn byte_order_mark(self) -> &'static [u8] {
+        match self {
+            Encoding::Utf16Le => &[0xff, 0xfe],
+            Encoding::Utf16Be => &[0xfe, 0xff],
+            _ => &[],
+        }
+    }
+
+    // Encodes the text, failing on the first character the encoding can't represent. No byte
+    // order mark is added.
+    pub fn encode(self, text: &str) -> Result<Vec<u8>> {
+        let unencodable =
+            |ch: char| PgnError::Encoding(format!("{:?} can't be written in {}", ch, self));
+        match self {
+            Encoding::Utf8 => Ok(text.as_bytes().to_vec()),
+            Encoding::Latin1 => text
+                .chars()
+                .map(|ch| u8::try_from(u32::from(ch)).map_err(|_| unencodable(ch)))
+                .collect(),
+            Encoding::Windows1252 => text
+                .chars()
+                .map(
+                    |ch| match WINDOWS_1252.iter().position(|&other| other == ch) {
+                        // The position is less than 32.
+                        Some(index) => Ok(0x80 + index as u8),
+                        None => match u8::try_from(u32::from(ch)) {
+                            Ok(byte) if !(0x80..=0x9f).contains(&byte) => Ok(byte),
+                            _ => Err(unencodable(ch)),
+                        },
+                    },
+                )
+                .collect(),
+            Encoding::Utf16Le | Encoding::Utf16Be => Ok(text
+                .encode_utf16()
+                .flat_map(|unit| match self {
+                    Encoding::Utf16Le => unit.to_le_bytes(),
+                    _ => unit.to_be_bytes(),
+                })
+                .collect()),
+        }
+    }
+}
+
+impl Display for Encoding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = PgnError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        NAMES
+            .iter()
+            .find(|(name, _)| s.eq_ignore_ascii_case(name))
+            .map(|(_, encoding)| *encoding)
+            .ok_or_else(|| PgnError::Encoding(format!("unknown encoding {}", s)))
+    }
+}
+
+// Decodes with the encoding if it is known. Otherwise the text is decoded as UTF-8 if it is valid
+// UTF-8, and as Windows-1252 if it isn't.
+pub fn decode_with(bytes: &[u8], encoding: Option<Encoding>) -> Result<String> {
+    match encoding {
+        Some(encoding) => encoding.decode(bytes),
+        None => match std::str::from_utf8(bytes) {
+            Ok(text) => Ok(text.to_string()),
+            Err(_) => Encoding::Windows1252.decode(bytes),
+        },
+    }
+}
+
+// Reads a file as text, in the given encoding or the one given by its byte order mark, and
+// otherwise in the detected encoding. The byte order mark is removed, and the file is
+// decompressed if it is compressed.
+pub fn read_file(path: impl AsRef<Path>, encoding: Option<Encoding>) -> Result<String> {
+    let mut bytes = vec![];
+    compression::open(path)?.read_to_end(&mut bytes)?;
+    let (marked, mark_length) = match Encoding::from_byte_order_mark(&bytes) {
+        Some((marked, length)) => (Some(marked), length),
+        None => (None, 0),
+    };
+    decode_with(&bytes[mark_length..], encoding.or(marked))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(Encoding::Utf8, Encoding::detect("Müller".as_bytes()));
+        assert_eq!(Encoding::Windows1252, Encoding::detect(b"M\xfcller"));
+        assert_eq!(Encoding::Utf16Le, Encoding::detect(b"\xff\xfeM\x00"));
+        assert_eq!(Encoding::Utf8, Encoding::detect(b"\xef\xbb\xbfM"));
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!("Müller", Encoding::Latin1.decode(b"M\xfcller").unwrap());
+        assert_eq!(
+            "\u{201c}Réti\u{201d} \u{20ac}",
+            Encoding::Windows1252
+                .decode(b"\x93R\xe9ti\x94 \x80")
+                .unwrap()
+        );
+        assert_eq!("\u{93}", Encoding::Latin1.decode(b"\x93").unwrap());
+        assert_eq!("Mü", Encoding::Utf16Le.decode(b"M\x00\xfc\x00").unwrap());
+        assert_eq!("Mü", Encoding::Utf16Be.decode(b"\x00M\x00\xfc").unwrap());
+        assert!(Encoding::Utf8.decode(b"M\xfcller").is_err());
+
+        assert_eq!("Müller", decode_with(b"M\xfcller", None).unwrap());
+        assert_eq!("Müller", decode_with("Müller".as_bytes(), None).unwrap());
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(
+            b"M\xfcller".to_vec(),
+            Encoding::Latin1.encode("Müller").unwrap()
+        );
+        assert!(Encoding::Latin1.encode("\u{20ac}").is_err());
+        assert_eq!(
+            b"\x93R\xe9ti\x94".to_vec(),
+            Encoding::Windows1252
+                .encode("\u{201c}Réti\u{201d}")
+                .unwrap()
+        );
+        assert!(Encoding::Windows1252.encode("\u{93}").is_err());
+        assert!(Encoding::Windows1252.encode("Ł").is_err());
+        assert_eq!(
+            b"M\x00\xfc\x00".to_vec(),
+            Encoding::Utf16Le.encode("Mü").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(Encoding::Latin1, "ISO-8859-1".parse().unwrap());
+        assert_eq!(Encoding::Windows1252, "cp1252".parse().unwrap());
+        assert!("ebcdic".parse::<Encoding>().is_err());
+    }
+}
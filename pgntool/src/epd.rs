@@ -0,0 +1,364 @@
+use crate::pgn_parser::SanMove;
+use crate::position::{Move, Position};
+use crate::{PgnError, Result};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+// An EPD operation. The standard opcodes used by test suites are parsed, and any others are kept
+// as they were written.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Operation {
+    // bm: the best moves in the position, any one of which solves it.
+    BestMoves(Vec<Move>),
+    // am: moves to avoid.
+    AvoidMoves(Vec<Move>),
+    // id: the name of the position, e.g. "WAC.001".
+    Id(String),
+    // c0 to c9: comments, by number.
+    Comment(u8, String),
+    // acd: the depth of the analysis, in plies.
+    AnalysisDepth(u32),
+    // ce: the evaluation in centipawns, from the point of view of the side to move.
+    Evaluation(i32),
+    // pv: the moves expected to be played from the position, one after another.
+    PredictedVariation(Vec<Move>),
+    // hmvc and fmvn: the halfmove clock and fullmove number, which a FEN has but an EPD doesn't.
+    HalfmoveClock(u16),
+    FullmoveNumber(u16),
+    // The operands are as written, with the quotes of any strings.
+    Other {
+        opcode: String,
+        operands: Vec<String>,
+    },
+}
+
+// Splits the operations into their opcodes and operands. A string operand is in double quotes,
+// and can hold spaces and semicolons. The semicolon after the last operation may be left out.
+fn split_operations(s: &str) -> Result<Vec<Vec<String>>> {
+    let mut operations = vec![];
+    let mut tokens = vec![];
+    let mut token = String::new();
+    let mut in_string = false;
+    for ch in s.chars() {
+        if in_string {
+            token.push(ch);
+            in_string = ch != '"';
+        } else if ch == ';' || ch.is_whitespace() {
+            if !token.is_empty() {
+                tokens.push(std::mem::take(&mut token));
+            }
+            if ch == ';' && !tokens.is_empty() {
+                operations.push(std::mem::take(&mut tokens));
+            }
+        } else {
+            token.push(ch);
+            in_string = ch == '"';
+        }
+    }
+    if in_string {
+        return Err(PgnError::InvalidEpd(format!(
+            "unterminated string {}",
+            token
+        )));
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+    if !tokens.is_empty() {
+        operations.push(tokens);
+    }
+    Ok(operations)
+}
+
+fn unquote(operand: &str) -> &str {
+    operand
+        .strip_prefix('"')
+        .and_then(|operand| operand.strip_suffix('"'))
+        .unwrap_or(operand)
+}
+
+// Resolves SAN moves in the position. The moves of bm and am are each played in the position, and
+// those of a pv one after another.
+fn resolve_moves(
+    opcode: &str,
+    position: &Position,
+    operands: &[String],
+    line: bool,
+) -> Result<Vec<Move>> {
+    if operands.is_empty() {
+        return Err(PgnError::InvalidEpd(format!("{} needs a move", opcode)));
+    }
+    let mut position = position.clone();
+    let mut moves = vec![];
+    for operand in operands {
+        let mv = position.resolve(&operand.parse::<SanMove>()?)?;
+        if line {
+            position = position.play(&mv);
+        }
+        moves.push(mv);
+    }
+    Ok(moves)
+}
+
+fn write_moves(position: &Position, moves: &[Move], line: bool) -> Vec<String> {
+    let mut position = position.clone();
+    let mut sans = vec![];
+    for mv in moves {
+        sans.push(position.san(mv).to_string());
+        if line {
+            position = position.play(mv);
+        }
+    }
+    sans
+}
+
+impl Operation {
+    fn parse(opcode: &str, operands: &[String], position: &Position) -> Result<Operation> {
+        let single = || match operands {
+            [operand] => Ok(operand.as_str()),
+            _ => Err(PgnError::InvalidEpd(format!(
+                "{} needs one operand, not {}",
+                opcode,
+                operands.len()
+            ))),
+        };
+        let comment = opcode
+            .strip_prefix('c')
+            .and_then(|digit| digit.parse::<u8>().ok())
+            .filter(|&number| number <= 9 && opcode.len() == 2);
+
+        Ok(match opcode {
+            "bm" => Operation::BestMoves(resolve_moves(opcode, position, operands, false)?),
+            "am" => Operation::AvoidMoves(resolve_moves(opcode, position, operands, false)?),
+            "pv" => Operation::PredictedVariation(resolve_moves(opcode, position, operands, true)?),
+            "id" => Operation::Id(unquote(single()?).to_string()),
+            "acd" => Operation::AnalysisDepth(parse_number(opcode, single()?)?),
+            "ce" => Operation::Evaluation(parse_number(opcode, single()?)?),
+            "hmvc" => Operation::HalfmoveClock(parse_number(opcode, single()?)?),
+            "fmvn" => Operation::FullmoveNumber(parse_number(opcode, single()?)?),
+            _ => match comment {
+                Some(number) => Operation::Comment(number, unquote(single()?).to_string()),
+                None => Operation::Other {
+                    opcode: opcode.to_string(),
+                    operands: operands.to_vec(),
+                },
+            },
+        })
+    }
+
+    pub fn opcode(&self) -> String {
+        match self {
+            Operation::BestMoves(_) => "bm".to_string(),
+            Operation::AvoidMoves(_) => "am".to_string(),
+            Operation::Id(_) => "id".to_string(),
+            Operation::Comment(number, _) => format!("c{}", number),
+            Operation::AnalysisDepth(_) => "acd".to_string(),
+            Operation::Evaluation(_) => "ce".to_string(),
+            Operation::PredictedVariation(_) => "pv".to_string(),
+            Operation::HalfmoveClock(_) => "hmvc".to_string(),
+            Operation::FullmoveNumber(_) => "fmvn".to_string(),
+            Operation::Other { opcode, .. } => opcode.clone(),
+        }
+    }
+
+    // The operands as written in the position, with SAN for the moves.
+    fn operands(&self, position: &Position) -> Vec<String> {
+        match self {
+            Operation::BestMoves(moves) | Operation::AvoidMoves(moves) => {
+                write_moves(position, moves, false)
+            }
+            Operation::PredictedVariation(moves) => write_moves(position, moves, true),
+            Operation::Id(text) | Operation::Comment(_, text) => vec![format!("\"{}\"", text)],
+            Operation::AnalysisDepth(depth) => vec![depth.to_string()],
+            Operation::Evaluation(centipawns) => vec![centipawns.to_string()],
+            Operation::HalfmoveClock(clock) => vec![clock.to_string()],
+            Operation::FullmoveNumber(number) => vec![number.to_string()],
+            Operation::Other { operands, .. } => operands.clone(),
+        }
+    }
+}
+
+fn parse_number<T: FromStr>(opcode: &str, operand: &str) -> Result<T> {
+    operand
+        .parse()
+        .map_err(|_| PgnError::InvalidEpd(format!("bad {} '{}'", opcode, operand)))
+}
+
+/*
+  16.2: EPD
+
+  An EPD record is the first four fields of a FEN, followed by operations. Each operation is an
+  opcode, then its operands separated by spaces, then a semicolon, e.g.
+
+    r1b1k2r/ppp2ppp/2n5/8/8/8/PPP2PPP/R1B1KB1R w KQkq - bm Bf4; id "example.1";
+
+  The halfmove clock and fullmove number of the position are taken from the hmvc and fmvn
+  operations, if there are any.
+*/
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Epd {
+    pub position: Position,
+    pub operations: Vec<Operation>,
+}
+
+impl Epd {
+    pub fn new(position: Position) -> Self {
+        Epd {
+            position,
+            operations: vec![],
+        }
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.operations
+            .iter()
+            .find_map(|operation| match operation {
+                Operation::Id(id) => Some(id.as_str()),
+                _ => None,
+            })
+    }
+
+    // The moves of the bm operation, or none if there isn't one.
+    pub fn best_moves(&self) -> &[Move] {
+        self.operations
+            .iter()
+            .find_map(|operation| match operation {
+                Operation::BestMoves(moves) => Some(moves.as_slice()),
+                _ => None,
+            })
+            .unwrap_or(&[])
+    }
+
+    // The moves of the am operation, or none if there isn't one.
+    pub fn avoid_moves(&self) -> &[Move] {
+        self.operations
+            .iter()
+            .find_map(|operation| match operation {
+                Operation::AvoidMoves(moves) => Some(moves.as_slice()),
+                _ => None,
+            })
+            .unwrap_or(&[])
+    }
+}
+
+impl FromStr for Epd {
+    type Err = PgnError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (mut position, rest) = Position::parse_epd_fields(s)?;
+        let operations = split_operations(rest)?
+            .iter()
+            .map(|tokens| Operation::parse(&tokens[0], &tokens[1..], &position))
+            .collect::<Result<Vec<_>>>()?;
+
+        for operation in &operations {
+            position = match *operation {
+                Operation::HalfmoveClock(clock) => position.with_halfmove_clock(clock),
+                Operation::FullmoveNumber(number) => position.with_fullmove_number(number),
+                _ => position,
+            };
+        }
+        Ok(Epd {
+            position,
+            operations,
+        })
+    }
+}
+
+impl Display for Epd {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.position.epd_fields())?;
+        for operation in &self.operations {
+            write!(f, " {}", operation.opcode())?;
+            for operand in operation.operands(&self.position) {
+                write!(f, " {}", operand)?;
+            }
+            write!(f, ";")?;
+        }
+        Ok(())
+    }
+}
+
+// Parses a file of EPD records, one to a line. Blank lines are skipped.
+pub fn parse_epd_file(text: &str) -> Result<Vec<Epd>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            line.parse()
+                .map_err(|err| PgnError::InvalidEpd(format!("line {}: {}", index + 1, err)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let epd: Epd =
+            "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id \"WAC.001\";"
+                .parse()
+                .unwrap();
+        assert_eq!(Some("WAC.001"), epd.id());
+        assert_eq!(1, epd.best_moves().len());
+        assert_eq!("g3g6", epd.best_moves()[0].to_string());
+        assert!(epd.avoid_moves().is_empty());
+        assert_eq!(
+            "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1",
+            epd.position.to_fen()
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let line = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - \
+                    bm Bb5 Bc4; am a3; pv Bb5 a6 Ba4; acd 20; ce -35; hmvc 2; fmvn 3; \
+                    c0 \"Ruy Lopez; or Italian\"; xyz 1 \"two words\";";
+        let epd: Epd = line.parse().unwrap();
+        assert_eq!(9, epd.operations.len());
+        assert_eq!(Operation::Evaluation(-35), epd.operations[4]);
+        assert_eq!(
+            Operation::Comment(0, "Ruy Lopez; or Italian".to_string()),
+            epd.operations[7]
+        );
+        assert_eq!("xyz", epd.operations[8].opcode());
+        assert_eq!(2, epd.position.halfmove_clock());
+        assert_eq!(3, epd.position.fullmove_number());
+        assert_eq!(line, epd.to_string());
+    }
+
+    #[test]
+    fn test_invalid() {
+        let fen = "4k3/8/8/8/8/8/8/4K2R w K -";
+        for operations in &[
+            "bm Ke3",
+            "bm",
+            "pv Kd2 Kd2",
+            "ce high",
+            "acd 1 2",
+            "id \"unterminated;",
+        ] {
+            assert!(format!("{} {};", fen, operations).parse::<Epd>().is_err());
+        }
+        assert!("4k3/8/8 w - - bm Kd2;".parse::<Epd>().is_err());
+
+        // The last semicolon can be left out.
+        let epd: Epd = format!("{} bm O-O; id \"castle\"", fen).parse().unwrap();
+        assert_eq!(Some("castle"), epd.id());
+    }
+
+    #[test]
+    fn test_file() {
+        let text = "8/8/8/8/8/8/8/K6k w - - id \"one\";\n\n8/8/8/8/8/8/8/K6k b - - id \"two\";\n";
+        let epds = parse_epd_file(text).unwrap();
+        assert_eq!(
+            vec![Some("one"), Some("two")],
+            epds.iter().map(Epd::id).collect::<Vec<_>>()
+        );
+
+        let error = parse_epd_file("8/8/8/8/8/8/8/K6k w - -\n8/8 w - -\n").unwrap_err();
+        assert!(error.to_string().contains("line 2"));
+    }
+}
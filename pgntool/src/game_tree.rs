@@ -0,0 +1,642 @@
+use crate::pgn_parser::{
+    Comment, Element, ElementSequence, MoveNumberIndication, NumericAnnotationGlyph, PgnGame,
+    RecursiveVariation, SanMove, SequenceMember,
+};
+use crate::position::{Color, Position};
+use crate::replay::starting_position;
+use crate::{PgnError, Result};
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+pub struct NodeId(usize);
+
+// A move of the game tree, or the starting position for the root.
+#[derive(Debug, Clone)]
+pub struct Node {
+    // None for the root.
+    san: Option<SanMove>,
+    parent: Option<NodeId>,
+    // The first child continues the line and the others are variations replacing it.
+    children: Vec<NodeId>,
+    nags: Vec<u8>,
+    // Comments before the move, as at the start of a variation.
+    comments_before: Vec<Comment>,
+    // Comments after the move.
+    comments: Vec<Comment>,
+}
+
+impl Node {
+    fn new(san: Option<SanMove>, parent: Option<NodeId>) -> Self {
+        Node {
+            san,
+            parent,
+            children: vec![],
+            nags: vec![],
+            comments_before: vec![],
+            comments: vec![],
+        }
+    }
+
+    pub fn san(&self) -> Option<&SanMove> {
+        self.san.as_ref()
+    }
+
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+
+    pub fn nags(&self) -> &[u8] {
+        &self.nags
+    }
+
+    pub fn comments_before(&self) -> &[Comment] {
+        &self.comments_before
+    }
+
+    pub fn comments(&self) -> &[Comment] {
+        &self.comments
+    }
+}
+
+// An editable game. The parse tree keeps variations as members of the sequence after the move
+// they replace; here each move has the moves that can follow it as children, so variations are
+// alternatives to the mainline move rather than siblings of it.
+//
+// Nodes are kept in an arena and referred to by NodeId. Deleting a variation detaches it from
+// the tree, but its ids stay valid until the tree is dropped.
+#[derive(Debug, Clone)]
+pub struct GameTree {
+    start: Position,
+    nodes: Vec<Node>,
+}
+
+impl GameTree {
+    pub fn new(start: Position) -> Self {
+        GameTree {
+            start,
+            nodes: vec![Node::new(None, None)],
+        }
+    }
+
+    pub fn from_sequence(start: Position, sequence: &ElementSequence) -> Self {
+        let mut tree = GameTree::new(start);
+        tree.add_sequence(tree.root(), sequence);
+        tree
+    }
+
+    pub fn from_game(game: &PgnGame) -> Result<Self> {
+        Ok(GameTree::from_sequence(
+            starting_position(game)?,
+            game.movetext().element_sequence(),
+        ))
+    }
+
+    // Adds the moves of a sequence as a line played from `start`.
+    //
+    // A variation before the first move, as in "{Intro} (1. d4) 1. e4", replaces the first move,
+    // but it is added after it, so that it doesn't take the place of the line.
+    fn add_sequence(&mut self, start: NodeId, sequence: &ElementSequence) {
+        let mut last: Option<NodeId> = None;
+        let mut comments_before = vec![];
+        let mut leading_variations = vec![];
+        for member in sequence.members() {
+            match member {
+                SequenceMember::Move(Element::Move(san)) => {
+                    let parent = last.unwrap_or(start);
+                    let id = self.push_child(parent, san.clone());
+                    self.nodes[id.0].comments_before = std::mem::take(&mut comments_before);
+                    last = Some(id);
+                    for variation in std::mem::take(&mut leading_variations) {
+                        self.add_sequence(start, variation);
+                    }
+                }
+                SequenceMember::Move(Element::Annotation(nag)) => {
+                    if let Some(last) = last {
+                        self.nodes[last.0].nags.push(nag.0);
+                    }
+                }
+                SequenceMember::Comment(comment) => match last {
+                    Some(last) => self.nodes[last.0].comments.push(comment.clone()),
+                    None => comments_before.push(comment.clone()),
+                },
+                SequenceMember::Variation(variation) => match last {
+                    // The variation replaces the last move, so it is played from the same node.
+                    Some(last) => {
+                        let parent = self.nodes[last.0].parent.unwrap_or(start);
+                        self.add_sequence(parent, variation.sequence());
+                    }
+                    None => leading_variations.push(variation.sequence()),
+                },
+                SequenceMember::Move(Element::MoveNumber(_)) => {}
+            }
+        }
+        // Comments in a sequence without moves, such as a game with only a comment. Variations
+        // there have no line to be added after, so they are added as they are.
+        if last.is_none() {
+            self.nodes[start.0].comments.extend(comments_before);
+            for variation in leading_variations {
+                self.add_sequence(start, variation);
+            }
+        }
+    }
+
+    fn push_child(&mut self, parent: NodeId, san: SanMove) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(Node::new(Some(san), Some(parent)));
+        self.nodes[parent.0].children.push(id);
+        id
+    }
+
+    pub fn root(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0]
+    }
+
+    pub fn start(&self) -> &Position {
+        &self.start
+    }
+
+    // The nodes of the mainline, not including the root.
+    pub fn mainline(&self) -> Vec<NodeId> {
+        let mut mainline = vec![];
+        let mut node = self.root();
+        while let Some(&next) = self.node(node).children.first() {
+            mainline.push(next);
+            node = next;
+        }
+        mainline
+    }
+
+    // The moves from the root to the node, not including the root.
+    pub fn path(&self, id: NodeId) -> Vec<NodeId> {
+        let mut path = vec![];
+        let mut node = Some(id);
+        while let Some(id) = node.filter(|&id| id != self.root()) {
+            path.push(id);
+            node = self.node(id).parent;
+        }
+        path.reverse();
+        path
+    }
+
+    // The position after the node's move.
+    pub fn position(&self, id: NodeId) -> Result<Position> {
+        let mut position = self.start.clone();
+        for node in self.path(id) {
+            // unwrap: only the root has no move, and it isn't on the path.
+            let mv = position.resolve(self.node(node).san().unwrap())?;
+            position = position.play(&mv);
+        }
+        Ok(position)
+    }
+
+    // Plays a move after the node. If that move is already there, the existing node is returned;
+    // otherwise the move continues the line if the node has no continuation, and starts a new
+    // variation if it does.
+    pub fn add_move(&mut self, at: NodeId, san: SanMove) -> NodeId {
+        let existing = self
+            .node(at)
+            .children
+            .iter()
+            .copied()
+            .find(|&child| self.node(child).san.as_ref() == Some(&san));
+        match existing {
+            Some(existing) => existing,
+            None => self.push_child(at, san),
+        }
+    }
+
+    // Plays a line of moves after the node, as a variation if the node already has a
+    // continuation. Returns the node of the last move.
+    pub fn add_variation(
+        &mut self,
+        at: NodeId,
+        moves: impl IntoIterator<Item = SanMove>,
+    ) -> NodeId {
+        moves
+            .into_iter()
+            .fold(at, |node, san| self.add_move(node, san))
+    }
+
+    pub fn is_mainline(&self, id: NodeId) -> bool {
+        self.variation_start(id).is_none()
+    }
+
+    // The first move of the innermost variation containing the node, or None if the node is on
+    // the mainline.
+    fn variation_start(&self, id: NodeId) -> Option<NodeId> {
+        let mut node = id;
+        while let Some(parent) = self.node(node).parent {
+            if self.node(parent).children.first() != Some(&node) {
+                return Some(node);
+            }
+            node = parent;
+        }
+        None
+    }
+
+    // Swaps the variation containing the node with the line it replaces, moving it up one level.
+    // Returns false if the node is already on the mainline.
+    pub fn promote_variation(&mut self, id: NodeId) -> bool {
+        let start = match self.variation_start(id) {
+            Some(start) => start,
+            None => return false,
+        };
+        // unwrap: a variation always has a parent.
+        let parent = self.node(start).parent.unwrap();
+        let children = &mut self.nodes[parent.0].children;
+        children.retain(|&child| child != start);
+        children.insert(0, start);
+        true
+    }
+
+    // Promotes the variation containing the node until the node is on the mainline.
+    pub fn promote_to_mainline(&mut self, id: NodeId) {
+        while self.promote_variation(id) {}
+    }
+
+    // Removes the innermost variation containing the node. Returns false if the node is on the
+    // mainline.
+    pub fn delete_variation(&mut self, id: NodeId) -> bool {
+        let start = match self.variation_start(id) {
+            Some(start) => start,
+            None => return false,
+        };
+        // unwrap: a variation always has a parent.
+        let parent = self.node(start).parent.unwrap();
+        self.nodes[parent.0]
+            .children
+            .retain(|&child| child != start);
+        true
+    }
+
+    // Removes everything after the node, including the variations replacing the next move.
+    pub fn delete_remaining_moves(&mut self, id: NodeId) {
+        self.nodes[id.0].children.clear();
+    }
+
+    pub fn comments_mut(&mut self, id: NodeId) -> &mut Vec<Comment> {
+        &mut self.nodes[id.0].comments
+    }
+
+    pub fn comments_before_mut(&mut self, id: NodeId) -> &mut Vec<Comment> {
+        &mut self.nodes[id.0].comments_before
+    }
+
+    // Replaces the comments after the node's move with the given text, or removes them.
+    pub fn set_comment(&mut self, id: NodeId, text: Option<&str>) {
+        self.nodes[id.0].comments = text.map(Comment::new).into_iter().collect();
+    }
+
+    pub fn nags_mut(&mut self, id: NodeId) -> &mut Vec<u8> {
+        &mut self.nodes[id.0].nags
+    }
+
+    // Adds a NAG to the node's move, unless it already has it.
+    pub fn add_nag(&mut self, id: NodeId, nag: u8) {
+        let nags = &mut self.nodes[id.0].nags;
+        if !nags.contains(&nag) {
+            nags.push(nag);
+        }
+    }
+
+    // Prefixes every comment with the author, as in "Smith: A good move".
+    pub fn credit_comments(&mut self, author: &str) {
+        for node in &mut self.nodes {
+            for comment in node
+                .comments_before
+                .iter_mut()
+                .chain(node.comments.iter_mut())
+            {
+                *comment = Comment::new(format!("{}: {}", author, comment.text()));
+            }
+        }
+    }
+
+    // Adds the variations, comments and NAGs of another copy of the same game. Lines that are the
+    // same move for move are merged, however the moves are written, and comments that are
+    // already here aren't repeated. The other copy's comments are credited to the author, if one
+    // is given.
+    //
+    // Both copies must start from the same position and have the same mainline.
+    pub fn merge(&mut self, other: &GameTree, author: Option<&str>) -> Result<()> {
+        if self.start != other.start {
+            return Err(PgnError::DifferentGames(format!(
+                "one starts from {} and the other from {}",
+                self.start.to_fen(),
+                other.start.to_fen()
+            )));
+        }
+        let ours = self.mainline();
+        let theirs = other.mainline();
+        let mut position = self.start.clone();
+        for ply in 0..ours.len().max(theirs.len()) {
+            let moves = match (ours.get(ply), theirs.get(ply)) {
+                (Some(&a), Some(&b)) => Some((
+                    position.resolve(self.node(a).san().unwrap())?,
+                    position.resolve(other.node(b).san().unwrap())?,
+                )),
+                _ => None,
+            };
+            match moves {
+                Some((a, b)) if a == b => position = position.play(&a),
+                _ => {
+                    return Err(PgnError::DifferentGames(format!(
+                        "the mainlines differ at ply {}",
+                        ply + 1
+                    )))
+                }
+            }
+        }
+
+        let start = self.start.clone();
+        self.merge_node(self.root(), other, other.root(), &start, author)
+    }
+
+    fn merge_node(
+        &mut self,
+        id: NodeId,
+        other: &GameTree,
+        other_id: NodeId,
+        position: &Position,
+        author: Option<&str>,
+    ) -> Result<()> {
+        let source = other.node(other_id);
+        for &nag in &source.nags {
+            self.add_nag(id, nag);
+        }
+        let credit = |comment: &Comment| match author {
+            Some(author) => Comment::new(format!("{}: {}", author, comment.text())),
+            None => comment.clone(),
+        };
+        let node = &mut self.nodes[id.0];
+        for comment in source.comments_before.iter().map(credit) {
+            if !node.comments_before.contains(&comment) {
+                node.comments_before.push(comment);
+            }
+        }
+        for comment in source.comments.iter().map(credit) {
+            if !node.comments.contains(&comment) {
+                node.comments.push(comment);
+            }
+        }
+
+        for &other_child in &source.children {
+            // unwrap: only the root has no move, and it is nobody's child.
+            let mv = position.resolve(other.node(other_child).san().unwrap())?;
+            let existing = self.node(id).children.iter().copied().find(|&child| {
+                // unwrap: as above.
+                position.resolve(self.node(child).san().unwrap()).ok() == Some(mv)
+            });
+            let child = match existing {
+                Some(child) => child,
+                None => self.push_child(id, position.san(&mv)),
+            };
+            self.merge_node(child, other, other_child, &position.play(&mv), author)?;
+        }
+        Ok(())
+    }
+
+    // The tree as an element sequence, with move numbers where the export format puts them.
+    pub fn to_sequence(&self) -> ElementSequence {
+        let root = self.node(self.root());
+        let mut members: Vec<SequenceMember> = root
+            .comments
+            .iter()
+            .cloned()
+            .map(SequenceMember::Comment)
+            .collect();
+        if let Some(&first) = root.children.first() {
+            self.write_line(first, 0, &mut members);
+        }
+        ElementSequence::new(members)
+    }
+
+    // Replaces the movetext of the game with the tree.
+    pub fn write_to(&self, game: &mut PgnGame) {
+        game.movetext_mut().set_element_sequence(self.to_sequence());
+    }
+
+    // Writes the line starting at `first`, which is played at the given ply, following the first
+    // children from there. The variations replacing each move follow the move and its
+    // annotations.
+    fn write_line(&self, first: NodeId, ply: usize, members: &mut Vec<SequenceMember>) {
+        // A black move needs its number at the start of a line and after a comment or variation.
+        let mut needs_number = true;
+        let mut next = Some(first);
+        let mut ply = ply;
+        while let Some(id) = next {
+            let node = self.node(id);
+            for comment in &node.comments_before {
+                members.push(SequenceMember::Comment(comment.clone()));
+            }
+            let (number, color) = self.start.move_number_after(ply);
+            if color == Color::White || needs_number || !node.comments_before.is_empty() {
+                let indication = MoveNumberIndication::new(number, color == Color::Black);
+                members.push(SequenceMember::Move(Element::MoveNumber(indication)));
+            }
+            // unwrap: only the root has no move, and lines start after it.
+            members.push(SequenceMember::Move(Element::Move(
+                node.san.clone().unwrap(),
+            )));
+            for &nag in &node.nags {
+                let nag = NumericAnnotationGlyph(nag);
+                members.push(SequenceMember::Move(Element::Annotation(nag)));
+            }
+            for comment in &node.comments {
+                members.push(SequenceMember::Comment(comment.clone()));
+            }
+            needs_number = !node.comments.is_empty();
+
+            // unwrap: lines start after the root, so every node here has a parent.
+            let parent = self.node(node.parent.unwrap());
+            if parent.children[0] == id {
+                for &variation in &parent.children[1..] {
+                    let mut inner = vec![];
+                    self.write_line(variation, ply, &mut inner);
+                    let variation = RecursiveVariation::new(ElementSequence::new(inner));
+                    members.push(SequenceMember::Variation(variation));
+                    needs_number = true;
+                }
+            }
+
+            next = node.children.first().copied();
+            ply += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse_pgn;
+
+    fn tree(movetext: &str) -> GameTree {
+        let database = parse_pgn(movetext).unwrap();
+        GameTree::from_game(&database.games()[0]).unwrap()
+    }
+
+    fn san(text: &str) -> SanMove {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn test_leading_variation() {
+        // The variation stays a variation, after the first move, and the comment stays first.
+        let tree = tree("{Intro} (1. d4 d5) 1. e4 e5 *");
+        assert_eq!(
+            "{Intro} 1. e4 (1. d4 d5) 1... e5",
+            tree.to_sequence().to_string()
+        );
+        let mut game = parse_pgn("{Intro} (1. d4 d5) 1. e4 e5 *")
+            .unwrap()
+            .into_games()
+            .remove(0);
+        tree.write_to(&mut game);
+        let tree = GameTree::from_game(&game).unwrap();
+        assert_eq!(
+            "{Intro} 1. e4 (1. d4 d5) 1... e5",
+            tree.to_sequence().to_string()
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let movetext = "{Opening} 1. e4 e5 (1... c5 2. Nf3 (2. Nc3 Nc6) 2... d6) (1... e6) \
+                        2. Nf3 $1 {Develops} 2... Nc6 3. Bb5";
+        let tree = tree(&format!("{} *", movetext));
+        assert_eq!(movetext, tree.to_sequence().to_string());
+
+        let mainline: Vec<String> = tree
+            .mainline()
+            .into_iter()
+            .map(|id| tree.node(id).san().unwrap().to_string())
+            .collect();
+        assert_eq!(vec!["e4", "e5", "Nf3", "Nc6", "Bb5"], mainline);
+    }
+
+    #[test]
+    fn test_move_numbers() {
+        // The numbers are regenerated, so missing and wrong ones are fixed.
+        let tree = tree("e4 e5 7. Nf3 {Develops} Nc6 (Nf6) d4 *");
+        assert_eq!(
+            "1. e4 e5 2. Nf3 {Develops} 2... Nc6 (2... Nf6) 3. d4",
+            tree.to_sequence().to_string()
+        );
+
+        let database = parse_pgn("[FEN \"4k3/8/8/8/8/8/8/4K3 b - - 0 30\"]\n\nKd7 Kd2 *").unwrap();
+        let tree = GameTree::from_game(&database.games()[0]).unwrap();
+        assert_eq!("30... Kd7 31. Kd2", tree.to_sequence().to_string());
+    }
+
+    #[test]
+    fn test_adding_moves() {
+        let mut tree = GameTree::new(Position::starting());
+        let e4 = tree.add_move(tree.root(), san("e4"));
+        let e5 = tree.add_move(e4, san("e5"));
+        assert_eq!(e4, tree.add_move(tree.root(), san("e4")));
+
+        let c5 = tree.add_variation(e4, vec![san("c5"), san("Nf3")]);
+        tree.add_move(e5, san("Nf3"));
+        assert_eq!(
+            "1. e4 e5 (1... c5 2. Nf3) 2. Nf3",
+            tree.to_sequence().to_string()
+        );
+        assert!(!tree.is_mainline(c5));
+        assert_eq!(
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2",
+            tree.position(c5).unwrap().to_fen()
+        );
+    }
+
+    #[test]
+    fn test_promote_and_delete() {
+        let mut tree = tree("1. e4 e5 (1... c5 2. Nf3 (2. Nc3)) (1... e6) 2. Nf3 *");
+        let c5 = tree.node(tree.mainline()[0]).children()[1];
+        let nc3 = tree.node(c5).children()[1];
+
+        tree.promote_variation(nc3);
+        assert_eq!(
+            "1. e4 e5 (1... c5 2. Nc3 (2. Nf3)) (1... e6) 2. Nf3",
+            tree.to_sequence().to_string()
+        );
+        tree.promote_to_mainline(nc3);
+        assert!(tree.is_mainline(nc3));
+        assert_eq!(
+            "1. e4 c5 (1... e5 2. Nf3) (1... e6) 2. Nc3 (2. Nf3)",
+            tree.to_sequence().to_string()
+        );
+        assert!(!tree.promote_variation(nc3));
+
+        let nf3 = tree.node(c5).children()[1];
+        assert!(tree.delete_variation(nf3));
+        assert!(!tree.delete_variation(nc3));
+        let e6 = tree.node(tree.mainline()[0]).children()[2];
+        assert!(tree.delete_variation(e6));
+        assert_eq!(
+            "1. e4 c5 (1... e5 2. Nf3) 2. Nc3",
+            tree.to_sequence().to_string()
+        );
+
+        tree.delete_remaining_moves(tree.mainline()[0]);
+        assert_eq!("1. e4", tree.to_sequence().to_string());
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut merged = tree("1. e4 {Best by test} e5 (1... c5 2. Nf3) 2. Nf3 *");
+        let other = tree("1. e4 $1 {Best by test} e5 (1... c5 2. Nf3 d6) (1... e6) 2. Nf3 *");
+        merged.merge(&other, None).unwrap();
+        assert_eq!(
+            "1. e4 $1 {Best by test} 1... e5 (1... c5 2. Nf3 d6) (1... e6) 2. Nf3",
+            merged.to_sequence().to_string()
+        );
+
+        // Moves written differently are the same move.
+        let mut merged = tree("1. e4 {Good} e5 2. Nf3 Nc6 (2... Nf6 3. Nxe5) *");
+        let other = tree("1. e4 {Good} e5 2. Nf3 Nc6 (2... Nf6 3. Ne5 {Petrov}) *");
+        merged.credit_comments("Ann");
+        merged.merge(&other, Some("Bob")).unwrap();
+        assert_eq!(
+            "1. e4 {Ann: Good} {Bob: Good} 1... e5 2. Nf3 Nc6 (2... Nf6 3. Nxe5 {Bob: Petrov})",
+            merged.to_sequence().to_string()
+        );
+    }
+
+    #[test]
+    fn test_merge_different_games() {
+        let mut merged = tree("1. e4 e5 2. Nf3 *");
+        assert!(merged.merge(&tree("1. e4 e5 2. Nc3 *"), None).is_err());
+        assert!(merged.merge(&tree("1. e4 e5 *"), None).is_err());
+        assert!(merged.merge(&tree("1. e4 e5 2. Nf3 Nc6 *"), None).is_err());
+    }
+
+    #[test]
+    fn test_annotations() {
+        let mut tree = tree("1. e4 {Best by test} e5 *");
+        let mainline = tree.mainline();
+        tree.set_comment(mainline[0], None);
+        tree.set_comment(mainline[1], Some("Symmetrical"));
+        tree.add_nag(mainline[0], 1);
+        tree.add_nag(mainline[0], 1);
+        tree.comments_before_mut(mainline[0])
+            .push(Comment::new("Start"));
+        assert_eq!(
+            "{Start} 1. e4 $1 e5 {Symmetrical}",
+            tree.to_sequence().to_string()
+        );
+
+        let mut game = parse_pgn("1. d4 *").unwrap().into_games().remove(0);
+        tree.write_to(&mut game);
+        assert_eq!(
+            "{Start} 1. e4 $1 e5 {Symmetrical} *",
+            game.movetext().to_string()
+        );
+    }
+}
@@ -4,6 +4,28 @@ mod pgn_error;
 pub use pgn_error::PgnError;
 
 mod pgn_parser;
-pub use pgn_parser::{parse_pgn, PgnDatabase};
+pub use pgn_parser::{
+    parse_pgn, parse_pgn_with, Check, Comment, Element, ElementSequence, File, GameTermination,
+    Localized, MovetextSection, ParseOptions, PgnDatabase, PgnGame, Piece, PieceLetters, Rank,
+    SanMove, SanMoveDetail, SanMoveType, SequenceMember, Square, TagPair, TagSection,
+};
+
+pub mod adjudicate;
+pub mod compression;
+pub mod dedupe;
+pub mod eco;
+pub mod encoding;
+pub mod epd;
+pub mod game_tree;
+pub mod move_numbers;
+pub mod opening_tree;
+pub mod polyglot;
+pub mod position;
+pub mod reader;
+pub mod replay;
+pub mod sort;
+pub mod strip;
+pub mod svg;
+pub mod viewer;
 
 type Result<T> = std::result::Result<T, PgnError>;
@@ -1,15 +1,43 @@
 use argh::FromArgs;
-use std::fs::read_to_string;
 use thiserror::Error;
 
+mod commands;
+
 #[derive(FromArgs)]
 /// DO NOT SUBMIT without putting something here. TODO
 struct Args {
+    /// the encoding of the input files: utf-8, latin-1, windows-1252, utf-16le or utf-16be
+    /// (default is to detect it)
+    #[argh(option)]
+    encoding: Option<pgntool::encoding::Encoding>,
+
+    #[argh(subcommand)]
+    command: Option<Command>,
+
     /// DO NOT SUBMIT without filling this in TODO
     #[argh(positional)]
     pgn_files: Vec<String>,
 }
 
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum Command {
+    Book(commands::book::BookArgs),
+    Convert(commands::convert::ConvertArgs),
+    Dedupe(commands::dedupe::DedupeArgs),
+    Diagram(commands::diagram::DiagramArgs),
+    Eco(commands::eco::EcoArgs),
+    FixResults(commands::fix_results::FixResultsArgs),
+    Lint(commands::lint::LintArgs),
+    Merge(commands::merge::MergeArgs),
+    MergeAnnotations(commands::merge_annotations::MergeAnnotationsArgs),
+    Show(commands::show::ShowArgs),
+    Split(commands::split::SplitArgs),
+    Strip(commands::strip::StripArgs),
+    Tree(commands::tree::TreeArgs),
+    View(commands::view::ViewArgs),
+}
+
 #[derive(Debug, Error)]
 enum Err {
     #[error("{0}")]
@@ -17,6 +45,12 @@ enum Err {
 
     #[error("{0}")]
     PgnError(#[from] pgntool::PgnError),
+
+    #[error("{0}")]
+    Usage(String),
+
+    #[error("{0} games had errors")]
+    Lint(usize),
 }
 
 type Result<T> = std::result::Result<T, Err>;
@@ -28,8 +62,7 @@ fn process_stdin() -> Result<()> {
 
 fn process_pgn_files(args: Args) -> Result<()> {
     for file in args.pgn_files {
-        let pgn_string = read_to_string(file)?;
-        let database = pgntool::parse_pgn(pgn_string)?;
+        let database = commands::read_database(&file)?;
         dbg!(database);
     }
     Ok(())
@@ -37,10 +70,24 @@ fn process_pgn_files(args: Args) -> Result<()> {
 
 fn main() -> Result<()> {
     let args: Args = argh::from_env();
+    commands::set_input_encoding(args.encoding);
 
-    if args.pgn_files.is_empty() {
-        process_stdin()
-    } else {
-        process_pgn_files(args)
+    match args.command {
+        Some(Command::Book(book_args)) => commands::book::run(book_args),
+        Some(Command::Convert(convert_args)) => commands::convert::run(convert_args),
+        Some(Command::Dedupe(dedupe_args)) => commands::dedupe::run(dedupe_args),
+        Some(Command::Diagram(diagram_args)) => commands::diagram::run(diagram_args),
+        Some(Command::Eco(eco_args)) => commands::eco::run(eco_args),
+        Some(Command::FixResults(fix_args)) => commands::fix_results::run(fix_args),
+        Some(Command::Lint(lint_args)) => commands::lint::run(lint_args),
+        Some(Command::Merge(merge_args)) => commands::merge::run(merge_args),
+        Some(Command::MergeAnnotations(merge_args)) => commands::merge_annotations::run(merge_args),
+        Some(Command::Show(show_args)) => commands::show::run(show_args),
+        Some(Command::Split(split_args)) => commands::split::run(split_args),
+        Some(Command::Strip(strip_args)) => commands::strip::run(strip_args),
+        Some(Command::Tree(tree_args)) => commands::tree::run(tree_args),
+        Some(Command::View(view_args)) => commands::view::run(view_args),
+        None if args.pgn_files.is_empty() => process_stdin(),
+        None => process_pgn_files(args),
     }
 }
@@ -0,0 +1,147 @@
+use crate::pgn_parser::{Element, ElementSequence, PgnGame, SequenceMember};
+use crate::position::{Color, Position};
+use crate::replay::starting_position;
+use crate::Result;
+
+// The move number which should come before a move in the position: "14." for White's
+// fourteenth move and "14..." for Black's.
+fn expected_number(position: &Position) -> String {
+    match position.side_to_move() {
+        Color::White => format!("{}.", position.fullmove_number()),
+        Color::Black => format!("{}...", position.fullmove_number()),
+    }
+}
+
+// Checks the move numbers in the sequence against the moves they come before. A variation
+// replaces the move before it, so it starts from the position before that move.
+//
+// Numbers are optional before most Black moves, but one is required before a Black move which
+// starts the game or a variation, or follows a comment or a variation, as in "2. Nf3 {Best}
+// 2... Nc6".
+fn check_sequence(
+    sequence: &ElementSequence,
+    mut position: Position,
+    warnings: &mut Vec<String>,
+) -> Result<()> {
+    let mut previous: Option<Position> = None;
+    let mut number = None;
+    let mut number_required = true;
+
+    for member in sequence.members() {
+        match member {
+            SequenceMember::Move(Element::MoveNumber(mni)) => number = Some(mni),
+            SequenceMember::Move(Element::Move(san_move)) => {
+                if let Some(mni) = number.take() {
+                    let black = position.side_to_move() == Color::Black;
+                    if mni.number() != position.fullmove_number() || mni.is_continuation() != black
+                    {
+                        warnings.push(format!(
+                            "move number {} before {} should be {}",
+                            mni,
+                            san_move,
+                            expected_number(&position)
+                        ));
+                    }
+                } else if number_required && position.side_to_move() == Color::Black {
+                    warnings.push(format!(
+                        "missing move number {} before {}",
+                        expected_number(&position),
+                        san_move
+                    ));
+                }
+                number_required = false;
+                let mv = position.resolve(san_move)?;
+                let next = position.play(&mv);
+                previous = Some(std::mem::replace(&mut position, next));
+            }
+            SequenceMember::Move(Element::Annotation(_)) => {}
+            SequenceMember::Comment(_) => number_required = true,
+            SequenceMember::Variation(variation) => {
+                number_required = true;
+                if let Some(start) = &previous {
+                    check_sequence(variation.sequence(), start.clone(), warnings)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// Checks that the move numbers in a game, including those in variations, agree with the moves
+// they are written before, returning a warning for each one which doesn't. It is an error if a
+// move can't be played.
+pub fn check_move_numbers(game: &PgnGame) -> Result<Vec<String>> {
+    let mut warnings = vec![];
+    check_sequence(
+        game.movetext().element_sequence(),
+        starting_position(game)?,
+        &mut warnings,
+    )?;
+    Ok(warnings)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse_pgn;
+
+    fn warnings(pgn: &str) -> Vec<String> {
+        let database = parse_pgn(pgn).unwrap();
+        check_move_numbers(&database.games()[0]).unwrap()
+    }
+
+    #[test]
+    fn test_consistent() {
+        assert!(warnings("1. e4 e5 2. Nf3 (2. Bc4 Nf6) 2... Nc6 3 . Bb5 *").is_empty());
+        // Numbers are optional.
+        assert!(warnings("e4 e5 Nf3 *").is_empty());
+        assert!(
+            warnings("[FEN \"4k3/8/8/8/8/8/8/R3K3 b Q - 0 20\"]\n\n20... Kf7 21. O-O-O *")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_mismatches() {
+        assert_eq!(
+            vec![
+                "move number 3. before Nf3 should be 2.",
+                "move number 2... before Bc4 should be 2.",
+                "move number 2. before Nc6 should be 2...",
+            ],
+            warnings("1. e4 e5 3. Nf3 (2... Bc4) 2. Nc6 *")
+        );
+        assert_eq!(
+            vec!["move number 1. before Kf7 should be 20..."],
+            warnings("[FEN \"4k3/8/8/8/8/8/8/R3K3 b Q - 0 20\"]\n\n1. Kf7 *")
+        );
+    }
+
+    #[test]
+    fn test_missing() {
+        assert_eq!(
+            vec!["missing move number 2... before Nc6"],
+            warnings("1. e4 e5 2. Nf3 (2. Bc4) Nc6 *")
+        );
+        assert_eq!(
+            vec!["missing move number 1... before e5"],
+            warnings("1. e4 {Best by test} e5 *")
+        );
+        assert_eq!(
+            vec!["missing move number 20... before Kf7"],
+            warnings("[FEN \"4k3/8/8/8/8/8/8/R3K3 b Q - 0 20\"]\n\nKf7 21. O-O-O *")
+        );
+        assert_eq!(
+            vec!["missing move number 1... before c5"],
+            warnings("1. e4 e5 (c5) 2. Nf3 *")
+        );
+        // A White move, or a Black move after a NAG, needs no number.
+        assert!(warnings("1. e4 e5 {Open} Nf3 $1 Nc6 *").is_empty());
+    }
+
+    #[test]
+    fn test_illegal_move() {
+        let database = parse_pgn("1. e4 e5 (1... e4) *").unwrap();
+        assert!(check_move_numbers(&database.games()[0]).is_err());
+    }
+}
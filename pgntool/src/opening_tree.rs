@@ -0,0 +1,218 @@
+use crate::pgn_parser::{GameTermination, PgnGame, SanMove};
+use crate::polyglot::polyglot_key;
+use crate::position::{Move, Position};
+use crate::replay::{require_standard_rules, Mainline};
+use crate::Result;
+use std::collections::HashMap;
+
+// Results and ratings aggregated over the games which played a move.
+#[derive(Debug, Default, Eq, PartialEq, Copy, Clone)]
+pub struct MoveStats {
+    pub games: u64,
+    pub white_wins: u64,
+    pub draws: u64,
+    pub black_wins: u64,
+    elo_total: u64,
+    elo_count: u64,
+}
+
+impl MoveStats {
+    fn add(&mut self, termination: GameTermination, elos: &[u32]) {
+        self.games += 1;
+        match termination {
+            GameTermination::WhiteWins => self.white_wins += 1,
+            GameTermination::BlackWins => self.black_wins += 1,
+            GameTermination::Tie => self.draws += 1,
+            GameTermination::Unfinished => {}
+        }
+        for &elo in elos {
+            self.elo_total += elo as u64;
+            self.elo_count += 1;
+        }
+    }
+
+    fn merge(&mut self, other: &MoveStats) {
+        self.games += other.games;
+        self.white_wins += other.white_wins;
+        self.draws += other.draws;
+        self.black_wins += other.black_wins;
+        self.elo_total += other.elo_total;
+        self.elo_count += other.elo_count;
+    }
+
+    // The number of games with a result, which the percentages are out of. Unfinished games
+    // count towards the games for a move but have no result to add up.
+    pub fn finished(&self) -> u64 {
+        self.white_wins + self.draws + self.black_wins
+    }
+
+    fn percent(&self, count: u64) -> f64 {
+        let finished = self.finished();
+        if finished == 0 {
+            0.0
+        } else {
+            100.0 * count as f64 / finished as f64
+        }
+    }
+
+    pub fn white_percent(&self) -> f64 {
+        self.percent(self.white_wins)
+    }
+
+    pub fn draw_percent(&self) -> f64 {
+        self.percent(self.draws)
+    }
+
+    pub fn black_percent(&self) -> f64 {
+        self.percent(self.black_wins)
+    }
+
+    // The average rating of the players in the games, counting only ratings which are known.
+    pub fn average_elo(&self) -> Option<u32> {
+        self.elo_total
+            .checked_div(self.elo_count)
+            .map(|elo| elo as u32)
+    }
+}
+
+// A move played from a position in the tree.
+#[derive(Debug, Clone)]
+pub struct TreeMove {
+    pub mv: Move,
+    // The move in SAN, however the games wrote it.
+    pub san: SanMove,
+    pub stats: MoveStats,
+}
+
+// Aggregates the moves played from each position in the first plies of a set of games, like an
+// opening explorer. Positions are keyed by position rather than by move order, so transpositions
+// are combined.
+#[derive(Debug)]
+pub struct OpeningTree {
+    max_plies: usize,
+    positions: HashMap<u64, Vec<TreeMove>>,
+}
+
+fn elo(game: &PgnGame, tag: &str) -> Option<u32> {
+    game.tag(tag)?.trim().parse().ok()
+}
+
+impl OpeningTree {
+    pub fn new(max_plies: usize) -> Self {
+        OpeningTree {
+            max_plies,
+            positions: HashMap::new(),
+        }
+    }
+
+    // Games of variants other than Chess960 are rejected.
+    pub fn add_game(&mut self, game: &PgnGame) -> Result<()> {
+        require_standard_rules(game)?;
+        let termination = game.movetext().game_termination();
+        let elos: Vec<u32> = ["WhiteElo", "BlackElo"]
+            .iter()
+            .filter_map(|tag| elo(game, tag))
+            .collect();
+
+        let mainline = Mainline::replay_plies(game, self.max_plies)?;
+        for (position, mv) in mainline.plies() {
+            if mv.is_null() {
+                continue;
+            }
+            let moves = self.positions.entry(polyglot_key(position)).or_default();
+            let index = match moves.iter().position(|tree_move| tree_move.mv == *mv) {
+                Some(index) => index,
+                None => {
+                    moves.push(TreeMove {
+                        mv: *mv,
+                        san: position.san(mv),
+                        stats: MoveStats::default(),
+                    });
+                    moves.len() - 1
+                }
+            };
+            moves[index].stats.add(termination, &elos);
+        }
+
+        Ok(())
+    }
+
+    // Returns the moves played from the position, most popular first.
+    pub fn moves(&self, position: &Position) -> Vec<&TreeMove> {
+        let mut moves: Vec<&TreeMove> = self
+            .positions
+            .get(&polyglot_key(position))
+            .map(|moves| moves.iter().collect())
+            .unwrap_or_default();
+        moves.sort_by_key(|tree_move| std::cmp::Reverse(tree_move.stats.games));
+        moves
+    }
+
+    // Returns the combined statistics of every move played from the position.
+    pub fn total(&self, position: &Position) -> MoveStats {
+        let mut total = MoveStats::default();
+        for tree_move in self.moves(position) {
+            total.merge(&tree_move.stats);
+        }
+        total
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse_pgn;
+
+    #[test]
+    fn test_tree() {
+        let database = parse_pgn(
+            "[WhiteElo \"2000\"]\n[BlackElo \"1800\"]\n\n1. e4 e5 2. Nf3 1-0\n\n\
+             [WhiteElo \"2200\"]\n\n1. e4 c5 0-1\n\n\
+             1. d4 d5 2. Nf3 Nf6 1/2-1/2\n\n\
+             1. Nf3 d5 2. d4 Nf6 *",
+        )
+        .unwrap();
+
+        let mut tree = OpeningTree::new(4);
+        for game in database.games() {
+            tree.add_game(game).unwrap();
+        }
+
+        let start = Position::starting();
+        let moves = tree.moves(&start);
+        assert_eq!(
+            vec!["e4", "d4", "Nf3"],
+            moves
+                .iter()
+                .map(|tree_move| tree_move.san.to_string())
+                .collect::<Vec<_>>()
+        );
+
+        let e4 = moves[0].stats;
+        assert_eq!(2, e4.games);
+        assert_eq!(50.0, e4.white_percent());
+        assert_eq!(50.0, e4.black_percent());
+        assert_eq!(Some(2000), e4.average_elo());
+
+        let total = tree.total(&start);
+        assert_eq!(4, total.games);
+        assert_eq!(1, total.draws);
+
+        // 1. d4 d5 2. Nf3 and 1. Nf3 d5 2. d4 reach the same position.
+        let database = parse_pgn("1. d4 d5 2. Nf3 *").unwrap();
+        let position = Mainline::replay(&database.games()[0]).unwrap();
+        let moves = tree.moves(position.final_position());
+        assert_eq!(1, moves.len());
+        assert_eq!("Nf6", moves[0].san.to_string());
+        assert_eq!(2, moves[0].stats.games);
+        assert_eq!(None, moves[0].stats.average_elo());
+        // The unfinished game isn't counted in the percentages.
+        assert_eq!(1, moves[0].stats.finished());
+        assert_eq!(100.0, moves[0].stats.draw_percent());
+        assert_eq!(0.0, moves[0].stats.white_percent());
+
+        let database = parse_pgn("[Variant \"Atomic\"]\n\n1. e4 d5 *").unwrap();
+        assert!(tree.add_game(&database.games()[0]).is_err());
+        assert_eq!(4, tree.total(&start).games);
+    }
+}
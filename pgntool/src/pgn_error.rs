@@ -15,6 +15,42 @@ pub enum PgnError {
     #[error("Unexpected character while parsing {0}: {1}")]
     UnmatchedChar(&'static str, char),
 
+    #[error("Invalid tag: {0}")]
+    InvalidTag(String),
+
+    #[error("Invalid FEN: {0}")]
+    InvalidFen(String),
+
+    #[error("Invalid EPD: {0}")]
+    InvalidEpd(String),
+
+    #[error("Unknown variant: {0}")]
+    UnknownVariant(String),
+
+    #[error("Unsupported variant: {0}")]
+    UnsupportedVariant(String),
+
+    #[error("Illegal move: {0}")]
+    IllegalMove(String),
+
+    #[error("Ambiguous move: {0}")]
+    AmbiguousMove(String),
+
+    #[error("Invalid opening book: {0}")]
+    InvalidBook(String),
+
+    #[error("Encoding error: {0}")]
+    Encoding(String),
+
+    #[error("Compression error: {0}")]
+    Compression(String),
+
+    #[error("The games differ: {0}")]
+    DifferentGames(String),
+
+    #[error("{0}")]
+    IOError(#[from] std::io::Error),
+
     // NOT a user-visible error.
     // This is used when a parse is rejected because of the character immediately after
     // the parsed input. This is required because parts of the grammar are ambiguous.
@@ -0,0 +1,142 @@
+use crate::pgn_error::PgnError;
+use crate::pgn_parser::GrammarNode;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Comment {
+    text: String,
+}
+
+impl Comment {
+    pub fn new(text: impl Into<String>) -> Self {
+        Comment { text: text.into() }
+    }
+
+    // The text of the comment, without the braces or semicolon.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    // Returns the commands embedded in the comment, such as "[%clk 0:03:00]" or
+    // "[%csl Ga4,Rb5]", as (name, value) pairs.
+    pub fn commands(&self) -> Vec<(&str, &str)> {
+        let mut commands = vec![];
+        let mut rest = self.text.as_str();
+        while let Some(start) = rest.find("[%") {
+            let command = &rest[start + 2..];
+            let end = match command.find(']') {
+                Some(end) => end,
+                None => break,
+            };
+            let (name, value) = command[..end]
+                .trim()
+                .split_once(char::is_whitespace)
+                .unwrap_or((command[..end].trim(), ""));
+            commands.push((name, value.trim()));
+            rest = &command[end + 1..];
+        }
+        commands
+    }
+
+    // The comment with its commands removed, leaving only the text.
+    pub fn without_commands(&self) -> Comment {
+        let mut text = String::new();
+        let mut rest = self.text.as_str();
+        while let Some(start) = rest.find("[%") {
+            let end = match rest[start..].find(']') {
+                Some(end) => start + end + 1,
+                None => break,
+            };
+            text.push_str(&rest[..start]);
+            text.push(' ');
+            rest = &rest[end..];
+        }
+        text.push_str(rest);
+        Comment::new(text.split_whitespace().collect::<Vec<_>>().join(" "))
+    }
+}
+
+// Comments are always exported in brace form. A right brace can't appear inside a brace
+// comment, so any (from a rest-of-line comment) are dropped.
+impl Display for Comment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{{{}}}", self.text.replace('}', ""))
+    }
+}
+
+/*
+  8.2.5: Movetext comment zone
+
+  Comment text may appear in PGN data. There are two kinds of comments. The first kind is the
+  "rest of line" comment; this comment type starts with a semicolon character and continues to
+  the end of the line. The second kind starts with a left brace character and continues to the
+  next right brace character. Brace comments cannot nest; a left brace character appearing in a
+  brace comment loses its special meaning and is ignored. A semicolon appearing inside of a
+  brace comment loses its special meaning and is ignored. Braces appearing inside of a semicolon
+  comments lose their special meaning and are ignored.
+*/
+impl GrammarNode for Comment {
+    fn check_start(s: &str) -> bool {
+        s.starts_with('{') || s.starts_with(';')
+    }
+
+    fn parse_wrapped(s: &str) -> crate::Result<(Self, &str)>
+    where
+        Self: Sized,
+    {
+        if let Some(s) = s.strip_prefix('{') {
+            let end = s.find('}').ok_or(PgnError::UnexpectedEOF("Comment"))?;
+            Ok((Comment::new(s[..end].trim()), &s[end + 1..]))
+        } else if let Some(s) = s.strip_prefix(';') {
+            let end = s.find('\n').unwrap_or(s.len());
+            Ok((Comment::new(s[..end].trim()), &s[end..]))
+        } else {
+            Err(PgnError::UnexpectedInput("Comment", s.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            (Comment::new("A good move; or is it?"), " 2. d4"),
+            Comment::parse("{ A good move; or is it? } 2. d4").unwrap()
+        );
+        assert_eq!(
+            (Comment::new("To the end {of the line}"), "\n2. d4"),
+            Comment::parse("; To the end {of the line}\n2. d4").unwrap()
+        );
+        assert_eq!(
+            (Comment::new("[%clk 0:03:00]"), ""),
+            Comment::parse("{[%clk 0:03:00]}").unwrap()
+        );
+        assert!(Comment::parse("{ never closed").is_err());
+    }
+
+    #[test]
+    fn test_commands() {
+        let comment = Comment::new("Good. [%csl Ga4,Rb5] [%cal Ge2e4] [%clk 0:03:00]");
+        assert_eq!(
+            vec![("csl", "Ga4,Rb5"), ("cal", "Ge2e4"), ("clk", "0:03:00")],
+            comment.commands()
+        );
+        assert!(Comment::new("No [commands] here").commands().is_empty());
+    }
+
+    #[test]
+    fn test_without_commands() {
+        let comment = Comment::new("Good. [%csl Ga4,Rb5] Better:[%clk 0:03:00]Nf3");
+        assert_eq!("Good. Better: Nf3", comment.without_commands().text());
+        assert_eq!("", Comment::new("[%clk 0:03:00]").without_commands().text());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!("{A comment}", Comment::new("A comment").to_string());
+        assert_eq!("{Braces {gone}", Comment::new("Braces {gone}").to_string());
+    }
+}
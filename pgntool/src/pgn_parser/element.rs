@@ -3,6 +3,7 @@ use crate::pgn_parser::move_number_indication::MoveNumberIndication;
 use crate::pgn_parser::numeric_annotation_glyph::NumericAnnotationGlyph;
 use crate::pgn_parser::san_move::SanMove;
 use crate::pgn_parser::GrammarNode;
+use std::fmt::{Display, Formatter};
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum Element {
@@ -11,6 +12,16 @@ pub enum Element {
     Annotation(NumericAnnotationGlyph),
 }
 
+impl Display for Element {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Element::MoveNumber(mni) => write!(f, "{}", mni),
+            Element::Move(san_move) => write!(f, "{}", san_move),
+            Element::Annotation(nag) => write!(f, "{}", nag),
+        }
+    }
+}
+
 /*
   <element> ::= <move-number-indication>
             ::= <SAN-move>
@@ -28,18 +39,20 @@ impl GrammarNode for Element {
     where
         Self: Sized,
     {
-        let (element, s) = if MoveNumberIndication::check_start(s) {
-            let (mni, remaining) = MoveNumberIndication::parse(s)?;
-            (Element::MoveNumber(mni), remaining)
-        } else if SanMove::check_start(s) {
-            let (sm, remaining) = SanMove::parse(s)?;
-            (Element::Move(sm), remaining)
-        } else if NumericAnnotationGlyph::check_start(s) {
-            let (nag, remaining) = NumericAnnotationGlyph::parse(s)?;
-            (Element::Annotation(nag), remaining)
-        } else {
-            return Err(PgnError::UnexpectedInput("Element", s.to_string()));
-        };
+        // "0000" is a null move, and "0-0" a (lenient) castle, not a move number.
+        let (element, s) =
+            if MoveNumberIndication::check_start(s) && !SanMove::starts_with_numeric_move(s) {
+                let (mni, remaining) = MoveNumberIndication::parse(s)?;
+                (Element::MoveNumber(mni), remaining)
+            } else if SanMove::check_start(s) {
+                let (sm, remaining) = SanMove::parse(s)?;
+                (Element::Move(sm), remaining)
+            } else if NumericAnnotationGlyph::check_start(s) {
+                let (nag, remaining) = NumericAnnotationGlyph::parse(s)?;
+                (Element::Annotation(nag), remaining)
+            } else {
+                return Err(PgnError::UnexpectedInput("Element", s.to_string()));
+            };
         Ok((element, s))
     }
 }
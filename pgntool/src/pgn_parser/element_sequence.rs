@@ -1,7 +1,10 @@
+use crate::pgn_parser::comment::Comment;
 use crate::pgn_parser::element::Element;
 use crate::pgn_parser::recursive_variation::RecursiveVariation;
+use crate::pgn_parser::san_move::SanMove;
 use crate::pgn_parser::GrammarNode;
 use crate::PgnError;
+use std::fmt::{Display, Formatter};
 
 #[derive(Debug, Default, PartialEq, Eq)]
 pub struct ElementSequence {
@@ -12,6 +15,96 @@ pub struct ElementSequence {
 pub enum SequenceMember {
     Move(Element),
     Variation(RecursiveVariation),
+    Comment(Comment),
+}
+
+impl ElementSequence {
+    pub(crate) fn new(sequence: Vec<SequenceMember>) -> Self {
+        ElementSequence { sequence }
+    }
+
+    pub fn members(&self) -> &[SequenceMember] {
+        &self.sequence
+    }
+
+    pub(crate) fn members_mut(&mut self) -> &mut Vec<SequenceMember> {
+        &mut self.sequence
+    }
+
+    // Returns the moves in this sequence, skipping move numbers, annotations and variations.
+    pub fn moves(&self) -> impl Iterator<Item = &SanMove> {
+        self.sequence.iter().filter_map(|member| match member {
+            SequenceMember::Move(Element::Move(san_move)) => Some(san_move),
+            _ => None,
+        })
+    }
+
+    // Returns true if there are comments anywhere in this sequence, including in variations.
+    pub fn has_comments(&self) -> bool {
+        self.sequence.iter().any(|member| match member {
+            SequenceMember::Comment(_) => true,
+            SequenceMember::Variation(variation) => variation.sequence().has_comments(),
+            SequenceMember::Move(_) => false,
+        })
+    }
+
+    // Appends the export format tokens for this sequence. Parentheses are attached to the
+    // first and last tokens of a variation, as in "(2. e5 e6)".
+    //
+    // A token starting with a newline continues the previous one on a new line, rather than
+    // after a space. Comments use these to keep their own line breaks.
+    pub(crate) fn export_tokens(&self, tokens: &mut Vec<String>) {
+        for member in &self.sequence {
+            match member {
+                SequenceMember::Move(element) => tokens.push(element.to_string()),
+                SequenceMember::Comment(comment) => comment_tokens(&comment.to_string(), tokens),
+                SequenceMember::Variation(variation) => {
+                    let mut inner = vec![];
+                    variation.sequence().export_tokens(&mut inner);
+                    if inner.is_empty() {
+                        tokens.push("()".to_string());
+                    } else {
+                        inner[0].insert(0, '(');
+                        // unwrap: inner is not empty.
+                        inner.last_mut().unwrap().push(')');
+                        tokens.extend(inner);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Splits a comment at each of its spaces, so that long comments can be wrapped. Joining the
+// tokens with spaces gives back the comment, with its line breaks, blank lines and indents.
+fn comment_tokens(text: &str, tokens: &mut Vec<String>) {
+    for (index, line) in text.split('\n').enumerate() {
+        let words = line.trim_start();
+        let indent = &line[..line.len() - words.len()];
+        for (word_index, word) in words.split(' ').enumerate() {
+            if word_index > 0 {
+                tokens.push(word.to_string());
+            } else if index > 0 {
+                tokens.push(format!("\n{}{}", indent, word));
+            } else {
+                tokens.push(format!("{}{}", indent, word));
+            }
+        }
+    }
+}
+
+impl Display for ElementSequence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut tokens = vec![];
+        self.export_tokens(&mut tokens);
+        for (index, token) in tokens.iter().enumerate() {
+            if index > 0 && !token.starts_with('\n') {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", token)?;
+        }
+        Ok(())
+    }
 }
 
 /*
@@ -21,7 +114,7 @@ pub enum SequenceMember {
 */
 impl GrammarNode for ElementSequence {
     fn check_start(s: &str) -> bool {
-        Element::check_start(s) || RecursiveVariation::check_start(s)
+        Element::check_start(s) || RecursiveVariation::check_start(s) || Comment::check_start(s)
     }
 
     fn parse_wrapped(s: &str) -> crate::Result<(Self, &str)>
@@ -44,6 +137,10 @@ impl GrammarNode for ElementSequence {
                 let (variation, remainder) = RecursiveVariation::parse(s)?;
                 sequence.push(SequenceMember::Variation(variation));
                 s = remainder.trim_start();
+            } else if Comment::check_start(s) {
+                let (comment, remainder) = Comment::parse(s)?;
+                sequence.push(SequenceMember::Comment(comment));
+                s = remainder.trim_start();
             } else {
                 break;
             }
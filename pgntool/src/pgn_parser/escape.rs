@@ -0,0 +1,93 @@
+use std::borrow::Cow;
+
+// The byte order mark some editors put at the start of UTF-8 files.
+const BYTE_ORDER_MARK: char = '\u{feff}';
+
+pub(crate) fn strip_byte_order_mark(s: &str) -> &str {
+    s.strip_prefix(BYTE_ORDER_MARK).unwrap_or(s)
+}
+
+pub(crate) fn is_escape_line(line: &str) -> bool {
+    line.starts_with('%')
+}
+
+/*
+  6: Escape mechanism
+
+  There is a special escape mechanism for PGN data. This mechanism is triggered by a percent sign
+  character ("%") appearing in the first column of a line; the data on the rest of the line is
+  ignored by publicly available PGN scanning software. This escape convention is intended for the
+  private use of software developers and researchers to embed non-PGN commands and data in PGN
+  streams.
+*/
+
+// Removes the escape lines, returning the rest of the text and each removed line (without its
+// line ending) with the offset in the rest of the text where it was.
+pub(crate) fn remove_escape_lines(s: &str) -> (Cow<'_, str>, Vec<(usize, String)>) {
+    if !s.starts_with('%') && !s.contains("\n%") {
+        return (Cow::Borrowed(s), vec![]);
+    }
+
+    let mut text = String::with_capacity(s.len());
+    let mut escape_lines = vec![];
+    for line in s.split_inclusive('\n') {
+        if is_escape_line(line) {
+            let line = line.trim_end_matches(&['\r', '\n'][..]);
+            escape_lines.push((text.len(), line.to_string()));
+        } else {
+            text.push_str(line);
+        }
+    }
+    (Cow::Owned(text), escape_lines)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pgn_parser::{parse_pgn, parse_pgn_with, ParseOptions};
+
+    #[test]
+    fn test_remove_escape_lines() {
+        let (text, lines) = remove_escape_lines("1. e4 e5 *\n");
+        assert!(matches!(text, Cow::Borrowed(_)));
+        assert!(lines.is_empty());
+
+        let (text, lines) = remove_escape_lines("%first\r\n1. e4 %not\n% second\ne5 *");
+        assert_eq!("1. e4 %not\ne5 *", text);
+        assert_eq!(
+            vec![(0, "%first".to_string()), (11, "% second".to_string())],
+            lines
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let pgn = "\u{feff}% header\r\n[Event\t\"One\"]\r\n\r\n1. e4\t; note\r\n\
+                   % inside\r\ne5 *\r\n\r\n%between\r\n1. d4 *\r\n% after\r\n";
+        let database = parse_pgn(pgn).unwrap();
+        assert_eq!(2, database.games().len());
+        assert!(database.games()[0].escape_lines().is_empty());
+        assert_eq!(
+            "[Event \"One\"]\n\n1. e4 {note} e5 *\n",
+            database.games()[0].to_string()
+        );
+
+        let options = ParseOptions {
+            keep_escape_lines: true,
+            ..ParseOptions::default()
+        };
+        let (database, _) = parse_pgn_with(pgn, options).unwrap();
+        assert_eq!(
+            vec!["% header", "% inside"],
+            database.games()[0].escape_lines()
+        );
+        assert_eq!(
+            vec!["%between", "% after"],
+            database.games()[1].escape_lines()
+        );
+        assert_eq!(
+            "%between\n% after\n1. d4 *\n",
+            database.games()[1].to_string()
+        );
+    }
+}
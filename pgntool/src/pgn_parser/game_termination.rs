@@ -1,6 +1,7 @@
 use crate::pgn_error::PgnError;
 use crate::pgn_parser::GrammarNode;
 use crate::Result;
+use std::fmt::{Display, Formatter};
 
 /*
    <game-termination> ::= 1-0
@@ -9,7 +10,7 @@ use crate::Result;
                       ::= *
 */
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
 pub enum GameTermination {
     WhiteWins,
     BlackWins,
@@ -17,6 +18,17 @@ pub enum GameTermination {
     Unfinished,
 }
 
+impl Display for GameTermination {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameTermination::WhiteWins => write!(f, "1-0"),
+            GameTermination::BlackWins => write!(f, "0-1"),
+            GameTermination::Tie => write!(f, "1/2-1/2"),
+            GameTermination::Unfinished => write!(f, "*"),
+        }
+    }
+}
+
 pub fn if_some_with<T>(pred: bool, f: impl FnOnce() -> T) -> Option<T> {
     if pred {
         Some(f())
@@ -53,12 +53,15 @@ trait GrammarNode {
         Self: Sized;
 }
 
+mod comment;
 mod element;
 mod element_sequence;
+mod escape;
 mod game_termination;
 mod move_number_indication;
 mod movetext_section;
 mod numeric_annotation_glyph;
+mod options;
 mod pgn_database;
 mod pgn_game;
 mod recursive_variation;
@@ -67,10 +70,58 @@ mod symbol;
 mod tag_pair;
 mod tag_section;
 
+pub use comment::Comment;
+pub use element::Element;
+pub use element_sequence::{ElementSequence, SequenceMember};
+pub(crate) use escape::is_escape_line;
+pub use game_termination::GameTermination;
+pub(crate) use move_number_indication::MoveNumberIndication;
+pub use movetext_section::MovetextSection;
+pub(crate) use numeric_annotation_glyph::NumericAnnotationGlyph;
+pub use options::ParseOptions;
 pub use pgn_database::PgnDatabase;
+pub use pgn_game::PgnGame;
+pub(crate) use recursive_variation::RecursiveVariation;
+pub use san_move::{
+    Check, File, Localized, Piece, PieceLetters, Rank, SanMove, SanMoveDetail, SanMoveType, Square,
+};
+pub use tag_pair::TagPair;
+pub use tag_section::TagSection;
 
 pub fn parse_pgn(s: impl AsRef<str>) -> Result<PgnDatabase> {
-    let s = s.as_ref().trim_start();
-    let (database, _) = PgnDatabase::parse(s)?;
+    let (database, _) = parse_pgn_with(s, ParseOptions::default())?;
     Ok(database)
 }
+
+// Parses with the given options, also returning warnings about any non-standard input which was
+// accepted.
+pub fn parse_pgn_with(
+    s: impl AsRef<str>,
+    options: ParseOptions,
+) -> Result<(PgnDatabase, Vec<String>)> {
+    let s = escape::strip_byte_order_mark(s.as_ref());
+    let (text, escape_lines) = escape::remove_escape_lines(s);
+    let trimmed = text.trim_start();
+    let (result, warnings) =
+        options::with_options(options, || PgnDatabase::parse_with_ends(trimmed));
+    let (mut database, ends) = result?;
+
+    if options.keep_escape_lines {
+        // Each escape line goes with the game it is in or before, or the last game if it is after
+        // them all.
+        let skipped = text.len() - trimmed.len();
+        let games = database.games_mut();
+        for (offset, line) in escape_lines {
+            let offset = offset.saturating_sub(skipped);
+            let index = ends.iter().position(|&end| offset < end);
+            let game = match index {
+                Some(index) => games.get_mut(index),
+                None => games.last_mut(),
+            };
+            if let Some(game) = game {
+                game.escape_lines_mut().push(line);
+            }
+        }
+    }
+    Ok((database, warnings))
+}
@@ -1,10 +1,40 @@
 use crate::pgn_parser::GrammarNode;
 use crate::PgnError;
-use itertools::put_back;
+use std::fmt::{Display, Formatter};
 
 #[derive(Debug, Eq, PartialEq)]
 pub struct MoveNumberIndication {
     number: u16,
+    // True if the number was followed by three or more periods, the export format's marker
+    // for the number of a black move.
+    continuation: bool,
+}
+
+impl MoveNumberIndication {
+    pub(crate) fn new(number: u16, continuation: bool) -> Self {
+        MoveNumberIndication {
+            number,
+            continuation,
+        }
+    }
+
+    pub fn number(&self) -> u16 {
+        self.number
+    }
+
+    pub fn is_continuation(&self) -> bool {
+        self.continuation
+    }
+}
+
+impl Display for MoveNumberIndication {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.continuation {
+            write!(f, "{}...", self.number)
+        } else {
+            write!(f, "{}.", self.number)
+        }
+    }
 }
 
 /*
@@ -23,41 +53,34 @@ impl GrammarNode for MoveNumberIndication {
     fn valid_follow(s: &str) -> bool {
         // MoveNumberIndication is ambiguous with GameTermination.
         // If the next non-space character could be a GameTermination, then it's not a valid follow.
-        !(s.trim().starts_with('/') || s.trim().starts_with('-'))
+        // "--" is a null move, as in "1... --", not the hyphen of a result.
+        let s = s.trim();
+        !(s.starts_with('/') || (s.starts_with('-') && !s.starts_with("--")))
     }
 
     fn parse_wrapped(s: &str) -> crate::Result<(Self, &str)>
     where
         Self: Sized,
     {
-        // TODO: is this enumerate necessary?
-        let mut iter = put_back(s.chars().enumerate());
-
-        let num_part: &str =
-            if let Some((idx, ch)) = iter.find(|(_, ch)| !ch.is_ascii_digit()) {
-                iter.put_back((idx, ch));
-                &s[..idx]
-            } else {
-                s
-            };
-
-        // TODO: deal with spaces between number and periods.
-        // TODO: deal with spaces between periods?
-        // TODO: clean up input?
-
-        let first_non_period = if let Some((idx, _)) = iter.find(|(_, ch)| *ch != '.') {
-            idx
-        } else {
-            s.len()
-        };
+        let digits = s.find(|ch: char| !ch.is_ascii_digit()).unwrap_or(s.len());
+        let number = s[..digits]
+            .parse()
+            .map_err(|e| PgnError::ParseIntError("Move number indicator", e))?;
+
+        // Spaces may separate the periods from the number and from each other, as in "56 ...".
+        let mut periods = 0;
+        let mut tail = &s[digits..];
+        while let Some(after_period) = tail.trim_start().strip_prefix('.') {
+            periods += 1;
+            tail = after_period;
+        }
 
         Ok((
             MoveNumberIndication {
-                number: num_part
-                    .parse()
-                    .map_err(|e| PgnError::ParseIntError("Move number indicator", e))?,
+                number,
+                continuation: periods >= 3,
             },
-            &s[first_non_period..],
+            tail,
         ))
     }
 }
@@ -70,8 +93,10 @@ mod test {
     macro_rules! mni_assert {
         ($num:literal, $tail:literal, $s:expr) => {
             assert_eq!(
-                (MoveNumberIndication { number: $num }, $tail),
-                MoveNumberIndication::parse($s).unwrap()
+                ($num, $tail),
+                MoveNumberIndication::parse($s)
+                    .map(|(mni, tail)| (mni.number, tail))
+                    .unwrap()
             )
         };
     }
@@ -84,10 +109,31 @@ mod test {
         mni_assert!(64, "NODOT", "64NODOT");
     }
 
-    //    #[test]
+    #[test]
+    fn test_continuation() {
+        let (mni, _) = MoveNumberIndication::parse("12. e4").unwrap();
+        assert!(!mni.is_continuation());
+        assert_eq!("12.", mni.to_string());
+
+        let (mni, _) = MoveNumberIndication::parse("12... e5").unwrap();
+        assert!(mni.is_continuation());
+        assert_eq!("12...", mni.to_string());
+    }
+
+    #[test]
     fn test_spaces() {
-        // TODO: make this work.
         mni_assert!(56, " WITHSPACE", "56.... WITHSPACE");
-        mni_assert!(65, " NODOTWITHSPACE", "64 NODOTWITHSPACE");
+        mni_assert!(64, " NODOTWITHSPACE", "64 NODOTWITHSPACE");
+        mni_assert!(56, " e5", "56 ... e5");
+        mni_assert!(7, " Nf3", "7 . Nf3");
+
+        let (mni, _) = MoveNumberIndication::parse("12 . . . e5").unwrap();
+        assert!(mni.is_continuation());
+    }
+
+    #[test]
+    fn test_null_move_follow() {
+        mni_assert!(12, " --", "12... --");
+        assert!(MoveNumberIndication::parse("1-0").is_err());
     }
 }
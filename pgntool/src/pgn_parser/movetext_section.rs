@@ -1,6 +1,38 @@
 use crate::pgn_parser::element_sequence::ElementSequence;
 use crate::pgn_parser::game_termination::GameTermination;
 use crate::pgn_parser::GrammarNode;
+use std::fmt::{Display, Formatter};
+
+// Export format lines are limited to 80 characters.
+const MAX_LINE_LENGTH: usize = 80;
+
+// Joins the tokens with spaces, breaking lines so that none is longer than MAX_LINE_LENGTH
+// (unless a single token is). A token starting with a newline, from a comment with line breaks,
+// is written as it is.
+fn wrap_tokens(tokens: &[String]) -> String {
+    let mut output = String::new();
+    let mut line_length = 0;
+    for token in tokens {
+        if let Some((_, last_line)) = token.rsplit_once('\n') {
+            output.push_str(token);
+            line_length = last_line.chars().count();
+            continue;
+        }
+        // Figurines take more than one byte, so the length is counted in characters.
+        let token_length = token.chars().count();
+        if line_length > 0 && line_length + 1 + token_length > MAX_LINE_LENGTH {
+            output.push('\n');
+            line_length = 0;
+        }
+        if line_length > 0 {
+            output.push(' ');
+            line_length += 1;
+        }
+        output.push_str(token);
+        line_length += token_length;
+    }
+    output
+}
 
 #[derive(Debug, Eq, PartialEq)]
 pub struct MovetextSection {
@@ -8,6 +40,37 @@ pub struct MovetextSection {
     game_termination: GameTermination,
 }
 
+impl MovetextSection {
+    pub fn element_sequence(&self) -> &ElementSequence {
+        &self.element_sequence
+    }
+
+    pub(crate) fn element_sequence_mut(&mut self) -> &mut ElementSequence {
+        &mut self.element_sequence
+    }
+
+    pub fn set_element_sequence(&mut self, element_sequence: ElementSequence) {
+        self.element_sequence = element_sequence;
+    }
+
+    pub fn game_termination(&self) -> GameTermination {
+        self.game_termination
+    }
+
+    pub fn set_game_termination(&mut self, game_termination: GameTermination) {
+        self.game_termination = game_termination;
+    }
+}
+
+impl Display for MovetextSection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut tokens = vec![];
+        self.element_sequence.export_tokens(&mut tokens);
+        tokens.push(self.game_termination.to_string());
+        write!(f, "{}", wrap_tokens(&tokens))
+    }
+}
+
 /*
   <movetext-section> ::= <element-sequence> <game-termination>
 */
@@ -44,4 +107,55 @@ mod test {
     fn test_with_termination() {
         MovetextSection::parse("e4 c6 0-1").unwrap();
     }
+
+    #[test]
+    fn test_display() {
+        let (movetext, _) =
+            MovetextSection::parse("1. e4 c6 2. d4 ( 2. e5 e6 ) 2... d5 $1 *").unwrap();
+        assert_eq!(
+            "1. e4 c6 2. d4 (2. e5 e6) 2... d5 $1 *",
+            movetext.to_string()
+        );
+
+        let (movetext, _) = MovetextSection::parse("1. e4 () 1-0").unwrap();
+        assert_eq!("1. e4 () 1-0", movetext.to_string());
+
+        let (movetext, _) =
+            MovetextSection::parse("{Start} 1. e4{Best by test} e5 ; rest\n 2. Nf3 *").unwrap();
+        assert_eq!(
+            "{Start} 1. e4 {Best by test} e5 {rest} 2. Nf3 *",
+            movetext.to_string()
+        );
+        assert!(movetext.element_sequence().has_comments());
+    }
+
+    #[test]
+    fn test_wrap() {
+        let tokens: Vec<String> = (0..30).map(|_| "Nf3".to_string()).collect();
+        let wrapped = wrap_tokens(&tokens);
+        assert!(wrapped.lines().all(|line| line.len() <= MAX_LINE_LENGTH));
+        assert_eq!(2, wrapped.lines().count());
+        assert_eq!(tokens.join(" "), wrapped.replace('\n', " "));
+    }
+
+    #[test]
+    fn test_comment_line_breaks() {
+        let (movetext, _) =
+            MovetextSection::parse("1. e4 {line one\n\n  - indented   spaced} e5 *").unwrap();
+        assert_eq!(
+            "1. e4 {line one\n\n  - indented   spaced} e5 *",
+            movetext.to_string()
+        );
+        assert_eq!(
+            "1. e4 {line one\n\n  - indented   spaced} e5",
+            movetext.element_sequence().to_string()
+        );
+
+        // Long lines of a comment are still wrapped at their spaces.
+        let long = vec!["word"; 30].join(" ");
+        let (movetext, _) = MovetextSection::parse(&format!("{{{}\nend}} *", long)).unwrap();
+        let text = movetext.to_string();
+        assert!(text.lines().all(|line| line.len() <= MAX_LINE_LENGTH));
+        assert!(text.ends_with("word\nend} *"));
+    }
 }
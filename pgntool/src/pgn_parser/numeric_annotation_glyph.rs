@@ -1,8 +1,22 @@
 use crate::pgn_parser::GrammarNode;
+use crate::PgnError;
+use std::fmt::{Display, Formatter};
 
-#[derive(Debug, Eq, PartialEq)]
-pub struct NumericAnnotationGlyph {}
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct NumericAnnotationGlyph(pub u8);
 
+impl Display for NumericAnnotationGlyph {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "${}", self.0)
+    }
+}
+
+/*
+  8.2.4: Movetext NAG (Numeric Annotation Glyph)
+
+  A NAG is a dollar sign ("$") immediately followed by one or more digit characters. NAG values
+  run from 0 to 255.
+*/
 impl GrammarNode for NumericAnnotationGlyph {
     fn check_start(s: &str) -> bool {
         s.starts_with('$')
@@ -12,6 +26,35 @@ impl GrammarNode for NumericAnnotationGlyph {
     where
         Self: Sized,
     {
-        todo!()
+        // Skip the '$'.
+        let s = &s[1..];
+        let end = s.find(|ch: char| !ch.is_ascii_digit()).unwrap_or(s.len());
+
+        let value = s[..end]
+            .parse()
+            .map_err(|e| PgnError::ParseIntError("Numeric annotation glyph", e))?;
+
+        Ok((NumericAnnotationGlyph(value), &s[end..]))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            (NumericAnnotationGlyph(1), " TAIL"),
+            NumericAnnotationGlyph::parse("$1 TAIL").unwrap()
+        );
+        assert_eq!(
+            (NumericAnnotationGlyph(255), ")"),
+            NumericAnnotationGlyph::parse("$255)").unwrap()
+        );
+        assert_eq!("$14", NumericAnnotationGlyph(14).to_string());
+
+        assert!(NumericAnnotationGlyph::parse("$").is_err());
+        assert!(NumericAnnotationGlyph::parse("$256").is_err());
     }
 }
@@ -0,0 +1,112 @@
+use crate::pgn_parser::PieceLetters;
+use std::cell::{Cell, RefCell};
+
+// How forgiving the parser is of input which doesn't follow the standard.
+#[derive(Debug, Default, Eq, PartialEq, Copy, Clone)]
+pub struct ParseOptions {
+    // Accept common non-standard notation, such as long algebraic moves or castling with zeros,
+    // recording a warning for each use. Parsing is otherwise strict, and rejects it.
+    pub lenient: bool,
+    // The piece letters the moves are written with.
+    pub letters: PieceLetters,
+    // Keep the escape lines (lines starting with '%') with the games, so they are written out
+    // again. Otherwise they are dropped, as the standard says.
+    pub keep_escape_lines: bool,
+}
+
+impl ParseOptions {
+    pub fn lenient() -> Self {
+        ParseOptions {
+            lenient: true,
+            ..ParseOptions::default()
+        }
+    }
+}
+
+// The grammar nodes are parsed by plain functions, so the options for the current parse, and the
+// warnings it produces, are kept here rather than passed down through every node.
+thread_local! {
+    static OPTIONS: Cell<ParseOptions> = const {
+        Cell::new(ParseOptions {
+            lenient: false,
+            letters: PieceLetters::ENGLISH,
+            keep_escape_lines: false,
+        })
+    };
+    static WARNINGS: RefCell<Vec<String>> = const { RefCell::new(vec![]) };
+}
+
+pub(crate) fn options() -> ParseOptions {
+    OPTIONS.with(|options| options.get())
+}
+
+// Records something which was accepted, but isn't standard.
+pub(crate) fn warn(message: String) {
+    WARNINGS.with(|warnings| warnings.borrow_mut().push(message));
+}
+
+// Runs a parse with the given options, returning its result and warnings.
+pub(crate) fn with_options<T>(
+    options: ParseOptions,
+    parse: impl FnOnce() -> T,
+) -> (T, Vec<String>) {
+    let previous_options = OPTIONS.with(|current| current.replace(options));
+    let previous_warnings = WARNINGS.with(|warnings| warnings.replace(vec![]));
+
+    let result = parse();
+
+    OPTIONS.with(|current| current.set(previous_options));
+    let warnings = WARNINGS.with(|warnings| warnings.replace(previous_warnings));
+    (result, warnings)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pgn_parser::{parse_pgn, parse_pgn_with};
+    use crate::replay::Mainline;
+
+    #[test]
+    fn test_lenient() {
+        let pgn = "1. e2-e4 e7e5 2. Ng1-f3 *";
+        assert!(parse_pgn(pgn).is_err());
+
+        let (database, warnings) = parse_pgn_with(pgn, ParseOptions::lenient()).unwrap();
+        assert_eq!(
+            vec![
+                "long algebraic move e2-e4",
+                "long algebraic move e7e5",
+                "long algebraic move Ng1-f3"
+            ],
+            warnings
+        );
+
+        let mainline = Mainline::replay(&database.games()[0]).unwrap();
+        assert_eq!(
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2",
+            mainline.final_position().to_fen()
+        );
+    }
+
+    #[test]
+    fn test_lenient_normalization() {
+        let pgn = "1. e4 d5 2. e5 f5 3. exf6 e.p. nc6 4. nf3 e5 5. Be2 Bd6 6. 0-0 *";
+        assert!(parse_pgn(pgn).is_err());
+
+        let (database, warnings) = parse_pgn_with(pgn, ParseOptions::lenient()).unwrap();
+        assert_eq!(
+            vec![
+                "en passant suffix e.p.",
+                "lowercase piece letter nc6",
+                "lowercase piece letter nf3",
+                "castling with zeros 0-0"
+            ],
+            warnings
+        );
+        assert_eq!(
+            "1. e4 d5 2. e5 f5 3. exf6 Nc6 4. Nf3 e5 5. Be2 Bd6 6. O-O *",
+            database.games()[0].movetext().to_string()
+        );
+        assert!(Mainline::replay(&database.games()[0]).is_ok());
+    }
+}
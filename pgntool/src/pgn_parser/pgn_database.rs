@@ -1,11 +1,53 @@
 use crate::pgn_parser::pgn_game::PgnGame;
 use crate::pgn_parser::GrammarNode;
+use std::fmt::{Display, Formatter};
 
 #[derive(Debug)]
 pub struct PgnDatabase {
     pgn_games: Vec<PgnGame>,
 }
 
+impl PgnDatabase {
+    pub fn games(&self) -> &[PgnGame] {
+        &self.pgn_games
+    }
+
+    pub fn games_mut(&mut self) -> &mut [PgnGame] {
+        &mut self.pgn_games
+    }
+
+    pub fn into_games(self) -> Vec<PgnGame> {
+        self.pgn_games
+    }
+
+    // Parses the games, also returning the offset in `s` of the end of each one.
+    pub(crate) fn parse_with_ends(s: &str) -> crate::Result<(Self, Vec<usize>)> {
+        let mut pgn_games: Vec<PgnGame> = Default::default();
+        let mut ends = vec![];
+        let mut remaining = s;
+        while PgnGame::check_start(remaining) {
+            let (game, remainder) = PgnGame::parse(remaining)?;
+            ends.push(s.len() - remainder.len());
+            remaining = remainder.trim_start();
+            pgn_games.push(game)
+        }
+        Ok((PgnDatabase { pgn_games }, ends))
+    }
+}
+
+// Games are separated by an empty line.
+impl Display for PgnDatabase {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (index, game) in self.pgn_games.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", game)?;
+        }
+        Ok(())
+    }
+}
+
 /*
  <PGN-database> ::= <PGN-game> <PGN-database>
                     <empty>
@@ -19,13 +61,20 @@ impl GrammarNode for PgnDatabase {
     where
         Self: Sized,
     {
-        let mut pgn_games: Vec<PgnGame> = Default::default();
-        let mut s = s;
-        while PgnGame::check_start(s) {
-            let (game, remainder) = PgnGame::parse(s)?;
-            s = remainder.trim_start();
-            pgn_games.push(game)
-        }
-        Ok((PgnDatabase { pgn_games }, s))
+        let (database, ends) = PgnDatabase::parse_with_ends(s)?;
+        let end = ends.last().copied().unwrap_or(0);
+        Ok((database, s[end..].trim_start()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let pgn = include_str!("../../../pgn_files/game.pgn");
+        let (database, _) = PgnDatabase::parse(pgn).unwrap();
+        assert_eq!(pgn, database.to_string());
     }
 }
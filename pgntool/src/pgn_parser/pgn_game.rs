@@ -1,13 +1,65 @@
 use crate::pgn_parser::movetext_section::MovetextSection;
 use crate::pgn_parser::tag_section::TagSection;
 use crate::pgn_parser::GrammarNode;
+use std::fmt::{Display, Formatter};
 
 #[derive(Debug)]
 pub struct PgnGame {
+    // Escape lines from the input, kept if the parse options ask for them.
+    escape_lines: Vec<String>,
     tag_section: TagSection,
     movetext_section: MovetextSection,
 }
 
+impl PgnGame {
+    pub fn escape_lines(&self) -> &[String] {
+        &self.escape_lines
+    }
+
+    pub fn escape_lines_mut(&mut self) -> &mut Vec<String> {
+        &mut self.escape_lines
+    }
+
+    pub fn tags(&self) -> &TagSection {
+        &self.tag_section
+    }
+
+    // Returns the value of the named tag, if the game has one.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tag_section.get(name)
+    }
+
+    pub fn tags_mut(&mut self) -> &mut TagSection {
+        &mut self.tag_section
+    }
+
+    pub fn movetext(&self) -> &MovetextSection {
+        &self.movetext_section
+    }
+
+    pub fn movetext_mut(&mut self) -> &mut MovetextSection {
+        &mut self.movetext_section
+    }
+}
+
+/*
+  8.1: Export format
+
+  The tag section, one tag pair per line, then an empty line, then the movetext. Any escape lines
+  come first.
+*/
+impl Display for PgnGame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for line in &self.escape_lines {
+            writeln!(f, "{}", line)?;
+        }
+        if !self.tag_section.pairs().is_empty() {
+            writeln!(f, "{}", self.tag_section)?;
+        }
+        writeln!(f, "{}", self.movetext_section)
+    }
+}
+
 /*
   <PGN-game> ::= <tag-section> <movetext-section>
 */
@@ -32,6 +84,7 @@ impl GrammarNode for PgnGame {
 
         Ok((
             PgnGame {
+                escape_lines: vec![],
                 tag_section,
                 movetext_section,
             },
@@ -7,6 +7,20 @@ pub struct RecursiveVariation {
     sequence: ElementSequence,
 }
 
+impl RecursiveVariation {
+    pub(crate) fn new(sequence: ElementSequence) -> Self {
+        RecursiveVariation { sequence }
+    }
+
+    pub fn sequence(&self) -> &ElementSequence {
+        &self.sequence
+    }
+
+    pub(crate) fn sequence_mut(&mut self) -> &mut ElementSequence {
+        &mut self.sequence
+    }
+}
+
 /*
   <recursive-variation> ::= ( <element-sequence> )
 */
@@ -1,7 +1,7 @@
 use crate::pgn_error::PgnError::{UnexpectedEOF, UnexpectedInput};
 use crate::pgn_parser::GrammarNode;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
 pub enum Check {
     Check,
     Mate,
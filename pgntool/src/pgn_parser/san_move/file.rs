@@ -4,7 +4,7 @@ use crate::PgnError;
 use std::convert::TryFrom;
 use std::fmt::{Debug, Formatter};
 
-#[derive(Eq, PartialEq, Copy, Clone)]
+#[derive(Eq, PartialEq, Copy, Clone, Hash)]
 pub struct File(pub u8);
 
 impl Debug for File {
@@ -13,6 +13,12 @@ impl Debug for File {
     }
 }
 
+impl File {
+    pub fn to_char(self) -> char {
+        (b'a' + self.0 - 1) as char
+    }
+}
+
 impl TryFrom<char> for File {
     type Error = PgnError;
 
@@ -1,65 +1,442 @@
-use file::File;
-use piece::Piece;
-use rank::Rank;
-use square::Square;
+pub use check::Check;
+pub use file::File;
+pub use piece::Piece;
+pub use piece_letters::{Localized, PieceLetters};
+pub use rank::Rank;
+pub use square::Square;
 use toolpack::trytools::if_some;
 
 use crate::pgn_error::PgnError;
 use crate::pgn_error::PgnError::UnexpectedInput;
+use crate::pgn_parser::options::{options, warn};
 use crate::pgn_parser::san_move::capture::Capture;
-use crate::pgn_parser::san_move::check::Check;
+use crate::pgn_parser::san_move::piece_letters::export_letters;
 use crate::pgn_parser::san_move::piecespec::PieceSpec;
 use crate::pgn_parser::san_move::promotion::Promotion;
 use crate::pgn_parser::GrammarNode;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
 mod capture;
 mod check;
 mod disambiguation;
 mod file;
 mod piece;
+mod piece_letters;
 mod piecespec;
 mod promotion;
 mod rank;
 mod square;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Clone)]
 pub struct SanMove {
-    move_type: SanMoveType,
-    check: Check,
+    pub move_type: SanMoveType,
+    pub check: Check,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Clone)]
 pub enum SanMoveType {
     Move(SanMoveDetail),
     LongCastle,
     ShortCastle,
+    // A null move passes the turn without moving a piece. It isn't part of the standard, but is
+    // used in analysis.
+    Null,
+    // A move given only by its squares, as in UCI ("e2e4", "e7e8q"). Only accepted by lenient
+    // parsing.
+    Coordinate {
+        from: Square,
+        to: Square,
+        promote: Option<Piece>,
+    },
+    // A piece from the player's hand put on an empty square, as in Crazyhouse ("N@f3", "P@e6").
+    Drop {
+        piece: Piece,
+        destination: Square,
+    },
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Clone)]
 pub struct SanMoveDetail {
-    piece: Piece,
-    destination: Square,
-    from_file: Option<File>,
-    from_rank: Option<Rank>,
-    capture: bool,
-    promote: Option<Piece>,
+    pub piece: Piece,
+    pub destination: Square,
+    pub from_file: Option<File>,
+    pub from_rank: Option<Rank>,
+    pub capture: bool,
+    pub promote: Option<Piece>,
+}
+
+// The common spellings of a null move. "--" is the one used for export.
+const NULL_MOVES: &[&str] = &["--", "Z0", "0000", "@@@@"];
+
+// Characters which end a move token, as they start or end another part of the movetext.
+const TOKEN_END: &[char] = &['{', '}', '(', ')', '[', ']', ';', '$', '!', '?'];
+
+fn is_promotion_letter(ch: char) -> bool {
+    matches!(
+        options().letters.piece(ch),
+        Some(Piece::Queen | Piece::Rook | Piece::Bishop | Piece::Knight)
+    )
+}
+
+// Lenient parsing accepts piece letters in lowercase, except for those which are also files: in
+// English "bxc3" could be a pawn capture.
+fn starts_with_lowercase_piece(s: &str) -> bool {
+    s.chars().next().is_some_and(|ch| {
+        ch.is_ascii_lowercase()
+            && !('a'..='h').contains(&ch)
+            && options().letters.piece(ch.to_ascii_uppercase()).is_some()
+    })
+}
+
+// The length of the move token at the start of the string.
+fn token_length(s: &str) -> usize {
+    let token_end = |s: &str| {
+        s.find(|ch: char| ch.is_whitespace() || TOKEN_END.contains(&ch))
+            .unwrap_or(s.len())
+    };
+
+    let mut length = token_end(s);
+    // A promotion in parentheses, as in "e8(Q)", is part of the move rather than a variation.
+    let mut tail = s[length..].chars();
+    if let (Some('('), Some(piece), Some(')')) = (tail.next(), tail.next(), tail.next()) {
+        if is_promotion_letter(piece) {
+            length += 2 + piece.len_utf8();
+            length += token_end(&s[length..]);
+        }
+    }
+    length
 }
 
 impl SanMove {
+    // Returns the tail after a null move at the start of the string, if there is one.
+    fn strip_null_move(s: &str) -> Option<&str> {
+        NULL_MOVES
+            .iter()
+            .find_map(|null_move| s.strip_prefix(null_move))
+            // "0000" must not be the start of a longer number.
+            .filter(|tail| !tail.starts_with(|ch: char| ch.is_ascii_digit()))
+    }
+
+    pub(crate) fn starts_with_null_move(s: &str) -> bool {
+        SanMove::strip_null_move(s).is_some()
+    }
+
+    fn starts_with_zero_castle(s: &str) -> bool {
+        s.starts_with("0-0")
+    }
+
+    // True if the string starts with a move which could be mistaken for a move number.
+    pub(crate) fn starts_with_numeric_move(s: &str) -> bool {
+        SanMove::starts_with_null_move(s)
+            || (options().lenient && SanMove::starts_with_zero_castle(s))
+    }
+
+    // Rewrites the common non-standard spellings of a move ("nf3", "e8Q", "e4:d5", "Qh7++",
+    // "exd6ep") as standard SAN. Returns the rewritten move and a warning for each change, or
+    // None if the move needed no changes.
+    fn normalize_sloppy(token: &str) -> Option<(String, Vec<String>)> {
+        let mut warnings = vec![];
+
+        let check_start = token.trim_end_matches(&['+', '#'][..]).len();
+        let (mut text, mut check) = (token[..check_start].to_string(), &token[check_start..]);
+        if check == "++" {
+            warnings.push(format!("'++' for mate {}", token));
+            check = "#";
+        }
+
+        for suffix in &["e.p.", "ep"] {
+            if text.len() > suffix.len() && text.ends_with(suffix) {
+                warnings.push(format!("en passant suffix {}", token));
+                text.truncate(text.len() - suffix.len());
+                break;
+            }
+        }
+
+        if text.contains(':') {
+            warnings.push(format!("':' capture {}", token));
+            text = text.replace(':', "x");
+        }
+
+        // "e8Q", "e8(Q)" and "e8/Q" all mean "e8=Q".
+        let mut reversed = text.chars().rev();
+        let promotion = match (reversed.next(), reversed.next(), reversed.next()) {
+            (Some(')'), Some(piece), Some('(')) if is_promotion_letter(piece) => Some((piece, 3)),
+            (Some(piece), Some('/'), _) if is_promotion_letter(piece) => Some((piece, 2)),
+            (Some(piece), Some('1' | '8'), _) if is_promotion_letter(piece) => Some((piece, 1)),
+            _ => None,
+        };
+        if let Some((piece, length)) = promotion {
+            warnings.push(format!("promotion without '=' {}", token));
+            let written: usize = text.chars().rev().take(length).map(char::len_utf8).sum();
+            text = format!("{}={}", &text[..text.len() - written], piece);
+        }
+
+        if starts_with_lowercase_piece(&text) {
+            warnings.push(format!("lowercase piece letter {}", token));
+            text = text[..1].to_ascii_uppercase() + &text[1..];
+        }
+
+        if warnings.is_empty() {
+            None
+        } else {
+            Some((text + check, warnings))
+        }
+    }
+
+    // Skips an en passant marker written after the move, as in "exd6 e.p.".
+    fn strip_separate_en_passant(s: &str) -> &str {
+        let trimmed = s.trim_start_matches(&[' ', '\t'][..]);
+        for marker in &["e.p.", "ep"] {
+            if let Some(tail) = trimmed.strip_prefix(marker) {
+                if tail.is_empty()
+                    || tail.starts_with(|ch: char| ch.is_whitespace() || TOKEN_END.contains(&ch))
+                {
+                    warn(format!("en passant suffix {}", marker));
+                    return tail;
+                }
+            }
+        }
+        s
+    }
+
+    // Parses long algebraic notation ("e2-e4", "Ng1-f3", "e7xd8=Q") and UCI coordinates
+    // ("e2e4", "e7e8q"), which give the from square in full. Returns None for anything which is
+    // also standard SAN, such as "Ng1f3", so that it is parsed without a warning.
+    fn parse_long_algebraic(s: &str) -> Option<(SanMove, &str)> {
+        let (piece, s) = Piece::parse(s)
+            .ok()
+            .filter(|(piece, _)| *piece != Piece::Pawn)
+            .map(|(piece, s)| (Some(piece), s))
+            .unwrap_or((None, s));
+
+        let (from, s) = if_some(Square::check_start(s)).and_then(|_| Square::parse(s).ok())?;
+        let (separator, s) = match s.chars().next() {
+            Some(ch @ '-') | Some(ch @ 'x') => (Some(ch), &s[1..]),
+            _ => (None, s),
+        };
+        let (to, s) = if_some(Square::check_start(s)).and_then(|_| Square::parse(s).ok())?;
+
+        let (move_type, s) = match (piece, separator) {
+            (Some(_), Some('x')) | (Some(_), None) => return None,
+            (None, None) => {
+                let (promote, s) = match s.chars().next() {
+                    Some('q') => (Some(Piece::Queen), &s[1..]),
+                    Some('r') => (Some(Piece::Rook), &s[1..]),
+                    Some('b') => (Some(Piece::Bishop), &s[1..]),
+                    Some('n') => (Some(Piece::Knight), &s[1..]),
+                    _ => (None, s),
+                };
+                (SanMoveType::Coordinate { from, to, promote }, s)
+            }
+            (piece, separator) => {
+                let (promote, s) = if Promotion::check_start(s) {
+                    Promotion::parse(s)
+                        .map(|(p, s)| (Some(Piece::from(p)), s))
+                        .ok()?
+                } else {
+                    (None, s)
+                };
+                let detail = SanMoveDetail {
+                    piece: piece.unwrap_or(Piece::Pawn),
+                    destination: to,
+                    from_file: Some(from.file),
+                    from_rank: Some(from.rank),
+                    capture: separator == Some('x'),
+                    promote,
+                };
+                (SanMoveType::Move(detail), s)
+            }
+        };
+
+        let (check, s) = Check::parse(s).unwrap_or((Check::None, s));
+        Some((SanMove { move_type, check }, s))
+    }
+
+    // Parses a move written in standard SAN, or as a null move or long algebraic move.
+    fn parse_standard(s: &str) -> crate::Result<(SanMove, &str)> {
+        if let Some(s) = SanMove::strip_null_move(s) {
+            let (check, s) = Check::parse(s).unwrap_or((Check::None, s));
+            return Ok((
+                SanMove {
+                    move_type: SanMoveType::Null,
+                    check,
+                },
+                s,
+            ));
+        }
+
+        if options().lenient {
+            if let Some((san_move, remaining)) = SanMove::parse_long_algebraic(s) {
+                let written = &s[..s.len() - remaining.len()];
+                warn(format!("long algebraic move {}", written));
+                return Ok((san_move, remaining));
+            }
+        }
+
+        if let Ok((castle, check, remaining)) = SanMove::parse_castle(s) {
+            return Ok((
+                SanMove {
+                    move_type: castle,
+                    check,
+                },
+                remaining,
+            ));
+        }
+
+        if let Some((san_move, remaining)) = SanMove::parse_drop(s) {
+            return Ok((san_move, remaining));
+        }
+
+        let (piecespec, s) = PieceSpec::parse(s).unwrap_or((PieceSpec::pawn(), s));
+
+        let (capture, s) = if_some(Capture::check_start(s))
+            .and_then(|_| Capture::parse(s).ok())
+            .map(|(_, remaining)| (true, remaining))
+            .unwrap_or((false, s));
+
+        let (destination, s) = if Square::check_start(s) {
+            Square::parse(s)?
+        } else {
+            return Err(UnexpectedInput("Destination square", s.to_string()));
+        };
+
+        let (promotion, s) = if Promotion::check_start(s) {
+            Promotion::parse(s).map(|(p, s)| (Some(Piece::from(p)), s))?
+        } else {
+            (None, s)
+        };
+
+        let (check, s) = if_some(Check::check_start(s))
+            .and_then(|_| Check::parse(s).ok())
+            .unwrap_or((Check::None, s));
+
+        let from_file = piecespec.disambiguation.file();
+        let from_rank = piecespec.disambiguation.rank();
+
+        let detail = SanMoveDetail {
+            piece: piecespec.piece,
+            destination,
+            from_file,
+            from_rank,
+            capture,
+            promote: promotion,
+        };
+
+        Ok((
+            SanMove {
+                move_type: SanMoveType::Move(detail),
+                check,
+            },
+            s,
+        ))
+    }
+
+    // Parses a drop, which is the piece letter (optional for a pawn), '@' and the square.
+    fn parse_drop(s: &str) -> Option<(SanMove, &str)> {
+        let (piece, s) = Piece::parse(s).unwrap_or((Piece::Pawn, s));
+        let s = s.strip_prefix('@')?;
+        let (destination, s) =
+            if_some(Square::check_start(s)).and_then(|_| Square::parse(s).ok())?;
+        let (check, s) = Check::parse(s).unwrap_or((Check::None, s));
+        Some((
+            SanMove {
+                move_type: SanMoveType::Drop { piece, destination },
+                check,
+            },
+            s,
+        ))
+    }
+
     fn parse_castle(s: &str) -> crate::Result<(SanMoveType, Check, &str)> {
+        // Lenient parsing also accepts castling written with zeros.
+        let zeros = options().lenient && SanMove::starts_with_zero_castle(s);
+        let (long, short) = if zeros {
+            ("0-0-0", "0-0")
+        } else {
+            ("O-O-O", "O-O")
+        };
+
         // Check for Long Castle first because short castle is a prefix of long castle.
-        if let Some(s) = s.strip_prefix("O-O-O") {
-            let (check, s) = Check::parse(s).unwrap_or((Check::None, s));
-            Ok((SanMoveType::LongCastle, check, s))
-        } else if let Some(s) = s.strip_prefix("O-O") {
-            let (check, s) = Check::parse(s).unwrap_or((Check::None, s));
-            Ok((SanMoveType::ShortCastle, check, s))
+        let (move_type, castle, s) = if let Some(s) = s.strip_prefix(long) {
+            (SanMoveType::LongCastle, long, s)
+        } else if let Some(s) = s.strip_prefix(short) {
+            (SanMoveType::ShortCastle, short, s)
         } else {
-            Err(PgnError::UnexpectedInput("Castle", s.to_string()))
+            return Err(PgnError::UnexpectedInput("Castle", s.to_string()));
+        };
+        if zeros {
+            warn(format!("castling with zeros {}", castle));
+        }
+
+        let (check, s) = Check::parse(s).unwrap_or((Check::None, s));
+        Ok((move_type, check, s))
+    }
+}
+
+impl FromStr for SanMove {
+    type Err = PgnError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !SanMove::check_start(s) {
+            return Err(UnexpectedInput("SanMove", s.to_string()));
+        }
+        match SanMove::parse(s)? {
+            (san_move, "") => Ok(san_move),
+            (_, tail) => Err(UnexpectedInput("SanMove", tail.to_string())),
+        }
+    }
+}
+
+impl Display for Check {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Check::Check => write!(f, "+"),
+            Check::Mate => write!(f, "#"),
+            Check::None => Ok(()),
         }
     }
 }
 
+impl Display for SanMove {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.move_type {
+            SanMoveType::LongCastle => write!(f, "O-O-O")?,
+            SanMoveType::ShortCastle => write!(f, "O-O")?,
+            SanMoveType::Null => write!(f, "--")?,
+            SanMoveType::Coordinate { from, to, promote } => {
+                write!(f, "{}{}", from, to)?;
+                if let Some(promote) = promote {
+                    write!(f, "{}", promote.to_char().to_ascii_lowercase())?;
+                }
+            }
+            SanMoveType::Drop { piece, destination } => {
+                write!(f, "{}@{}", export_letters().letter(*piece), destination)?;
+            }
+            SanMoveType::Move(detail) => {
+                let letters = export_letters();
+                if detail.piece != Piece::Pawn {
+                    write!(f, "{}", letters.letter(detail.piece))?;
+                }
+                if let Some(file) = detail.from_file {
+                    write!(f, "{}", file.to_char())?;
+                }
+                if let Some(rank) = detail.from_rank {
+                    write!(f, "{}", rank.to_char())?;
+                }
+                if detail.capture {
+                    write!(f, "x")?;
+                }
+                write!(f, "{}", detail.destination)?;
+                if let Some(promote) = detail.promote {
+                    write!(f, "={}", letters.letter(promote))?;
+                }
+            }
+        }
+        write!(f, "{}", self.check)
+    }
+}
+
 /*
   8.2.3: Movetext SAN (Standard Algebraic Notation)
 
@@ -94,6 +471,10 @@ impl SanMove {
     Qa6xb7#
     fxg1=Q+
 
+  Drops, which aren't part of the standard but are used for Crazyhouse:
+    N@f3
+    P@e6+
+
   A candidate grammar for a SAN move:
     SANMOVE ::= <PIECESPEC><CAPTURE><DESTINATION><PROMOTION><CHECK>
     PIECESPEC ::= <PIECE><DISAMBIGUATION>
@@ -125,64 +506,30 @@ impl GrammarNode for SanMove {
             || Capture::check_start(s)
             || Square::check_start(s)
             || s.starts_with('O')
+            || s.starts_with('@')
+            || SanMove::starts_with_null_move(s)
+            || (options().lenient
+                && (SanMove::starts_with_zero_castle(s) || starts_with_lowercase_piece(s)))
     }
 
     fn parse_wrapped(s: &str) -> crate::Result<(Self, &str)>
     where
         Self: Sized,
     {
-        if let Ok((castle, check, remaining)) = SanMove::parse_castle(s) {
-            return Ok((
-                SanMove {
-                    move_type: castle,
-                    check,
-                },
-                remaining,
-            ));
+        if !options().lenient {
+            return SanMove::parse_standard(s);
         }
 
-        let (piecespec, s) = PieceSpec::parse(s).unwrap_or((PieceSpec::pawn(), s));
-
-        let (capture, s) = if_some(Capture::check_start(s))
-            .and_then(|_| Capture::parse(s).ok())
-            .map(|(_, remaining)| (true, remaining))
-            .unwrap_or((false, s));
-
-        let (destination, s) = if Square::check_start(s) {
-            Square::parse(s)?
-        } else {
-            return Err(UnexpectedInput("Destination square", s.to_string()));
+        let length = token_length(s);
+        let (san_move, s) = match SanMove::normalize_sloppy(&s[..length]) {
+            Some((text, warnings)) => {
+                let san_move = text.parse()?;
+                warnings.into_iter().for_each(warn);
+                (san_move, &s[length..])
+            }
+            None => SanMove::parse_standard(s)?,
         };
-
-        let (promotion, s) = if Promotion::check_start(s) {
-            Promotion::parse(s).map(|(p, s)| (Some(Piece::from(p)), s))?
-        } else {
-            (None, s)
-        };
-
-        let (check, s) = if_some(Check::check_start(s))
-            .and_then(|_| Check::parse(s).ok())
-            .unwrap_or((Check::None, s));
-
-        let from_file = piecespec.disambiguation.file();
-        let from_rank = piecespec.disambiguation.rank();
-
-        let detail = SanMoveDetail {
-            piece: piecespec.piece,
-            destination,
-            from_file,
-            from_rank,
-            capture,
-            promote: promotion,
-        };
-
-        Ok((
-            SanMove {
-                move_type: SanMoveType::Move(detail),
-                check,
-            },
-            s,
-        ))
+        Ok((san_move, SanMove::strip_separate_en_passant(s)))
     }
 }
 
@@ -211,7 +558,9 @@ mod test_macros {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::pgn_parser::options::with_options;
     use crate::pgn_parser::san_move::check::Check;
+    use crate::pgn_parser::ParseOptions;
     use std::convert::TryFrom;
     use toolpack::tupl::first;
 
@@ -422,4 +771,111 @@ mod test {
         assert_promotion!("P", "g8", false, None, "Q", "g8=Q");
         assert_promotion!("P", "e1", true, File::try_from('d').ok(), "N", "dxe1=N");
     }
+
+    #[test]
+    fn test_display() {
+        for san in &[
+            "e4", "dxe5", "Qxf6", "Nd7#", "Rhe8", "N3e1", "Ba3c5", "g8=Q", "fxg1=Q+", "O-O",
+            "O-O-O+",
+        ] {
+            assert_eq!(*san, SanMove::parse(san).unwrap().0.to_string());
+        }
+    }
+
+    #[test]
+    fn test_null() {
+        for null_move in &["--", "Z0", "0000", "@@@@"] {
+            let input = format!("{} TAIL", null_move);
+            let (san, tail) = SanMove::parse(&input).unwrap();
+            assert_eq!(SanMoveType::Null, san.move_type);
+            assert_eq!(" TAIL", tail);
+            assert_eq!("--", san.to_string());
+        }
+        assert!(!SanMove::starts_with_null_move("00000"));
+    }
+
+    #[test]
+    fn test_long_algebraic() {
+        for (input, expected) in &[
+            ("e2-e4", "e2e4"),
+            ("Ng1-f3+", "Ng1f3+"),
+            ("e7xd8=Q", "e7xd8=Q"),
+            ("e2e4", "e2e4"),
+            ("e7e8q", "e7e8q"),
+        ] {
+            let (san_move, tail) = SanMove::parse_long_algebraic(input).unwrap();
+            assert_eq!(*expected, san_move.to_string());
+            assert_eq!("", tail);
+        }
+
+        // Standard SAN is left to the normal parse.
+        assert!(SanMove::parse_long_algebraic("Ng1f3").is_none());
+        assert!(SanMove::parse_long_algebraic("Nf3").is_none());
+        assert!(SanMove::parse_long_algebraic("e4").is_none());
+    }
+
+    #[test]
+    fn test_sloppy() {
+        for (input, expected, warning) in &[
+            ("0-0", "O-O", "castling with zeros 0-0"),
+            ("0-0-0+", "O-O-O+", "castling with zeros 0-0-0"),
+            ("nf3", "Nf3", "lowercase piece letter nf3"),
+            ("qxe5", "Qxe5", "lowercase piece letter qxe5"),
+            ("e8Q", "e8=Q", "promotion without '=' e8Q"),
+            ("e8(Q)", "e8=Q", "promotion without '=' e8(Q)"),
+            ("dxe1/N+", "dxe1=N+", "promotion without '=' dxe1/N+"),
+            ("exd6ep", "exd6", "en passant suffix exd6ep"),
+            ("exd6e.p.", "exd6", "en passant suffix exd6e.p."),
+            ("N:e5", "Nxe5", "':' capture N:e5"),
+            ("Qh7++", "Qh7#", "'++' for mate Qh7++"),
+        ] {
+            let (parsed, warnings) =
+                with_options(ParseOptions::lenient(), || SanMove::parse(input).unwrap());
+            assert_eq!((*expected, ""), (parsed.0.to_string().as_str(), parsed.1));
+            assert_eq!(vec![*warning], warnings);
+
+            // Strict parsing rejects them.
+            assert!(input.parse::<SanMove>().is_err());
+        }
+    }
+
+    #[test]
+    fn test_drop() {
+        assert_eq!(
+            (
+                SanMove {
+                    move_type: SanMoveType::Drop {
+                        piece: Piece::Knight,
+                        destination: "f3".parse().unwrap(),
+                    },
+                    check: Check::Check,
+                },
+                " TAIL"
+            ),
+            SanMove::parse("N@f3+ TAIL").unwrap()
+        );
+        for (input, expected) in &[("P@e6", "P@e6"), ("@e6", "P@e6"), ("Q@h7#", "Q@h7#")] {
+            assert_eq!(*expected, input.parse::<SanMove>().unwrap().to_string());
+        }
+        assert!("N@".parse::<SanMove>().is_err());
+        assert!("K@i9".parse::<SanMove>().is_err());
+    }
+
+    #[test]
+    fn test_separate_en_passant() {
+        let (parsed, warnings) = with_options(ParseOptions::lenient(), || {
+            SanMove::parse("exd6 e.p. 2. Nf3").unwrap()
+        });
+        assert_eq!(
+            ("exd6".to_string(), " 2. Nf3"),
+            (parsed.0.to_string(), parsed.1)
+        );
+        assert_eq!(vec!["en passant suffix e.p."], warnings);
+
+        // "e" alone is just the start of the next move.
+        let (parsed, _) = with_options(ParseOptions::lenient(), || {
+            SanMove::parse("exd6 e5").unwrap()
+        });
+        assert_eq!(" e5", parsed.1);
+    }
 }
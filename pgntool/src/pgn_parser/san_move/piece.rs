@@ -1,7 +1,8 @@
 use crate::pgn_error::PgnError;
+use crate::pgn_parser::options::options;
 use crate::pgn_parser::GrammarNode;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
 pub enum Piece {
     King,
     Queen,
@@ -11,26 +12,39 @@ pub enum Piece {
     Pawn,
 }
 
+impl Piece {
+    pub fn to_char(self) -> char {
+        match self {
+            Piece::Pawn => 'P',
+            Piece::Knight => 'N',
+            Piece::Bishop => 'B',
+            Piece::Rook => 'R',
+            Piece::Queen => 'Q',
+            Piece::King => 'K',
+        }
+    }
+}
+
+// Pieces are parsed with the letters from the parse options, which are English by default.
 impl GrammarNode for Piece {
     fn check_start(s: &str) -> bool {
-        let chs: &[char] = &['P', 'N', 'B', 'R', 'Q', 'K'];
-        s.starts_with(chs)
+        let letters = options().letters;
+        s.chars()
+            .next()
+            .is_some_and(|ch| letters.piece(ch).is_some())
     }
 
     fn parse_wrapped(s: &str) -> crate::Result<(Self, &str)>
     where
         Self: Sized,
     {
-        let piece = match s.chars().next() {
-            Some('P') => Piece::Pawn,
-            Some('N') => Piece::Knight,
-            Some('B') => Piece::Bishop,
-            Some('R') => Piece::Rook,
-            Some('Q') => Piece::Queen,
-            Some('K') => Piece::King,
-            _ => return Err(PgnError::UnexpectedInput("Piece", s.to_string())),
-        };
-        Ok((piece, &s[1..]))
+        match s.chars().next() {
+            Some(ch) => match options().letters.piece(ch) {
+                Some(piece) => Ok((piece, &s[ch.len_utf8()..])),
+                None => Err(PgnError::UnexpectedInput("Piece", s.to_string())),
+            },
+            None => Err(PgnError::UnexpectedInput("Piece", s.to_string())),
+        }
     }
 }
 
@@ -0,0 +1,174 @@
+use crate::pgn_error::PgnError;
+use crate::pgn_parser::san_move::piece::Piece;
+use std::cell::Cell;
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+// The letters used for the pieces in SAN. PGN export is always in English, as the standard
+// requires, but files from other programs, and printed material, often use the letters of another
+// language or figurine glyphs.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct PieceLetters {
+    // King, queen, rook, bishop, knight and pawn.
+    letters: [char; 6],
+}
+
+const LANGUAGES: &[(&str, PieceLetters)] = &[
+    ("en", PieceLetters::ENGLISH),
+    ("de", PieceLetters::GERMAN),
+    ("es", PieceLetters::SPANISH),
+    ("figurine", PieceLetters::FIGURINE),
+];
+
+impl PieceLetters {
+    pub const ENGLISH: PieceLetters = PieceLetters::new(['K', 'Q', 'R', 'B', 'N', 'P']);
+    pub const GERMAN: PieceLetters = PieceLetters::new(['K', 'D', 'T', 'L', 'S', 'B']);
+    pub const SPANISH: PieceLetters = PieceLetters::new(['R', 'D', 'T', 'A', 'C', 'P']);
+    pub const FIGURINE: PieceLetters = PieceLetters::new(['♔', '♕', '♖', '♗', '♘', '♙']);
+
+    // The letters are given in the order king, queen, rook, bishop, knight, pawn.
+    pub const fn new(letters: [char; 6]) -> Self {
+        PieceLetters { letters }
+    }
+
+    fn index(piece: Piece) -> usize {
+        match piece {
+            Piece::King => 0,
+            Piece::Queen => 1,
+            Piece::Rook => 2,
+            Piece::Bishop => 3,
+            Piece::Knight => 4,
+            Piece::Pawn => 5,
+        }
+    }
+
+    pub fn letter(&self, piece: Piece) -> char {
+        self.letters[PieceLetters::index(piece)]
+    }
+
+    pub fn piece(&self, letter: char) -> Option<Piece> {
+        [
+            Piece::King,
+            Piece::Queen,
+            Piece::Rook,
+            Piece::Bishop,
+            Piece::Knight,
+            Piece::Pawn,
+        ]
+        .iter()
+        .copied()
+        .find(|piece| self.letter(*piece) == letter)
+    }
+
+    // Wraps a move, game or database so that it is displayed with these letters.
+    pub fn localize<T: Display>(self, value: &T) -> Localized<'_, T> {
+        Localized {
+            value,
+            letters: self,
+        }
+    }
+}
+
+impl Default for PieceLetters {
+    fn default() -> Self {
+        PieceLetters::ENGLISH
+    }
+}
+
+// Accepts a language ("en", "de", "es" or "figurine"), or six letters for the king, queen, rook,
+// bishop, knight and pawn, e.g. "KDTLSB".
+impl FromStr for PieceLetters {
+    type Err = PgnError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((_, letters)) = LANGUAGES
+            .iter()
+            .find(|(name, _)| s.eq_ignore_ascii_case(name))
+        {
+            return Ok(*letters);
+        }
+
+        let chars: Vec<char> = s.chars().collect();
+        match <[char; 6]>::try_from(chars) {
+            Ok(letters) => Ok(PieceLetters::new(letters)),
+            Err(_) => Err(PgnError::UnexpectedInput("Piece letters", s.to_string())),
+        }
+    }
+}
+
+// Display writes moves with the letters set here, so that whole games can be localized without
+// passing the letters through every node.
+thread_local! {
+    static EXPORT_LETTERS: Cell<PieceLetters> = const { Cell::new(PieceLetters::ENGLISH) };
+}
+
+pub(crate) fn export_letters() -> PieceLetters {
+    EXPORT_LETTERS.with(|letters| letters.get())
+}
+
+pub struct Localized<'a, T> {
+    value: &'a T,
+    letters: PieceLetters,
+}
+
+impl<T: Display> Display for Localized<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let previous = EXPORT_LETTERS.with(|letters| letters.replace(self.letters));
+        let result = self.value.fmt(f);
+        EXPORT_LETTERS.with(|letters| letters.set(previous));
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pgn_parser::{parse_pgn_with, ParseOptions};
+
+    #[test]
+    fn test_letters() {
+        assert_eq!('S', PieceLetters::GERMAN.letter(Piece::Knight));
+        assert_eq!(Some(Piece::Pawn), PieceLetters::GERMAN.piece('B'));
+        assert_eq!(Some(Piece::King), PieceLetters::SPANISH.piece('R'));
+        assert_eq!(None, PieceLetters::SPANISH.piece('N'));
+        assert_eq!('♘', PieceLetters::FIGURINE.letter(Piece::Knight));
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(PieceLetters::GERMAN, "de".parse().unwrap());
+        assert_eq!(PieceLetters::FIGURINE, "Figurine".parse().unwrap());
+        assert_eq!(PieceLetters::GERMAN, "KDTLSB".parse().unwrap());
+        assert!("KDT".parse::<PieceLetters>().is_err());
+    }
+
+    #[test]
+    fn test_localize() {
+        let options = ParseOptions {
+            letters: PieceLetters::GERMAN,
+            ..ParseOptions::default()
+        };
+        let (database, _) = parse_pgn_with(
+            "1. e4 e5 2. Sf3 Sc6 3. Lb5 a6 4. O-O Sf6 5. Te1 b5 6. d8=D *",
+            options,
+        )
+        .unwrap();
+        let movetext = database.games()[0].movetext();
+
+        assert_eq!(
+            "1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. O-O Nf6 5. Re1 b5 6. d8=Q *",
+            movetext.to_string()
+        );
+        assert_eq!(
+            "1. e4 e5 2. Cf3 Cc6 3. Ab5 a6 4. O-O Cf6 5. Te1 b5 6. d8=D *",
+            PieceLetters::SPANISH.localize(movetext).to_string()
+        );
+        assert_eq!(
+            "1. e4 e5 2. ♘f3 ♘c6 3. ♗b5 a6 4. O-O ♘f6 5. ♖e1 b5 6. d8=♕ *",
+            PieceLetters::FIGURINE.localize(movetext).to_string()
+        );
+        // The letters only apply inside the wrapper.
+        assert!(movetext.to_string().contains("Nf3"));
+    }
+}
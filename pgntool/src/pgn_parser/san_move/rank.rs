@@ -4,7 +4,7 @@ use crate::PgnError;
 use std::convert::TryFrom;
 use std::fmt::{Debug, Formatter};
 
-#[derive(Eq, PartialEq, Copy, Clone)]
+#[derive(Eq, PartialEq, Copy, Clone, Hash)]
 pub struct Rank(pub u8);
 
 impl Debug for Rank {
@@ -13,6 +13,12 @@ impl Debug for Rank {
     }
 }
 
+impl Rank {
+    pub fn to_char(self) -> char {
+        (b'1' + self.0 - 1) as char
+    }
+}
+
 impl TryFrom<char> for Rank {
     type Error = PgnError;
 
@@ -2,14 +2,66 @@ use crate::pgn_error::PgnError::UnexpectedInput;
 use crate::pgn_parser::san_move::file::File;
 use crate::pgn_parser::san_move::rank::Rank;
 use crate::pgn_parser::GrammarNode;
-use std::fmt::{Debug, Formatter};
+use crate::PgnError;
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
 
-#[derive(Eq, PartialEq, Copy, Clone)]
+#[derive(Eq, PartialEq, Copy, Clone, Hash)]
 pub struct Square {
     pub rank: Rank,
     pub file: File,
 }
 
+impl Square {
+    pub fn new(file: File, rank: Rank) -> Self {
+        Square { rank, file }
+    }
+
+    // Squares are indexed from 0 (a1) to 63 (h8), rank by rank.
+    pub fn index(self) -> usize {
+        (self.rank.0 as usize - 1) * 8 + (self.file.0 as usize - 1)
+    }
+
+    pub fn from_index(index: usize) -> Self {
+        Square {
+            rank: Rank((index / 8) as u8 + 1),
+            file: File((index % 8) as u8 + 1),
+        }
+    }
+
+    // Returns the square offset by the given number of files and ranks, or None if it is
+    // off the board.
+    pub fn offset(self, files: i8, ranks: i8) -> Option<Square> {
+        let file = self.file.0 as i8 + files;
+        let rank = self.rank.0 as i8 + ranks;
+        if (1..=8).contains(&file) && (1..=8).contains(&rank) {
+            Some(Square {
+                rank: Rank(rank as u8),
+                file: File(file as u8),
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl FromStr for Square {
+    type Err = PgnError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match Square::parse(s) {
+            Ok((square, "")) => Ok(square),
+            _ => Err(UnexpectedInput("Square", s.to_string())),
+        }
+    }
+}
+
+impl Display for Square {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.file.to_char(), self.rank.to_char())
+    }
+}
+
 impl Debug for Square {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}{}", (b'a' + self.file.0 - 1) as char, self.rank.0)
@@ -63,4 +115,20 @@ mod test {
 
         assert!(Square::parse("aTAIL").is_err());
     }
+
+    #[test]
+    fn test_index() {
+        let (a1, _) = Square::parse("a1").unwrap();
+        let (h8, _) = Square::parse("h8").unwrap();
+        let (e4, _) = Square::parse("e4").unwrap();
+        assert_eq!(0, a1.index());
+        assert_eq!(63, h8.index());
+        assert_eq!(28, e4.index());
+        assert_eq!(e4, Square::from_index(28));
+        assert_eq!("e4", e4.to_string());
+
+        assert_eq!(Some(h8), e4.offset(3, 4));
+        assert_eq!(None, h8.offset(1, 0));
+        assert_eq!(None, a1.offset(0, -1));
+    }
 }
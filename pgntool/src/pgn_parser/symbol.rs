@@ -1,4 +1,7 @@
+use crate::pgn_error::PgnError;
+use crate::pgn_parser::options::{options, warn};
 use crate::pgn_parser::GrammarNode;
+use crate::Result;
 
 #[derive(Debug, Eq, PartialEq)]
 pub struct Symbol(String);
@@ -15,6 +18,37 @@ impl AsRef<str> for Symbol {
     }
 }
 
+// The most characters a string or symbol may have.
+const MAX_LENGTH: usize = 255;
+
+// The start of a long string or symbol, for messages.
+pub(crate) fn excerpt(token: &str) -> String {
+    match token.char_indices().nth(20) {
+        Some((end, _)) => format!("{}...", &token[..end]),
+        None => token.to_string(),
+    }
+}
+
+// Rejects a string or symbol which is too long, or when parsing leniently, truncates it.
+pub(crate) fn limit_length(mut token: String, kind: &str) -> Result<String> {
+    let end = match token.char_indices().nth(MAX_LENGTH) {
+        Some((end, _)) => end,
+        None => return Ok(token),
+    };
+    let message = format!(
+        "{} longer than {} characters \"{}\"",
+        kind,
+        MAX_LENGTH,
+        excerpt(&token)
+    );
+    if !options().lenient {
+        return Err(PgnError::InvalidTag(message));
+    }
+    warn(message);
+    token.truncate(end);
+    Ok(token)
+}
+
 fn is_identifier_continuation(ch: char) -> bool {
     ch.is_ascii_alphanumeric()
         || ch == '_'
@@ -43,17 +77,19 @@ impl GrammarNode for Symbol {
     where
         Self: Sized,
     {
-        if let Some(end_index) = s.find(|ch| !is_identifier_continuation(ch)) {
-            Ok((Symbol(s[..end_index].to_string()), &s[end_index..]))
-        } else {
-            Ok((Symbol(s.to_string()), ""))
-        }
+        let end_index = s
+            .find(|ch| !is_identifier_continuation(ch))
+            .unwrap_or(s.len());
+        let symbol = limit_length(s[..end_index].to_string(), "symbol")?;
+        Ok((Symbol(symbol), &s[end_index..]))
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::pgn_parser::options::with_options;
+    use crate::pgn_parser::ParseOptions;
 
     #[test]
     fn test_check_start() {
@@ -116,4 +152,17 @@ mod test {
             Symbol::parse("Z-+#_=:").unwrap()
         );
     }
+
+    #[test]
+    fn test_length() {
+        let symbol = "S".repeat(255);
+        assert_eq!(Symbol(symbol.clone()), Symbol::parse(&symbol).unwrap().0);
+        let long = "S".repeat(256);
+        assert!(Symbol::parse(&long).is_err());
+
+        let (parsed, warnings) =
+            with_options(ParseOptions::lenient(), || Symbol::parse(&long).unwrap());
+        assert_eq!((Symbol(symbol), ""), parsed);
+        assert_eq!(1, warnings.len());
+    }
 }
@@ -1,7 +1,9 @@
 use crate::pgn_error::PgnError;
-use crate::pgn_parser::symbol::Symbol;
+use crate::pgn_parser::options::{options, warn};
+use crate::pgn_parser::symbol::{excerpt, limit_length};
 use crate::pgn_parser::GrammarNode;
 use crate::Result;
+use std::fmt::{Display, Formatter};
 
 #[derive(Debug, Eq, PartialEq)]
 pub struct TagPair {
@@ -9,6 +11,34 @@ pub struct TagPair {
     value: String,
 }
 
+impl TagPair {
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        TagPair {
+            name: name.into(),
+            value: value.into(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn set_value(&mut self, value: impl Into<String>) {
+        self.value = value.into();
+    }
+}
+
+impl Display for TagPair {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let escaped = self.value.replace('\\', "\\\\").replace('"', "\\\"");
+        write!(f, "[{} \"{}\"]", self.name, escaped)
+    }
+}
+
 fn parse_char(s: &str, ch: char) -> Result<&str> {
     if let Some(next_ch) = s.chars().next() {
         if ch == next_ch {
@@ -35,38 +65,101 @@ fn parse_pgn_string(s: &str) -> Result<(String, &str)> {
 
     let mut output = String::new();
     let mut escaping = false;
-    let mut remaining = s;
-    for ch in s.chars() {
-        match escaping {
-            false => {
-                if ch == '"' {
-                    break;
-                }
-                remaining = &remaining[1..];
-
-                if ch == '\\' {
-                    escaping = true;
-                    continue;
-                }
-
-                output.push(ch);
+    for (index, ch) in s.char_indices() {
+        if escaping {
+            output.push(ch);
+            escaping = false;
+        } else if ch == '"' {
+            return Ok((limit_length(output, "string")?, &s[index + 1..]));
+        } else if ch == '\\' {
+            escaping = true;
+        } else if ch == '\r' || ch == '\n' {
+            // Either the closing quote is missing, or the string goes on to the next line.
+            if s[..index].trim_end().ends_with(']') {
+                return close_unterminated(output, s, index);
             }
-            true => {
-                output.push(ch);
-                remaining = &remaining[1..];
-                escaping = false;
+            let message = format!("newline in string \"{}\"", excerpt(&output));
+            if !options().lenient {
+                return Err(PgnError::InvalidTag(message));
             }
+            if ch == '\n' {
+                warn(message);
+                output.push(' ');
+            }
+        } else if ch.is_control() {
+            let message = format!(
+                "non-printing character {:?} in string \"{}\"",
+                ch,
+                excerpt(&output)
+            );
+            if !options().lenient {
+                return Err(PgnError::InvalidTag(message));
+            }
+            warn(message);
+            output.push(' ');
+        } else {
+            output.push(ch);
         }
     }
 
-    let s = parse_char(remaining, '"')?;
+    close_unterminated(output, s, s.len())
+}
 
-    Ok((output, s))
+// Ends a string without a closing quote at the bracket closing its tag pair, as in
+// [White "Fischer], if its line ends with one, rather than reading on into the next line.
+fn close_unterminated(output: String, s: &str, line_end: usize) -> Result<(String, &str)> {
+    let message = format!("unterminated string \"{}\"", excerpt(output.trim_end()));
+    let line = s[..line_end].trim_end();
+    if !options().lenient || !line.ends_with(']') {
+        return Err(PgnError::InvalidTag(message));
+    }
+    warn(message);
+
+    let value = output.trim_end();
+    let value = value.strip_suffix(']').unwrap_or(value).trim_end();
+    Ok((
+        limit_length(value.to_string(), "string")?,
+        &s[line.len() - 1..],
+    ))
+}
+
+/*
+ Tag names, formed from symbol tokens, are case sensitive and are composed exclusively of letters,
+ digits, and the underscore character.
+*/
+fn is_tag_name_character(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '_'
 }
 
+// Tag names should end at the space or quote before the value. Unlike other symbols, they can't
+// have the characters used in moves, such as "-" or "+".
 fn parse_tag_name(s: &str) -> Result<(String, &str)> {
-    let (symbol, s) = Symbol::parse(s)?;
-    Ok((symbol.into(), s))
+    let (name, tail) = if s.starts_with(|ch: char| ch.is_ascii_alphanumeric()) {
+        let end = s.find(|ch| !is_tag_name_character(ch)).unwrap_or(s.len());
+        (limit_length(s[..end].to_string(), "symbol")?, &s[end..])
+    } else {
+        (String::new(), s)
+    };
+
+    let end = tail
+        .find(|ch: char| ch.is_whitespace() || ch == '"' || ch == ']')
+        .unwrap_or(tail.len());
+    if end == 0 {
+        if name.is_empty() {
+            return Err(PgnError::InvalidTag("missing tag name".to_string()));
+        }
+        return Ok((name, tail));
+    }
+
+    let written = &s[..s.len() - tail.len() + end];
+    // unwrap: the tail isn't empty, as end isn't 0.
+    let illegal = tail.chars().next().unwrap();
+    let message = format!("illegal character {:?} in tag name {}", illegal, written);
+    if !options().lenient {
+        return Err(PgnError::InvalidTag(message));
+    }
+    warn(message);
+    Ok((written.to_string(), &tail[end..]))
 }
 
 fn parse_tag_value(s: &str) -> Result<(String, &str)> {
@@ -85,13 +178,15 @@ impl GrammarNode for TagPair {
     where
         Self: Sized,
     {
-        let s = parse_char(s, '[')?;
+        // The export format has no spaces inside the brackets, but files from other programs
+        // sometimes do, or have none between the name and the value.
+        let s = parse_char(s, '[')?.trim_start();
 
         let (name, s) = parse_tag_name(s)?;
         let s = s.trim_start();
         let (value, s) = parse_tag_value(s)?;
 
-        let s = parse_char(s, ']')?;
+        let s = parse_char(s.trim_start(), ']')?;
 
         Ok((TagPair { name, value }, s))
     }
@@ -100,18 +195,40 @@ impl GrammarNode for TagPair {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::pgn_parser::options::with_options;
+    use crate::pgn_parser::ParseOptions;
 
     #[test]
     fn test_tag_pair() {
-        let (pair, tail) = TagPair::parse(r#"[Tag-Name "Tag Value"]TAIL"#).unwrap();
+        let (pair, tail) = TagPair::parse(r#"[Tag_Name "Tag Value"]TAIL"#).unwrap();
         assert_eq!("TAIL", tail);
-        assert_eq!("Tag-Name", pair.name);
+        assert_eq!("Tag_Name", pair.name);
         assert_eq!("Tag Value", pair.value);
 
         let (pair, tail) = TagPair::parse(r#"[Escaped "Has a \\ and a \"."]TAIL"#).unwrap();
         assert_eq!("TAIL", tail);
         assert_eq!("Escaped", pair.name);
         assert_eq!(r#"Has a \ and a "."#, pair.value);
+
+        let (pair, tail) = TagPair::parse("[ White\t\"Müller\"\r\n]TAIL").unwrap();
+        assert_eq!("TAIL", tail);
+        assert_eq!("White", pair.name);
+        assert_eq!("Müller", pair.value);
+
+        let (pair, _) = TagPair::parse(r#"[Black"Ñuñez"]"#).unwrap();
+        assert_eq!("Black", pair.name);
+        assert_eq!("Ñuñez", pair.value);
+    }
+
+    #[test]
+    fn test_display() {
+        for tag in &[
+            r#"[Tag_Name "Tag Value"]"#,
+            r#"[Escaped "Has a \\ and a \"."]"#,
+        ] {
+            let (pair, _) = TagPair::parse(tag).unwrap();
+            assert_eq!(*tag, pair.to_string());
+        }
     }
 
     #[test]
@@ -133,4 +250,89 @@ mod test {
             parse_pgn_string("\"back\\\\slash\"TAIL").unwrap()
         )
     }
+
+    fn error(tag: &str) -> String {
+        TagPair::parse(tag).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(
+            "Invalid tag: unterminated string \"Fischer]\"",
+            error("[White \"Fischer]\n[Black \"Spassky\"]")
+        );
+        assert_eq!(
+            "Invalid tag: unterminated string \"Fischer\"",
+            error("[White \"Fischer")
+        );
+        assert_eq!(
+            "Invalid tag: newline in string \"Two\"",
+            error("[Annotator \"Two\r\nLines\"]")
+        );
+        assert_eq!(
+            "Invalid tag: non-printing character '\\t' in string \"A\"",
+            error("[Event \"A\tB\"]")
+        );
+        let long = format!("[Event \"{}\"]", "x".repeat(256));
+        assert_eq!(
+            "Invalid tag: string longer than 255 characters \"xxxxxxxxxxxxxxxxxxxx...\"",
+            error(&long)
+        );
+        assert_eq!(
+            "Invalid tag: illegal character '@' in tag name White@Home",
+            error("[White@Home \"Fischer\"]")
+        );
+        assert_eq!(
+            "Invalid tag: illegal character '_' in tag name _White",
+            error("[_White \"Fischer\"]")
+        );
+        assert_eq!(
+            "Invalid tag: illegal character '-' in tag name Tag-Name",
+            error("[Tag-Name \"Value\"]")
+        );
+        assert_eq!(
+            "Invalid tag: illegal character '+' in tag name Time+Control",
+            error("[Time+Control \"40/7200\"]")
+        );
+        assert_eq!("Invalid tag: missing tag name", error("[\"Fischer\"]"));
+
+        let max = format!("[Event \"{}\"]", "é".repeat(255));
+        assert!(TagPair::parse(&max).is_ok());
+    }
+
+    #[test]
+    fn test_lenient() {
+        let (parsed, warnings) = with_options(ParseOptions::lenient(), || {
+            let (white, tail) =
+                TagPair::parse("[White \"Fischer ] \r\n[Black \"Spassky\"]").unwrap();
+            assert_eq!(" \r\n[Black \"Spassky\"]", tail);
+            let (annotator, _) = TagPair::parse("[Annotator \"Two\r\nLines\"]").unwrap();
+            let (event, _) = TagPair::parse("[Event \"A\tB\"]").unwrap();
+            let long = format!("[Site \"{}\"]", "é".repeat(300));
+            let (site, _) = TagPair::parse(&long).unwrap();
+            let (name, _) = TagPair::parse("[White@Home \"Fischer\"]").unwrap();
+            [white, annotator, event, site, name]
+        });
+        assert_eq!("Fischer", parsed[0].value());
+        assert_eq!("Two Lines", parsed[1].value());
+        assert_eq!("A B", parsed[2].value());
+        assert_eq!("é".repeat(255), parsed[3].value());
+        assert_eq!("White@Home", parsed[4].name());
+        assert_eq!(
+            vec![
+                "unterminated string \"Fischer ]\"",
+                "newline in string \"Two\"",
+                "non-printing character '\\t' in string \"A\"",
+                "string longer than 255 characters \"éééééééééééééééééééé...\"",
+                "illegal character '@' in tag name White@Home",
+            ],
+            warnings
+        );
+
+        // Without a bracket to end it on, an unterminated string is still an error.
+        let (result, _) = with_options(ParseOptions::lenient(), || {
+            TagPair::parse("[White \"Fischer\n1. e4 *").map(|_| ())
+        });
+        assert!(result.is_err());
+    }
 }
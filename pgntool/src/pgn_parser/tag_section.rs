@@ -1,5 +1,6 @@
 use crate::pgn_parser::tag_pair::TagPair;
 use crate::pgn_parser::GrammarNode;
+use std::fmt::{Display, Formatter};
 
 #[derive(Debug)]
 pub struct TagSection {
@@ -10,6 +11,45 @@ impl TagSection {
     pub fn empty() -> Self {
         TagSection { pairs: vec![] }
     }
+
+    pub fn pairs(&self) -> &[TagPair] {
+        &self.pairs
+    }
+
+    // Returns the value of the first tag with the given name.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|pair| pair.name() == name)
+            .map(|pair| pair.value())
+    }
+
+    // Sets the value of the named tag, adding it at the end if it isn't already present.
+    pub fn set(&mut self, name: &str, value: impl Into<String>) {
+        match self.pairs.iter_mut().find(|pair| pair.name() == name) {
+            Some(pair) => pair.set_value(value),
+            None => self.pairs.push(TagPair::new(name, value)),
+        }
+    }
+
+    // Removes every tag with the given name.
+    pub fn remove(&mut self, name: &str) {
+        self.pairs.retain(|pair| pair.name() != name);
+    }
+
+    // Keeps only the tags for which `keep` returns true.
+    pub fn retain(&mut self, keep: impl FnMut(&TagPair) -> bool) {
+        self.pairs.retain(keep);
+    }
+}
+
+impl Display for TagSection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for pair in &self.pairs {
+            writeln!(f, "{}", pair)?;
+        }
+        Ok(())
+    }
 }
 
 /*
@@ -0,0 +1,270 @@
+use crate::pgn_parser::{Piece, Square};
+use crate::polyglot::keys::polyglot_key;
+use crate::position::{Move, Position};
+use crate::{PgnError, Result};
+use std::io::{Read, Write};
+
+const ENTRY_SIZE: usize = 16;
+
+/*
+  A Polyglot book is a sequence of 16-byte entries, sorted by key, all fields big-endian:
+
+    key     u64   Polyglot hash of the position
+    move    u16   bits 0-5 to-square, 6-11 from-square, 12-14 promotion piece
+    weight  u16   relative frequency with which the move should be played
+    learn   u32   reserved for learning data; written as zero
+
+  Squares are numbered 0 (a1) to 63 (h8). Castling is encoded as the king capturing its own
+  rook, e.g. e1h1 for white short castling, or f1g1 in Chess960 with the rook on g1.
+*/
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct BookEntry {
+    pub key: u64,
+    pub raw_move: u16,
+    pub weight: u16,
+    pub learn: u32,
+}
+
+impl BookEntry {
+    fn from_bytes(bytes: &[u8]) -> BookEntry {
+        let mut key = [0u8; 8];
+        key.copy_from_slice(&bytes[0..8]);
+        BookEntry {
+            key: u64::from_be_bytes(key),
+            raw_move: u16::from_be_bytes([bytes[8], bytes[9]]),
+            weight: u16::from_be_bytes([bytes[10], bytes[11]]),
+            learn: u32::from_be_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]),
+        }
+    }
+
+    fn to_bytes(self) -> [u8; ENTRY_SIZE] {
+        let mut bytes = [0u8; ENTRY_SIZE];
+        bytes[0..8].copy_from_slice(&self.key.to_be_bytes());
+        bytes[8..10].copy_from_slice(&self.raw_move.to_be_bytes());
+        bytes[10..12].copy_from_slice(&self.weight.to_be_bytes());
+        bytes[12..16].copy_from_slice(&self.learn.to_be_bytes());
+        bytes
+    }
+}
+
+fn square_bits(square: Square) -> u16 {
+    square.index() as u16
+}
+
+fn promotion_bits(promotion: Option<Piece>) -> u16 {
+    match promotion {
+        None => 0,
+        Some(Piece::Knight) => 1,
+        Some(Piece::Bishop) => 2,
+        Some(Piece::Rook) => 3,
+        Some(Piece::Queen) => 4,
+        // Pawns and kings are never promotion pieces.
+        Some(Piece::Pawn) | Some(Piece::King) => 0,
+    }
+}
+
+// Encodes a move in the Polyglot move format. The position is the one the move is played in,
+// which gives the square of the rook a castling king takes.
+pub fn encode_move(mv: &Move, position: &Position) -> u16 {
+    let to = match mv.castle {
+        Some(side) => {
+            let rook_file = position
+                .castling_rights()
+                .rook_file(position.side_to_move(), side)
+                .unwrap_or_else(|| side.rook_file());
+            Square::new(rook_file, mv.from.rank)
+        }
+        None => mv.to,
+    };
+    square_bits(to) | square_bits(mv.from) << 6 | promotion_bits(mv.promotion) << 12
+}
+
+// Finds the legal move in the position matching a Polyglot move, if there is one.
+pub fn decode_move(raw_move: u16, position: &Position) -> Option<Move> {
+    position
+        .legal_moves()
+        .into_iter()
+        .find(|mv| encode_move(mv, position) == raw_move)
+}
+
+// A book move decoded against the position it was looked up for.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct BookMove {
+    pub mv: Move,
+    pub weight: u16,
+    pub learn: u32,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct PolyglotBook {
+    entries: Vec<BookEntry>,
+}
+
+impl PolyglotBook {
+    // Creates a book from entries in any order. Entries are sorted by key, and by descending
+    // weight within a key.
+    pub fn new(mut entries: Vec<BookEntry>) -> Self {
+        entries.sort_by(|a, b| a.key.cmp(&b.key).then(b.weight.cmp(&a.weight)));
+        PolyglotBook { entries }
+    }
+
+    pub fn read(mut reader: impl Read) -> Result<Self> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes)?;
+        if bytes.len() % ENTRY_SIZE != 0 {
+            return Err(PgnError::InvalidBook(format!(
+                "size {} is not a multiple of {}",
+                bytes.len(),
+                ENTRY_SIZE
+            )));
+        }
+
+        let entries: Vec<BookEntry> = bytes
+            .chunks(ENTRY_SIZE)
+            .map(BookEntry::from_bytes)
+            .collect();
+        if entries.windows(2).any(|pair| pair[0].key > pair[1].key) {
+            return Err(PgnError::InvalidBook("entries are not sorted".to_string()));
+        }
+
+        Ok(PolyglotBook { entries })
+    }
+
+    pub fn write(&self, mut writer: impl Write) -> Result<()> {
+        for entry in &self.entries {
+            writer.write_all(&entry.to_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn entries(&self) -> &[BookEntry] {
+        &self.entries
+    }
+
+    // Returns all of the entries with the given key.
+    pub fn lookup(&self, key: u64) -> &[BookEntry] {
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        let end = self.entries.partition_point(|entry| entry.key <= key);
+        &self.entries[start..end]
+    }
+
+    // Returns the book moves for the position, skipping any entries which do not decode to a
+    // legal move (e.g. from a key collision).
+    pub fn probe(&self, position: &Position) -> Vec<BookMove> {
+        self.lookup(polyglot_key(position))
+            .iter()
+            .filter_map(|entry| {
+                decode_move(entry.raw_move, position).map(|mv| BookMove {
+                    mv,
+                    weight: entry.weight,
+                    learn: entry.learn,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::position::CastleSide;
+
+    fn find_move(position: &Position, coordinates: &str) -> Move {
+        position
+            .legal_moves()
+            .into_iter()
+            .find(|mv| mv.to_string() == coordinates)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_encode_move() {
+        let start = Position::starting();
+        // e2 is square 12, e4 is square 28.
+        assert_eq!(
+            12 << 6 | 28,
+            encode_move(&find_move(&start, "e2e4"), &start)
+        );
+
+        let castle: Position = "4k3/8/8/8/8/8/8/4K2R w K - 0 1".parse().unwrap();
+        let short = find_move(&castle, "e1g1");
+        // Castling is encoded as the king moving to the rook's square.
+        assert_eq!(4 << 6 | 7, encode_move(&short, &castle));
+        assert_eq!(Some(short), decode_move(4 << 6 | 7, &castle));
+
+        let promote: Position = "4k3/1P6/8/8/8/8/8/4K3 w - - 0 1".parse().unwrap();
+        let knight = find_move(&promote, "b7b8n");
+        assert_eq!(1 << 12 | 49 << 6 | 57, encode_move(&knight, &promote));
+    }
+
+    #[test]
+    fn test_encode_chess960_castle() {
+        // The king on f1, with the rooks on g1 and b1 rather than in the corners.
+        let position: Position = "4k3/8/8/8/8/8/8/1R3KR1 w GB - 0 1".parse().unwrap();
+        let moves = position.legal_moves();
+        let short = moves
+            .iter()
+            .find(|mv| mv.castle == Some(CastleSide::KingSide))
+            .unwrap();
+        assert_eq!(5 << 6 | 6, encode_move(short, &position));
+        assert_eq!(Some(*short), decode_move(5 << 6 | 6, &position));
+
+        let long = moves
+            .iter()
+            .find(|mv| mv.castle == Some(CastleSide::QueenSide))
+            .unwrap();
+        assert_eq!(5 << 6 | 1, encode_move(long, &position));
+        assert_eq!(Some(*long), decode_move(5 << 6 | 1, &position));
+    }
+
+    #[test]
+    fn test_read_write() {
+        let start = Position::starting();
+        let key = polyglot_key(&start);
+        let book = PolyglotBook::new(vec![
+            BookEntry {
+                key: key + 1,
+                raw_move: 1,
+                weight: 1,
+                learn: 0,
+            },
+            BookEntry {
+                key,
+                raw_move: encode_move(&find_move(&start, "d2d4"), &start),
+                weight: 10,
+                learn: 0,
+            },
+            BookEntry {
+                key,
+                raw_move: encode_move(&find_move(&start, "e2e4"), &start),
+                weight: 20,
+                learn: 0,
+            },
+        ]);
+
+        let mut bytes = vec![];
+        book.write(&mut bytes).unwrap();
+        assert_eq!(48, bytes.len());
+        // The key is written big-endian.
+        assert_eq!(key.to_be_bytes(), bytes[0..8]);
+
+        let read = PolyglotBook::read(bytes.as_slice()).unwrap();
+        assert_eq!(book.entries(), read.entries());
+
+        let moves = read.probe(&start);
+        assert_eq!(2, moves.len());
+        assert_eq!("e2e4", moves[0].mv.to_string());
+        assert_eq!(20, moves[0].weight);
+        assert_eq!("d2d4", moves[1].mv.to_string());
+    }
+
+    #[test]
+    fn test_read_invalid() {
+        assert!(PolyglotBook::read(&[0u8; 15][..]).is_err());
+
+        let mut unsorted = vec![];
+        unsorted.extend_from_slice(&[1u8; 16]);
+        unsorted.extend_from_slice(&[0u8; 16]);
+        assert!(PolyglotBook::read(unsorted.as_slice()).is_err());
+    }
+}
@@ -0,0 +1,131 @@
+use crate::pgn_parser::{GameTermination, PgnGame};
+use crate::polyglot::book::{encode_move, BookEntry, PolyglotBook};
+use crate::polyglot::keys::polyglot_key;
+use crate::position::Color;
+use crate::replay::{require_standard_rules, Mainline};
+use crate::Result;
+use std::collections::HashMap;
+
+// Builds a Polyglot book from the opening moves of a set of games.
+//
+// Each move is scored from the point of view of the player who made it: 2 points for a win and
+// 1 for a draw. A move's weight is its total score, so moves which only ever lost are left out
+// of the book. Unfinished games carry no result and are skipped.
+#[derive(Debug)]
+pub struct BookBuilder {
+    max_plies: usize,
+    scores: HashMap<(u64, u16), u64>,
+}
+
+impl BookBuilder {
+    pub fn new(max_plies: usize) -> Self {
+        BookBuilder {
+            max_plies,
+            scores: HashMap::new(),
+        }
+    }
+
+    // Games of variants other than Chess960 are rejected, since their moves, such as Crazyhouse
+    // drops, can't be written in a book.
+    pub fn add_game(&mut self, game: &PgnGame) -> Result<()> {
+        require_standard_rules(game)?;
+        let winner = match game.movetext().game_termination() {
+            GameTermination::WhiteWins => Some(Color::White),
+            GameTermination::BlackWins => Some(Color::Black),
+            GameTermination::Tie => None,
+            GameTermination::Unfinished => return Ok(()),
+        };
+
+        let mainline = Mainline::replay_plies(game, self.max_plies)?;
+        for (position, mv) in mainline.plies() {
+            // Null moves can't be played from a book.
+            if mv.is_null() {
+                continue;
+            }
+            let score = match winner {
+                Some(color) if color == position.side_to_move() => 2,
+                Some(_) => 0,
+                None => 1,
+            };
+            *self
+                .scores
+                .entry((polyglot_key(position), encode_move(mv, position)))
+                .or_insert(0) += score;
+        }
+
+        Ok(())
+    }
+
+    pub fn build(self) -> PolyglotBook {
+        // Weights are 16 bits, so scale everything down if the largest score won't fit.
+        let max_score = self.scores.values().copied().max().unwrap_or(0);
+        let divisor = if max_score > u16::MAX as u64 {
+            max_score / u16::MAX as u64 + 1
+        } else {
+            1
+        };
+
+        let entries = self
+            .scores
+            .into_iter()
+            .filter(|(_, score)| *score > 0)
+            .map(|((key, raw_move), score)| BookEntry {
+                key,
+                raw_move,
+                // Don't let scaling drop a move that scored.
+                weight: (score / divisor).max(1) as u16,
+                learn: 0,
+            })
+            .collect();
+
+        PolyglotBook::new(entries)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::position::Position;
+    use crate::{parse_pgn, PgnError};
+
+    #[test]
+    fn test_build() {
+        let database = parse_pgn(
+            "1. e4 e5 2. Nf3 1-0\n\
+             1. e4 c5 0-1\n\
+             1. d4 d5 1/2-1/2\n\
+             1. c4 *",
+        )
+        .unwrap();
+
+        let mut builder = BookBuilder::new(2);
+        for game in database.games() {
+            builder.add_game(game).unwrap();
+        }
+        let book = builder.build();
+
+        let start = Position::starting();
+        let moves: Vec<(String, u16)> = book
+            .probe(&start)
+            .iter()
+            .map(|book_move| (book_move.mv.to_string(), book_move.weight))
+            .collect();
+        // e4 won once and lost once, d4 drew, and c4 is unfinished.
+        assert_eq!(
+            vec![("e2e4".to_string(), 2), ("d2d4".to_string(), 1)],
+            moves
+        );
+
+        // 1...e5 only lost, and 2. Nf3 is beyond the ply limit.
+        assert_eq!(4, book.entries().len());
+
+        let database =
+            parse_pgn("[Variant \"Crazyhouse\"]\n\n1. e4 d5 2. exd5 Qxd5 3. N@f3 1-0").unwrap();
+        let mut builder = BookBuilder::new(10);
+        assert!(matches!(
+            builder.add_game(&database.games()[0]),
+            Err(PgnError::UnsupportedVariant(_))
+        ));
+        assert!(builder.build().entries().is_empty());
+    }
+}
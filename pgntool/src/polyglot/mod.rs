@@ -0,0 +1,7 @@
+mod book;
+mod builder;
+mod keys;
+
+pub use book::{decode_move, encode_move, BookEntry, BookMove, PolyglotBook};
+pub use builder::BookBuilder;
+pub use keys::polyglot_key;
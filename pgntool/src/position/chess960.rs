@@ -0,0 +1,151 @@
+use crate::pgn_parser::{File, Piece, Rank, Square};
+use crate::position::{CastleSide, CastlingRights, Color, ColoredPiece, Position, Variant};
+
+// The files of the two knights among the five squares left after placing the bishops and queen,
+// for each of the ten ways of placing them.
+const KNIGHT_PLACEMENTS: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
+/*
+  The 960 starting positions are numbered from 0 to 959 as Reinhard Scharnagl numbered them,
+  under which the standard starting position is 518. Dividing the number by 4 repeatedly gives the
+  file of the light-squared bishop (b, d, f or h), then of the dark-squared bishop (a, c, e or g),
+  then the queen's place among the six empty squares left, and last the knights' places among the
+  five squares then left. The rooks and the king fill the last three squares, the king between
+  the rooks.
+*/
+impl Position {
+    // Returns the Chess960 starting position with the given number, if it is less than 960. The
+    // position is played by the Chess960 rules.
+    pub fn chess960(number: u16) -> Option<Position> {
+        if number >= 960 {
+            return None;
+        }
+        let number = usize::from(number);
+
+        let mut back_rank = [None; 8];
+        back_rank[2 * (number % 4) + 1] = Some(Piece::Bishop);
+        back_rank[2 * (number / 4 % 4)] = Some(Piece::Bishop);
+        let place = |back_rank: &mut [Option<Piece>; 8], index: usize, piece: Piece| {
+            // unwrap: there are always enough empty squares for the piece.
+            let file = (0..8)
+                .filter(|&file| back_rank[file].is_none())
+                .nth(index)
+                .unwrap();
+            back_rank[file] = Some(piece);
+        };
+        place(&mut back_rank, number / 16 % 6, Piece::Queen);
+        let (first, second) = KNIGHT_PLACEMENTS[number / 96];
+        // The second knight is placed after the first, which takes up one of the empty squares.
+        place(&mut back_rank, first, Piece::Knight);
+        place(&mut back_rank, second - 1, Piece::Knight);
+        for &piece in &[Piece::Rook, Piece::King, Piece::Rook] {
+            place(&mut back_rank, 0, piece);
+        }
+
+        let mut position = Position::empty();
+        let mut castling = CastlingRights::default();
+        for (file, piece) in back_rank.iter().enumerate() {
+            // unwrap: every square has been filled.
+            let piece = piece.unwrap();
+            let file = File(file as u8 + 1);
+            for &color in &[Color::White, Color::Black] {
+                let pawn_rank = Rank((color.back_rank().0 as i8 + color.forward()) as u8);
+                position.set(
+                    Square::new(file, color.back_rank()),
+                    Some(ColoredPiece::new(color, piece)),
+                );
+                position.set(
+                    Square::new(file, pawn_rank),
+                    Some(ColoredPiece::new(color, Piece::Pawn)),
+                );
+            }
+            if piece == Piece::Rook {
+                // The first rook is on the queen's side of the king, and the second on the king's.
+                let side = if castling.white_queen_side.is_none() {
+                    CastleSide::QueenSide
+                } else {
+                    CastleSide::KingSide
+                };
+                castling.set(Color::White, side, Some(file));
+                castling.set(Color::Black, side, Some(file));
+            }
+        }
+        position.castling = castling;
+        Some(position.with_variant(Variant::Chess960))
+    }
+
+    // Returns the number of the Chess960 starting position this is, if it is one. The variant
+    // isn't compared, so the standard starting position is number 518.
+    pub fn chess960_number(&self) -> Option<u16> {
+        (0..960).find(|&number| {
+            Position::chess960(number).map(|start| start.with_variant(self.variant()))
+                == Some(self.clone())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse_pgn;
+    use crate::position::STARTING_FEN;
+    use crate::replay::starting_position;
+
+    #[test]
+    fn test_numbering() {
+        assert_eq!(STARTING_FEN, Position::chess960(518).unwrap().to_fen());
+        assert_eq!(
+            "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1",
+            Position::chess960(0).unwrap().to_fen()
+        );
+        assert_eq!(
+            "rkrnnqbb/pppppppp/8/8/8/8/PPPPPPPP/RKRNNQBB w KQkq - 0 1",
+            Position::chess960(959).unwrap().to_fen()
+        );
+        assert!(Position::chess960(960).is_none());
+
+        assert_eq!(Some(518), Position::starting().chess960_number());
+        let shredder: Position = "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w HFhf - 0 1"
+            .parse()
+            .unwrap();
+        assert_eq!(Some(0), shredder.chess960_number());
+        let moved: Position = "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w Kkq - 0 1"
+            .parse()
+            .unwrap();
+        assert_eq!(None, moved.chess960_number());
+    }
+
+    #[test]
+    fn test_number_of_game() {
+        let database = parse_pgn(
+            "[Variant \"Chess960\"]\n[SetUp \"1\"]\n\
+             [FEN \"bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1\"]\n\n1. g4 *",
+        )
+        .unwrap();
+        let start = starting_position(&database.games()[0]).unwrap();
+        assert_eq!(Variant::Chess960, start.variant());
+        assert_eq!(Some(0), start.chess960_number());
+        assert_eq!(Variant::Chess960, Position::chess960(0).unwrap().variant());
+    }
+
+    #[test]
+    fn test_distinct() {
+        let mut fens: Vec<String> = (0..960)
+            .map(|number| Position::chess960(number).unwrap().to_fen())
+            .collect();
+        fens.sort();
+        fens.dedup();
+        assert_eq!(960, fens.len());
+    }
+}
@@ -0,0 +1,475 @@
+use crate::pgn_parser::{File, Piece, Rank, Square};
+use crate::position::{CastleSide, Color, ColoredPiece, Position, Variant};
+use crate::PgnError;
+use std::str::FromStr;
+
+fn piece_from_fen_char(ch: char) -> Option<ColoredPiece> {
+    let color = if ch.is_ascii_uppercase() {
+        Color::White
+    } else {
+        Color::Black
+    };
+    let piece = match ch.to_ascii_uppercase() {
+        'P' => Piece::Pawn,
+        'N' => Piece::Knight,
+        'B' => Piece::Bishop,
+        'R' => Piece::Rook,
+        'Q' => Piece::Queen,
+        'K' => Piece::King,
+        _ => return None,
+    };
+    Some(ColoredPiece::new(color, piece))
+}
+
+fn piece_to_fen_char(piece: ColoredPiece) -> char {
+    match piece.color {
+        Color::White => piece.piece.to_char(),
+        Color::Black => piece.piece.to_char().to_ascii_lowercase(),
+    }
+}
+
+// A Crazyhouse FEN gives the pieces in hand after the placement, either in brackets, as in
+// "RNBQKBNR[Qn]", or as a ninth rank, as in "RNBQKBNR/Qn".
+fn split_pocket(placement: &str) -> Result<(&str, Option<&str>), PgnError> {
+    if let Some(start) = placement.find('[') {
+        return match placement[start + 1..].strip_suffix(']') {
+            Some(pocket) => Ok((&placement[..start], Some(pocket))),
+            None => Err(PgnError::InvalidFen(format!(
+                "unclosed pocket in '{}'",
+                placement
+            ))),
+        };
+    }
+    match placement.match_indices('/').nth(7) {
+        Some((end, _)) => Ok((&placement[..end], Some(&placement[end + 1..]))),
+        None => Ok((placement, None)),
+    }
+}
+
+fn parse_pocket(position: &mut Position, pocket: &str) -> Result<(), PgnError> {
+    for ch in pocket.chars().filter(|&ch| ch != '-') {
+        match piece_from_fen_char(ch).filter(|piece| piece.piece != Piece::King) {
+            Some(piece) => position.pocket_mut(piece.color).add(piece.piece),
+            None => {
+                return Err(PgnError::InvalidFen(format!(
+                    "unexpected piece '{}' in pocket",
+                    ch
+                )))
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse_placement(position: &mut Position, placement: &str) -> Result<(), PgnError> {
+    let (placement, pocket) = split_pocket(placement)?;
+    if let Some(pocket) = pocket {
+        parse_pocket(position, pocket)?;
+    }
+
+    let ranks: Vec<&str> = placement.split('/').collect();
+    if ranks.len() != 8 {
+        return Err(PgnError::InvalidFen(format!(
+            "expected 8 ranks in '{}'",
+            placement
+        )));
+    }
+
+    // FEN lists the ranks from 8 down to 1.
+    for (rank_str, rank) in ranks.iter().zip((1..=8u8).rev()) {
+        let mut file = 1u8;
+        for ch in rank_str.chars() {
+            if let Some(skip) = ch.to_digit(10) {
+                file = Some(skip as u8)
+                    .filter(|skip| (1..=8).contains(skip))
+                    .and_then(|skip| file.checked_add(skip))
+                    .filter(|&file| file <= 9)
+                    .ok_or_else(|| {
+                        PgnError::InvalidFen(format!("rank {} has more than 8 squares", rank))
+                    })?;
+            } else if ch == '~' && file > 1 {
+                // Marks the piece before it as promoted, in Crazyhouse.
+                position.set_promoted(Square::new(File(file - 1), Rank(rank)), true);
+            } else if let Some(piece) = piece_from_fen_char(ch) {
+                if file > 8 {
+                    return Err(PgnError::InvalidFen(format!(
+                        "rank {} has more than 8 squares",
+                        rank
+                    )));
+                }
+                position.set(Square::new(File(file), Rank(rank)), Some(piece));
+                file += 1;
+            } else {
+                return Err(PgnError::InvalidFen(format!(
+                    "unexpected character '{}' in rank {}",
+                    ch, rank
+                )));
+            }
+        }
+        if file != 9 {
+            return Err(PgnError::InvalidFen(format!(
+                "rank {} does not describe 8 squares",
+                rank
+            )));
+        }
+    }
+    Ok(())
+}
+
+// Returns the file of the outermost rook of the color on the side of its king, as X-FEN's KQkq
+// mean.
+fn outermost_rook(position: &Position, color: Color, side: CastleSide) -> Option<File> {
+    let rank = color.back_rank();
+    let king_file = king_file(position, color);
+    let files: Vec<u8> = match side {
+        CastleSide::KingSide => (king_file.0 + 1..=8).rev().collect(),
+        CastleSide::QueenSide => (1..king_file.0).collect(),
+    };
+    files.into_iter().map(File).find(|&file| {
+        position.piece_at(Square::new(file, rank)) == Some(ColoredPiece::new(color, Piece::Rook))
+    })
+}
+
+// The file of the king on its back rank, or the e-file if it isn't there.
+fn king_file(position: &Position, color: Color) -> File {
+    position
+        .king_square(color)
+        .filter(|square| square.rank == color.back_rank())
+        .map(|square| square.file)
+        .unwrap_or(File(5))
+}
+
+/*
+  The castling field is standard "KQkq", or for Chess960, X-FEN or Shredder-FEN. X-FEN uses KQkq
+  for the outermost rook on each side of the king, and the rook's file letter otherwise, e.g.
+  "Bkq". Shredder-FEN always uses the file letters, e.g. "HBhb".
+*/
+fn parse_castling(position: &mut Position, castling: &str) -> Result<(), PgnError> {
+    if castling == "-" {
+        return Ok(());
+    }
+    for ch in castling.chars() {
+        let color = if ch.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let (side, rook_file) = match ch.to_ascii_lowercase() {
+            'k' => (CastleSide::KingSide, None),
+            'q' => (CastleSide::QueenSide, None),
+            'a'..='h' => {
+                let file = File(ch.to_ascii_lowercase() as u8 - b'a' + 1);
+                if file.0 > king_file(position, color).0 {
+                    (CastleSide::KingSide, Some(file))
+                } else {
+                    (CastleSide::QueenSide, Some(file))
+                }
+            }
+            _ => {
+                return Err(PgnError::InvalidFen(format!(
+                    "unexpected castling right '{}'",
+                    ch
+                )))
+            }
+        };
+        // Without a rook to castle with, KQkq still mean the corner rooks, as in standard FEN.
+        let rook_file = rook_file
+            .or_else(|| outermost_rook(position, color, side))
+            .unwrap_or_else(|| side.rook_file());
+        position.castling.set(color, side, Some(rook_file));
+    }
+    Ok(())
+}
+
+fn parse_counter(value: Option<&str>, default: u16, name: &str) -> Result<u16, PgnError> {
+    match value {
+        None => Ok(default),
+        Some(value) => value
+            .parse()
+            .map_err(|_| PgnError::InvalidFen(format!("bad {} '{}'", name, value))),
+    }
+}
+
+// Three-check FENs have an extra field after the en passant square with the checks each side has
+// left to give, as in "3+2", or in some tools the checks each side has given, as in "+0+1".
+fn parse_checks(position: &mut Position, field: &str) -> Result<(), PgnError> {
+    let bad = || PgnError::InvalidFen(format!("bad check counts '{}'", field));
+    let (given, field) = match field.strip_prefix('+') {
+        Some(field) => (true, field),
+        None => (false, field),
+    };
+    let (white, black) = field.split_once('+').ok_or_else(bad)?;
+    for (color, count) in &[(Color::White, white), (Color::Black, black)] {
+        let count: u8 = count.parse().map_err(|_| bad())?;
+        if count > 3 {
+            return Err(bad());
+        }
+        position.set_checks_given(*color, if given { count } else { 3 - count });
+    }
+    Ok(())
+}
+
+/*
+  16.1: FEN
+
+  A FEN record is six fields separated by spaces: piece placement (from rank 8 down to rank 1),
+  active color, castling availability, en passant target square, halfmove clock and fullmove
+  number. The two counters are optional here, since many tools omit them.
+
+  Variants extend it as Lichess does: Crazyhouse with the pieces in hand and "~" after promoted
+  pieces, and Three-check with the remaining checks after the en passant square.
+*/
+impl FromStr for Position {
+    type Err = PgnError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (mut position, rest) = Position::parse_epd_fields(s)?;
+
+        let mut fields = rest.split_whitespace().peekable();
+        if let Some(checks) = fields.next_if(|field| field.contains('+')) {
+            parse_checks(&mut position, checks)?;
+        }
+
+        position.halfmove_clock = parse_counter(fields.next(), 0, "halfmove clock")?;
+        position.fullmove_number = parse_counter(fields.next(), 1, "fullmove number")?;
+
+        Ok(position)
+    }
+}
+
+// Splits the first field off the string, returning it and the rest of the string.
+fn split_field(s: &str) -> Option<(&str, &str)> {
+    let s = s.trim_start();
+    let end = s.find(char::is_whitespace).unwrap_or(s.len());
+    Some((&s[..end], &s[end..])).filter(|(field, _)| !field.is_empty())
+}
+
+impl Position {
+    // Parses the four fields FEN and EPD share: the placement, active color, castling and en
+    // passant square. Returns the position and the rest of the string, which is the counters in
+    // a FEN and the operations in an EPD.
+    pub(crate) fn parse_epd_fields(s: &str) -> Result<(Position, &str), PgnError> {
+        let mut position = Position::empty();
+
+        let (placement, rest) =
+            split_field(s).ok_or_else(|| PgnError::InvalidFen(s.to_string()))?;
+        parse_placement(&mut position, placement)?;
+
+        let (color, rest) = split_field(rest).unwrap_or(("", rest));
+        position.side_to_move = match color {
+            "w" => Color::White,
+            "b" => Color::Black,
+            _ => return Err(PgnError::InvalidFen(format!("bad active color in '{}'", s))),
+        };
+
+        let (castling, rest) = split_field(rest).unwrap_or(("-", rest));
+        parse_castling(&mut position, castling)?;
+
+        let (en_passant, rest) = split_field(rest).unwrap_or(("-", rest));
+        position.en_passant = match en_passant {
+            "-" => None,
+            square => Some(
+                square
+                    .parse()
+                    .map_err(|_| PgnError::InvalidFen(format!("bad en passant '{}'", square)))?,
+            ),
+        };
+
+        Ok((position, rest))
+    }
+
+    pub fn to_fen(&self) -> String {
+        let mut fen = self.epd_fields();
+
+        if self.variant == Variant::ThreeCheck {
+            fen.push_str(&format!(
+                " {}+{}",
+                3u8.saturating_sub(self.checks_given(Color::White)),
+                3u8.saturating_sub(self.checks_given(Color::Black))
+            ));
+        }
+
+        fen.push_str(&format!(
+            " {} {}",
+            self.halfmove_clock, self.fullmove_number
+        ));
+
+        fen
+    }
+
+    // The four fields FEN and EPD share, without the counters.
+    pub(crate) fn epd_fields(&self) -> String {
+        let mut fen = String::new();
+
+        for rank in (1..=8u8).rev() {
+            let mut empty = 0;
+            for file in 1..=8u8 {
+                match self.piece_at(Square::new(File(file), Rank(rank))) {
+                    Some(piece) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        fen.push(piece_to_fen_char(piece));
+                        if self.is_promoted(Square::new(File(file), Rank(rank))) {
+                            fen.push('~');
+                        }
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if rank > 1 {
+                fen.push('/');
+            }
+        }
+
+        if self.variant == Variant::Crazyhouse {
+            fen.push('[');
+            for &color in &[Color::White, Color::Black] {
+                for piece in self.pocket(color).pieces() {
+                    fen.push(piece_to_fen_char(ColoredPiece::new(color, piece)));
+                }
+            }
+            fen.push(']');
+        }
+
+        fen.push(' ');
+        fen.push(match self.side_to_move {
+            Color::White => 'w',
+            Color::Black => 'b',
+        });
+
+        fen.push(' ');
+        let castling_start = fen.len();
+        for &(color, side, ch) in &[
+            (Color::White, CastleSide::KingSide, 'K'),
+            (Color::White, CastleSide::QueenSide, 'Q'),
+            (Color::Black, CastleSide::KingSide, 'k'),
+            (Color::Black, CastleSide::QueenSide, 'q'),
+        ] {
+            if let Some(rook_file) = self.castling.rook_file(color, side) {
+                let outermost =
+                    outermost_rook(self, color, side).unwrap_or_else(|| side.rook_file());
+                if rook_file == outermost {
+                    fen.push(ch);
+                } else {
+                    // X-FEN gives the file of a Chess960 rook which isn't the outermost one.
+                    let letter = char::from(b'a' + rook_file.0 - 1);
+                    fen.push(match color {
+                        Color::White => letter.to_ascii_uppercase(),
+                        Color::Black => letter,
+                    });
+                }
+            }
+        }
+        if fen.len() == castling_start {
+            fen.push('-');
+        }
+
+        fen.push(' ');
+        match self.en_passant {
+            Some(square) => fen.push_str(&square.to_string()),
+            None => fen.push('-'),
+        }
+
+        fen
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::position::STARTING_FEN;
+
+    #[test]
+    fn test_round_trip() {
+        for fen in &[
+            STARTING_FEN,
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R w Kq - 12 40",
+            "8/8/8/4k3/8/8/8/4K3 b - - 0 70",
+        ] {
+            let position: Position = fen.parse().unwrap();
+            assert_eq!(*fen, position.to_fen());
+        }
+    }
+
+    #[test]
+    fn test_optional_counters() {
+        let position: Position = "8/8/8/4k3/8/8/8/4K3 w - -".parse().unwrap();
+        assert_eq!(0, position.halfmove_clock());
+        assert_eq!(1, position.fullmove_number());
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!("".parse::<Position>().is_err());
+        assert!("8/8/8 w - - 0 1".parse::<Position>().is_err());
+        assert!("9/8/8/8/8/8/8/8 w - - 0 1".parse::<Position>().is_err());
+        assert!("0/8/8/8/8/8/8/8 w - - 0 1".parse::<Position>().is_err());
+        assert!("99999999999999999999999999999999/8/8/8/8/8/8/8 w - - 0 1"
+            .parse::<Position>()
+            .is_err());
+        assert!("8/8/8/8/8/8/8/8 x - - 0 1".parse::<Position>().is_err());
+        assert!("8/8/8/8/8/8/8/7X w - - 0 1".parse::<Position>().is_err());
+        assert!("8/8/8/8/8/8/8/8 w KX - 0 1".parse::<Position>().is_err());
+    }
+
+    #[test]
+    fn test_variants() {
+        let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2+3 2 3";
+        let position = fen
+            .parse::<Position>()
+            .unwrap()
+            .with_variant(Variant::ThreeCheck);
+        assert_eq!(1, position.checks_given(Color::White));
+        assert_eq!(fen, position.to_fen());
+        let given: Position = "4k3/8/8/8/8/8/8/4K3 w - - +2+0 0 1".parse().unwrap();
+        assert_eq!(2, given.checks_given(Color::White));
+
+        let fen = "rQ~bqk2r/8/8/8/8/8/8/4K3[QNPPn] b - - 0 30";
+        let position = fen
+            .parse::<Position>()
+            .unwrap()
+            .with_variant(Variant::Crazyhouse);
+        assert!(position.is_promoted("b8".parse().unwrap()));
+        assert_eq!(2, position.pocket(Color::White).count(Piece::Pawn));
+        assert_eq!(1, position.pocket(Color::Black).count(Piece::Knight));
+        assert_eq!(fen, position.to_fen());
+        let ninth_rank: Position = "rQ~bqk2r/8/8/8/8/8/8/4K3/QNPPn b - - 0 30".parse().unwrap();
+        assert_eq!(position, ninth_rank.with_variant(Variant::Crazyhouse));
+
+        assert!("8/8/8/8/8/8/8/4K3[K] w - - 0 1"
+            .parse::<Position>()
+            .is_err());
+        assert!("8/8/8/8/8/8/8/4K3[Q w - - 0 1".parse::<Position>().is_err());
+        assert!("8/8/8/8/8/8/8/4K3 w - - 4+0 0 1"
+            .parse::<Position>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_chess960_castling() {
+        // Shredder-FEN, written back as X-FEN.
+        let position: Position = "rkrbbqnn/pppppppp/8/8/8/8/PPPPPPPP/RKRBBQNN w CAca - 0 1"
+            .parse()
+            .unwrap();
+        let rights = position.castling_rights();
+        assert_eq!(Some(File(3)), rights.white_king_side);
+        assert_eq!(Some(File(1)), rights.black_queen_side);
+        assert_eq!(
+            "rkrbbqnn/pppppppp/8/8/8/8/PPPPPPPP/RKRBBQNN w KQkq - 0 1",
+            position.to_fen()
+        );
+
+        // X-FEN with an inner rook, which needs its file.
+        let fen = "4k3/8/8/8/8/8/8/RR2K1R1 w BG - 0 1";
+        let position: Position = fen.parse().unwrap();
+        assert_eq!(Some(File(2)), position.castling_rights().white_queen_side);
+        assert_eq!(Some(File(7)), position.castling_rights().white_king_side);
+        assert_eq!("4k3/8/8/8/8/8/8/RR2K1R1 w KB - 0 1", position.to_fen());
+    }
+}
@@ -0,0 +1,408 @@
+use crate::pgn_parser::{File, Piece, Rank, Square};
+
+mod chess960;
+mod fen;
+mod movegen;
+mod render;
+mod resolve;
+mod san;
+mod variant;
+
+pub use movegen::Move;
+pub use render::BoardDiagram;
+pub use variant::{Pocket, Variant};
+
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+pub enum Color {
+    White,
+    Black,
+}
+
+impl Color {
+    pub fn opponent(self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+
+    // The rank direction in which this color's pawns advance.
+    pub fn forward(self) -> i8 {
+        match self {
+            Color::White => 1,
+            Color::Black => -1,
+        }
+    }
+
+    // The index of the color in arrays kept for each color.
+    pub(crate) fn index(self) -> usize {
+        match self {
+            Color::White => 0,
+            Color::Black => 1,
+        }
+    }
+
+    pub fn back_rank(self) -> Rank {
+        match self {
+            Color::White => Rank(1),
+            Color::Black => Rank(8),
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+pub struct ColoredPiece {
+    pub color: Color,
+    pub piece: Piece,
+}
+
+impl ColoredPiece {
+    pub fn new(color: Color, piece: Piece) -> Self {
+        ColoredPiece { color, piece }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+pub enum CastleSide {
+    KingSide,
+    QueenSide,
+}
+
+impl CastleSide {
+    // The file the castling rook starts on in standard chess.
+    pub fn rook_file(self) -> File {
+        match self {
+            CastleSide::KingSide => File(8),
+            CastleSide::QueenSide => File(1),
+        }
+    }
+
+    // The files the king and rook end up on after castling, which are the same in Chess960.
+    pub fn king_destination(self) -> File {
+        match self {
+            CastleSide::KingSide => File(7),
+            CastleSide::QueenSide => File(3),
+        }
+    }
+
+    pub fn rook_destination(self) -> File {
+        match self {
+            CastleSide::KingSide => File(6),
+            CastleSide::QueenSide => File(4),
+        }
+    }
+}
+
+// The castling rights, each given by the file of the rook it castles with. In standard chess these
+// are the corner files, but in Chess960 the rooks can start on any files either side of the king.
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash, Default)]
+pub struct CastlingRights {
+    pub white_king_side: Option<File>,
+    pub white_queen_side: Option<File>,
+    pub black_king_side: Option<File>,
+    pub black_queen_side: Option<File>,
+}
+
+impl CastlingRights {
+    pub fn all() -> Self {
+        CastlingRights {
+            white_king_side: Some(CastleSide::KingSide.rook_file()),
+            white_queen_side: Some(CastleSide::QueenSide.rook_file()),
+            black_king_side: Some(CastleSide::KingSide.rook_file()),
+            black_queen_side: Some(CastleSide::QueenSide.rook_file()),
+        }
+    }
+
+    pub fn has(&self, color: Color, side: CastleSide) -> bool {
+        self.flag(color, side).is_some()
+    }
+
+    // The file of the rook the right castles with, if the right hasn't been lost.
+    pub fn rook_file(&self, color: Color, side: CastleSide) -> Option<File> {
+        *self.flag(color, side)
+    }
+
+    pub fn set(&mut self, color: Color, side: CastleSide, rook_file: Option<File>) {
+        *self.flag_mut(color, side) = rook_file;
+    }
+
+    fn flag(&self, color: Color, side: CastleSide) -> &Option<File> {
+        match (color, side) {
+            (Color::White, CastleSide::KingSide) => &self.white_king_side,
+            (Color::White, CastleSide::QueenSide) => &self.white_queen_side,
+            (Color::Black, CastleSide::KingSide) => &self.black_king_side,
+            (Color::Black, CastleSide::QueenSide) => &self.black_queen_side,
+        }
+    }
+
+    fn flag_mut(&mut self, color: Color, side: CastleSide) -> &mut Option<File> {
+        match (color, side) {
+            (Color::White, CastleSide::KingSide) => &mut self.white_king_side,
+            (Color::White, CastleSide::QueenSide) => &mut self.white_queen_side,
+            (Color::Black, CastleSide::KingSide) => &mut self.black_king_side,
+            (Color::Black, CastleSide::QueenSide) => &mut self.black_queen_side,
+        }
+    }
+}
+
+// A chess position: the placement of the pieces plus the state needed to generate moves
+// (side to move, castling rights, en passant square) and the move counters from the FEN.
+//
+// The variant gives the rules the position is played by, and some variants keep more state: the
+// pieces in each player's hand and which pieces were promoted in Crazyhouse, and the checks each
+// player has given in Three-check.
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+pub struct Position {
+    board: [Option<ColoredPiece>; 64],
+    side_to_move: Color,
+    castling: CastlingRights,
+    en_passant: Option<Square>,
+    halfmove_clock: u16,
+    fullmove_number: u16,
+    variant: Variant,
+    pockets: [Pocket; 2],
+    // A bit for each square, by index, with a promoted piece on it.
+    promoted: u64,
+    checks: [u8; 2],
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Position::starting()
+    }
+}
+
+impl Position {
+    pub fn starting() -> Self {
+        // unwrap: the starting FEN is known to be valid.
+        STARTING_FEN.parse().unwrap()
+    }
+
+    fn empty() -> Self {
+        Position {
+            board: [None; 64],
+            side_to_move: Color::White,
+            castling: CastlingRights::default(),
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            variant: Variant::Standard,
+            pockets: [Pocket::default(); 2],
+            promoted: 0,
+            checks: [0; 2],
+        }
+    }
+
+    pub fn piece_at(&self, square: Square) -> Option<ColoredPiece> {
+        self.board[square.index()]
+    }
+
+    pub fn side_to_move(&self) -> Color {
+        self.side_to_move
+    }
+
+    pub fn castling_rights(&self) -> CastlingRights {
+        self.castling
+    }
+
+    pub fn en_passant(&self) -> Option<Square> {
+        self.en_passant
+    }
+
+    pub fn halfmove_clock(&self) -> u16 {
+        self.halfmove_clock
+    }
+
+    pub fn fullmove_number(&self) -> u16 {
+        self.fullmove_number
+    }
+
+    // The move number and the side to move `ply` half-moves after this position, as for
+    // writing the numbers of a line played from it.
+    pub fn move_number_after(&self, ply: usize) -> (u16, Color) {
+        let black_first = self.side_to_move == Color::Black;
+        let half_moves = ply + usize::from(black_first);
+        // The move numbers of a game fit in a u16, as they are parsed from one.
+        let number = self.fullmove_number + (half_moves / 2) as u16;
+        match half_moves % 2 {
+            0 => (number, Color::White),
+            _ => (number, Color::Black),
+        }
+    }
+
+    // The counters aren't part of an EPD, which gives them with the hmvc and fmvn operations.
+    pub fn with_halfmove_clock(mut self, halfmove_clock: u16) -> Self {
+        self.halfmove_clock = halfmove_clock;
+        self
+    }
+
+    pub fn with_fullmove_number(mut self, fullmove_number: u16) -> Self {
+        self.fullmove_number = fullmove_number;
+        self
+    }
+
+    // Returns every occupied square with the piece on it, from a1 to h8.
+    pub fn pieces(&self) -> impl Iterator<Item = (Square, ColoredPiece)> + '_ {
+        self.board
+            .iter()
+            .enumerate()
+            .filter_map(|(index, piece)| piece.map(|piece| (Square::from_index(index), piece)))
+    }
+
+    pub fn king_square(&self, color: Color) -> Option<Square> {
+        self.pieces()
+            .find(|(_, piece)| *piece == ColoredPiece::new(color, Piece::King))
+            .map(|(square, _)| square)
+    }
+
+    fn set(&mut self, square: Square, piece: Option<ColoredPiece>) {
+        self.board[square.index()] = piece;
+    }
+
+    // Returns the position after playing the move. The move is assumed to be legal in this
+    // position (e.g. it came from legal_moves() or resolve()).
+    pub fn play(&self, mv: &Move) -> Position {
+        let mut next = self.clone();
+        let color = self.side_to_move;
+
+        // A null move only passes the turn, which also gives up any en passant capture.
+        if mv.is_null() {
+            next.en_passant = None;
+            next.halfmove_clock = self.halfmove_clock.saturating_add(1);
+            if color == Color::Black {
+                next.fullmove_number = self.fullmove_number.saturating_add(1);
+            }
+            next.side_to_move = color.opponent();
+            return next;
+        }
+
+        if mv.drop {
+            next.set(mv.to, Some(ColoredPiece::new(color, mv.piece)));
+        } else {
+            self.move_piece(mv, &mut next);
+        }
+        self.play_variant(mv, &mut next);
+
+        if mv.piece == Piece::King && !mv.drop {
+            next.castling.set(color, CastleSide::KingSide, None);
+            next.castling.set(color, CastleSide::QueenSide, None);
+        }
+        // A rook leaving its starting square, or being captured there, loses that castling right.
+        for square in &[mv.from, mv.to] {
+            for &side in &[CastleSide::KingSide, CastleSide::QueenSide] {
+                for &rook_color in &[Color::White, Color::Black] {
+                    let rook_file = self.castling.rook_file(rook_color, side);
+                    if rook_file.map(|file| Square::new(file, rook_color.back_rank()))
+                        == Some(*square)
+                    {
+                        next.castling.set(rook_color, side, None);
+                    }
+                }
+            }
+        }
+
+        // In Horde, a pawn advancing two squares from the first rank can't be taken en passant.
+        next.en_passant = if mv.piece == Piece::Pawn
+            && (mv.to.rank.0 as i8 - mv.from.rank.0 as i8).abs() == 2
+            && mv.from.rank != color.back_rank()
+        {
+            mv.from.offset(0, color.forward())
+        } else {
+            None
+        };
+
+        next.halfmove_clock = if mv.piece == Piece::Pawn || mv.captured.is_some() {
+            0
+        } else {
+            self.halfmove_clock.saturating_add(1)
+        };
+        if color == Color::Black {
+            next.fullmove_number = self.fullmove_number.saturating_add(1);
+        }
+        next.side_to_move = color.opponent();
+
+        if self.variant == Variant::ThreeCheck && next.is_check() {
+            next.add_check(color);
+        }
+
+        next
+    }
+
+    // Moves the piece from its square to its destination, along with the rook when castling and
+    // the captured pawn when capturing en passant.
+    fn move_piece(&self, mv: &Move, next: &mut Position) {
+        let color = self.side_to_move;
+        next.set(mv.from, None);
+        if let Some(side) = mv.castle {
+            // In Chess960 the king and rook can land on each other's squares, so both are taken
+            // off the board before either is put back.
+            let rank = color.back_rank();
+            let rook_file = self.castling.rook_file(color, side);
+            let rook_square = Square::new(rook_file.unwrap_or_else(|| side.rook_file()), rank);
+            let rook = next.piece_at(rook_square);
+            next.set(rook_square, None);
+            next.set(Square::new(side.rook_destination(), rank), rook);
+        }
+
+        let placed = mv.promotion.unwrap_or(mv.piece);
+        next.set(mv.to, Some(ColoredPiece::new(color, placed)));
+
+        if mv.en_passant {
+            // The captured pawn is beside the moving pawn, not on the destination square.
+            next.set(Square::new(mv.to.file, mv.from.rank), None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn square(s: &str) -> Square {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_starting() {
+        let position = Position::starting();
+        assert_eq!(Color::White, position.side_to_move());
+        assert_eq!(CastlingRights::all(), position.castling_rights());
+        assert_eq!(32, position.pieces().count());
+        assert_eq!(Some(square("e1")), position.king_square(Color::White));
+        assert_eq!(Some(square("e8")), position.king_square(Color::Black));
+        assert_eq!(
+            Some(ColoredPiece::new(Color::Black, Piece::Queen)),
+            position.piece_at(square("d8"))
+        );
+    }
+
+    #[test]
+    fn test_play_updates_state() {
+        let position = Position::starting();
+        let e4 = position
+            .legal_moves()
+            .into_iter()
+            .find(|mv| mv.from == square("e2") && mv.to == square("e4"))
+            .unwrap();
+        let position = position.play(&e4);
+        assert_eq!(Color::Black, position.side_to_move());
+        assert_eq!(Some(square("e3")), position.en_passant());
+        assert_eq!(0, position.halfmove_clock());
+        assert_eq!(1, position.fullmove_number());
+        assert_eq!(None, position.piece_at(square("e2")));
+    }
+
+    #[test]
+    fn test_play_null() {
+        let position: Position = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+            .parse()
+            .unwrap();
+        let position = position.play(&Move::null());
+        assert_eq!(
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 1 2",
+            position.to_fen()
+        );
+    }
+}
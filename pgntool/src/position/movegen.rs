@@ -0,0 +1,516 @@
+use crate::pgn_parser::{File, Piece, Square};
+use crate::position::{CastleSide, Color, ColoredPiece, Position, Variant};
+use std::fmt::{Display, Formatter};
+
+// A fully resolved move: where the piece came from and went to, and everything about the move
+// that can only be known from the position it was played in.
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+pub struct Move {
+    pub piece: Piece,
+    pub from: Square,
+    pub to: Square,
+    pub captured: Option<Piece>,
+    pub promotion: Option<Piece>,
+    pub castle: Option<CastleSide>,
+    pub en_passant: bool,
+    // A piece put on the board from the player's hand in Crazyhouse, which starts and ends on
+    // the square it is dropped on.
+    pub drop: bool,
+}
+
+impl Move {
+    fn new(piece: Piece, from: Square, to: Square) -> Self {
+        Move {
+            piece,
+            from,
+            to,
+            captured: None,
+            promotion: None,
+            castle: None,
+            en_passant: false,
+            drop: false,
+        }
+    }
+
+    pub(crate) fn drop_at(piece: Piece, to: Square) -> Self {
+        Move {
+            drop: true,
+            ..Move::new(piece, to, to)
+        }
+    }
+
+    // A null move passes the turn. It is represented as a king staying on a1, since no real
+    // move other than a castle or a drop starts and ends on the same square.
+    pub fn null() -> Self {
+        Move::new(Piece::King, Square::from_index(0), Square::from_index(0))
+    }
+
+    pub fn is_null(&self) -> bool {
+        // A Chess960 castle can also leave the king where it is, and a drop has no from square.
+        self.from == self.to && self.castle.is_none() && !self.drop
+    }
+
+    pub fn is_capture(&self) -> bool {
+        self.captured.is_some()
+    }
+}
+
+// Coordinate notation, e.g. "e2e4" or "e7e8q". A null move is "0000" and a drop is "N@f3", as
+// in UCI.
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_null() {
+            return write!(f, "0000");
+        }
+        if self.drop {
+            return write!(f, "{}@{}", self.piece.to_char(), self.to);
+        }
+        write!(f, "{}{}", self.from, self.to)?;
+        if let Some(promotion) = self.promotion {
+            write!(f, "{}", promotion.to_char().to_ascii_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+const KNIGHT_OFFSETS: &[(i8, i8)] = &[
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+
+const KING_OFFSETS: &[(i8, i8)] = &[
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+
+const BISHOP_DIRECTIONS: &[(i8, i8)] = &[(1, 1), (1, -1), (-1, -1), (-1, 1)];
+const ROOK_DIRECTIONS: &[(i8, i8)] = &[(0, 1), (1, 0), (0, -1), (-1, 0)];
+
+const PROMOTION_PIECES: &[Piece] = &[Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight];
+// In Antichess the king is an ordinary piece, which pawns can also promote to.
+const ANTICHESS_PROMOTION_PIECES: &[Piece] = &[
+    Piece::Queen,
+    Piece::Rook,
+    Piece::Bishop,
+    Piece::Knight,
+    Piece::King,
+];
+
+impl Position {
+    // Returns all of the legal moves for the side to move, by the rules of the position's
+    // variant.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let color = self.side_to_move;
+        let mut moves = self.pseudo_legal_moves();
+        match self.variant {
+            // There is no check, but a player who can capture must.
+            Variant::Antichess => {
+                if moves.iter().any(Move::is_capture) {
+                    moves.retain(Move::is_capture);
+                }
+            }
+            // A move which explodes the opponent's king wins even if it leaves the player's own
+            // king in check, but one which explodes the player's own king isn't allowed.
+            Variant::Atomic => moves.retain(|mv| {
+                let next = self.play(mv);
+                next.king_square(color).is_some()
+                    && (next.king_square(color.opponent()).is_none() || !next.is_in_check(color))
+            }),
+            _ => moves.retain(|mv| !self.play(mv).is_in_check(color)),
+        }
+        moves
+    }
+
+    // Returns true if the side to move is in check.
+    pub fn is_check(&self) -> bool {
+        self.is_in_check(self.side_to_move)
+    }
+
+    fn is_in_check(&self, color: Color) -> bool {
+        match self.variant {
+            Variant::Antichess => return false,
+            Variant::Atomic if self.kings_touch() => return false,
+            _ => {}
+        }
+        self.king_square(color)
+            .map(|square| self.is_attacked(square, color.opponent()))
+            .unwrap_or(false)
+    }
+
+    // Returns true if any piece of the given color attacks the square.
+    pub fn is_attacked(&self, square: Square, by: Color) -> bool {
+        let is = |target: Option<Square>, pieces: &[Piece]| {
+            target
+                .and_then(|target| self.piece_at(target))
+                .map(|piece| piece.color == by && pieces.contains(&piece.piece))
+                .unwrap_or(false)
+        };
+
+        // Pawns attack diagonally forward, so look diagonally backward from the square.
+        let pawn_rank = -by.forward();
+        if is(square.offset(-1, pawn_rank), &[Piece::Pawn])
+            || is(square.offset(1, pawn_rank), &[Piece::Pawn])
+        {
+            return true;
+        }
+
+        if KNIGHT_OFFSETS
+            .iter()
+            .any(|&(df, dr)| is(square.offset(df, dr), &[Piece::Knight]))
+        {
+            return true;
+        }
+
+        if KING_OFFSETS
+            .iter()
+            .any(|&(df, dr)| is(square.offset(df, dr), &[Piece::King]))
+        {
+            return true;
+        }
+
+        BISHOP_DIRECTIONS.iter().any(|&dir| {
+            is(
+                self.first_piece_along(square, dir),
+                &[Piece::Bishop, Piece::Queen],
+            )
+        }) || ROOK_DIRECTIONS.iter().any(|&dir| {
+            is(
+                self.first_piece_along(square, dir),
+                &[Piece::Rook, Piece::Queen],
+            )
+        })
+    }
+
+    // Returns the first occupied square in the given direction, if any.
+    fn first_piece_along(&self, from: Square, (df, dr): (i8, i8)) -> Option<Square> {
+        let mut square = from.offset(df, dr);
+        while let Some(current) = square {
+            if self.piece_at(current).is_some() {
+                return Some(current);
+            }
+            square = current.offset(df, dr);
+        }
+        None
+    }
+
+    // Moves which obey the movement rules of the pieces but may leave the king in check.
+    fn pseudo_legal_moves(&self) -> Vec<Move> {
+        let mut moves = vec![];
+        let color = self.side_to_move;
+
+        for (from, piece) in self.pieces().filter(|(_, piece)| piece.color == color) {
+            match piece.piece {
+                Piece::Pawn => self.pawn_moves(from, &mut moves),
+                Piece::Knight => self.step_moves(from, Piece::Knight, KNIGHT_OFFSETS, &mut moves),
+                Piece::King => {
+                    self.step_moves(from, Piece::King, KING_OFFSETS, &mut moves);
+                    if self.variant != Variant::Antichess {
+                        self.castle_moves(from, &mut moves);
+                    }
+                }
+                Piece::Bishop => {
+                    self.slide_moves(from, Piece::Bishop, BISHOP_DIRECTIONS, &mut moves)
+                }
+                Piece::Rook => self.slide_moves(from, Piece::Rook, ROOK_DIRECTIONS, &mut moves),
+                Piece::Queen => {
+                    self.slide_moves(from, Piece::Queen, BISHOP_DIRECTIONS, &mut moves);
+                    self.slide_moves(from, Piece::Queen, ROOK_DIRECTIONS, &mut moves);
+                }
+            }
+        }
+
+        match self.variant {
+            Variant::Crazyhouse => self.drop_moves(&mut moves),
+            // Capturing with the king would explode it.
+            Variant::Atomic => moves.retain(|mv| !(mv.piece == Piece::King && mv.is_capture())),
+            _ => {}
+        }
+
+        moves
+    }
+
+    fn opponent_piece(&self, square: Square) -> Option<ColoredPiece> {
+        self.piece_at(square)
+            .filter(|piece| piece.color != self.side_to_move)
+    }
+
+    fn step_moves(&self, from: Square, piece: Piece, offsets: &[(i8, i8)], moves: &mut Vec<Move>) {
+        for &(df, dr) in offsets {
+            if let Some(to) = from.offset(df, dr) {
+                match self.piece_at(to) {
+                    None => moves.push(Move::new(piece, from, to)),
+                    Some(target) if target.color != self.side_to_move => moves.push(Move {
+                        captured: Some(target.piece),
+                        ..Move::new(piece, from, to)
+                    }),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn slide_moves(
+        &self,
+        from: Square,
+        piece: Piece,
+        directions: &[(i8, i8)],
+        moves: &mut Vec<Move>,
+    ) {
+        for &(df, dr) in directions {
+            let mut square = from.offset(df, dr);
+            while let Some(to) = square {
+                match self.piece_at(to) {
+                    None => moves.push(Move::new(piece, from, to)),
+                    Some(target) => {
+                        if target.color != self.side_to_move {
+                            moves.push(Move {
+                                captured: Some(target.piece),
+                                ..Move::new(piece, from, to)
+                            });
+                        }
+                        break;
+                    }
+                }
+                square = to.offset(df, dr);
+            }
+        }
+    }
+
+    fn pawn_moves(&self, from: Square, moves: &mut Vec<Move>) {
+        let color = self.side_to_move;
+        let forward = color.forward();
+        let promotion_rank = color.opponent().back_rank();
+        let promotion_pieces = if self.variant == Variant::Antichess {
+            ANTICHESS_PROMOTION_PIECES
+        } else {
+            PROMOTION_PIECES
+        };
+
+        let mut push = |mv: Move| {
+            if mv.to.rank == promotion_rank {
+                for &promotion in promotion_pieces {
+                    moves.push(Move {
+                        promotion: Some(promotion),
+                        ..mv
+                    });
+                }
+            } else {
+                moves.push(mv);
+            }
+        };
+
+        if let Some(one) = from.offset(0, forward) {
+            if self.piece_at(one).is_none() {
+                push(Move::new(Piece::Pawn, from, one));
+
+                // Pawns on their starting rank may advance two squares, and in Horde so may
+                // pawns on the first rank.
+                let start_rank = color.back_rank().0 as i8 + forward;
+                if from.rank.0 as i8 == start_rank
+                    || (self.variant == Variant::Horde && from.rank == color.back_rank())
+                {
+                    if let Some(two) = one.offset(0, forward) {
+                        if self.piece_at(two).is_none() {
+                            push(Move::new(Piece::Pawn, from, two));
+                        }
+                    }
+                }
+            }
+        }
+
+        for &df in &[-1, 1] {
+            if let Some(to) = from.offset(df, forward) {
+                if let Some(target) = self.opponent_piece(to) {
+                    push(Move {
+                        captured: Some(target.piece),
+                        ..Move::new(Piece::Pawn, from, to)
+                    });
+                } else if self.en_passant == Some(to) {
+                    push(Move {
+                        captured: Some(Piece::Pawn),
+                        en_passant: true,
+                        ..Move::new(Piece::Pawn, from, to)
+                    });
+                }
+            }
+        }
+    }
+
+    // Castling follows the Chess960 rules, which are the standard ones when the king and rooks
+    // start on their usual squares: the king and rook end up on the same files as in standard
+    // chess, every square either of them crosses or lands on must be empty apart from the two of
+    // them, and the king may not be in check or cross an attacked square.
+    fn castle_moves(&self, from: Square, moves: &mut Vec<Move>) {
+        let color = self.side_to_move;
+        let rank = color.back_rank();
+        if from.rank != rank || self.is_in_check(color) {
+            return;
+        }
+
+        for &side in &[CastleSide::KingSide, CastleSide::QueenSide] {
+            let rook_file = match self.castling.rook_file(color, side) {
+                Some(file) => file,
+                None => continue,
+            };
+            let rook_square = Square::new(rook_file, rank);
+            if self.piece_at(rook_square) != Some(ColoredPiece::new(color, Piece::Rook))
+                || (rook_file.0 > from.file.0) != (side == CastleSide::KingSide)
+            {
+                continue;
+            }
+
+            let to = Square::new(side.king_destination(), rank);
+            let rook_to = Square::new(side.rook_destination(), rank);
+            let blocked = files_between(from.file, to.file)
+                .chain(files_between(rook_file, rook_to.file))
+                .map(|file| Square::new(File(file), rank))
+                .any(|square| {
+                    square != from && square != rook_square && self.piece_at(square).is_some()
+                });
+            if blocked {
+                continue;
+            }
+
+            // The squares the king crosses are checked with the king and rook off the board, so
+            // that neither shields a square from an attack. (The destination square is checked
+            // by the legality filter.)
+            let mut lifted = self.clone();
+            lifted.set(from, None);
+            lifted.set(rook_square, None);
+            let attacked = files_between(from.file, to.file)
+                .map(|file| Square::new(File(file), rank))
+                .filter(|&square| square != from && square != to)
+                .any(|square| lifted.is_attacked(square, color.opponent()));
+            if attacked {
+                continue;
+            }
+
+            moves.push(Move {
+                castle: Some(side),
+                ..Move::new(Piece::King, from, to)
+            });
+        }
+    }
+}
+
+// The files from one file to another, including both.
+fn files_between(a: File, b: File) -> std::ops::RangeInclusive<u8> {
+    a.0.min(b.0)..=a.0.max(b.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn position(fen: &str) -> Position {
+        fen.parse().unwrap()
+    }
+
+    // Counts the leaf nodes of the move tree to the given depth.
+    fn perft(position: &Position, depth: usize) -> usize {
+        if depth == 0 {
+            return 1;
+        }
+        position
+            .legal_moves()
+            .iter()
+            .map(|mv| perft(&position.play(mv), depth - 1))
+            .sum()
+    }
+
+    #[test]
+    fn test_perft_start() {
+        let start = Position::starting();
+        assert_eq!(20, perft(&start, 1));
+        assert_eq!(400, perft(&start, 2));
+        assert_eq!(8902, perft(&start, 3));
+    }
+
+    #[test]
+    fn test_perft_kiwipete() {
+        // Exercises castling, en passant and promotions.
+        let kiwipete =
+            position("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        assert_eq!(48, perft(&kiwipete, 1));
+        assert_eq!(2039, perft(&kiwipete, 2));
+    }
+
+    #[test]
+    fn test_perft_endgame() {
+        let endgame = position("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1");
+        assert_eq!(14, perft(&endgame, 1));
+        assert_eq!(191, perft(&endgame, 2));
+        assert_eq!(2812, perft(&endgame, 3));
+    }
+
+    #[test]
+    fn test_perft_chess960() {
+        let position =
+            position("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9");
+        assert_eq!(21, perft(&position, 1));
+        assert_eq!(528, perft(&position, 2));
+        assert_eq!(12189, perft(&position, 3));
+    }
+
+    #[test]
+    fn test_chess960_castling() {
+        // The king stays on g1 and the rook goes from h1 to f1.
+        let short = position("4k3/8/8/8/8/8/8/1R4KR w HB - 0 1");
+        let castle = short
+            .legal_moves()
+            .into_iter()
+            .find(|mv| mv.castle == Some(CastleSide::KingSide))
+            .unwrap();
+        assert!(!castle.is_null());
+        assert_eq!(
+            "4k3/8/8/8/8/8/8/1R3RK1 b - - 1 1",
+            short.play(&castle).to_fen()
+        );
+
+        // The king and rook swap squares.
+        let long = position("4k3/8/8/8/8/8/8/2RK4 w C - 0 1");
+        let castle = long
+            .legal_moves()
+            .into_iter()
+            .find(|mv| mv.castle == Some(CastleSide::QueenSide))
+            .unwrap();
+        assert_eq!(
+            "4k3/8/8/8/8/8/8/2KR4 b - - 1 1",
+            long.play(&castle).to_fen()
+        );
+
+        // The king would cross d1, which the rook on b1 no longer shields from the rook on a1.
+        let attacked = position("4k3/8/8/8/8/8/8/rR2K3 w B - 0 1");
+        assert!(attacked.legal_moves().iter().all(|mv| mv.castle.is_none()));
+    }
+
+    #[test]
+    fn test_check() {
+        assert!(!Position::starting().is_check());
+        assert!(position("4k3/8/8/8/8/8/8/4R1K1 b - - 0 1").is_check());
+        assert!(position("4k3/3P4/8/8/8/8/8/6K1 b - - 0 1").is_check());
+    }
+
+    #[test]
+    fn test_display() {
+        let start = Position::starting();
+        let mv = start
+            .legal_moves()
+            .into_iter()
+            .find(|mv| mv.to.to_string() == "f3" && mv.piece == Piece::Knight)
+            .unwrap();
+        assert_eq!("g1f3", mv.to_string());
+    }
+}
@@ -0,0 +1,154 @@
+use crate::pgn_parser::{File, Piece, Rank, Square};
+use crate::position::{Color, ColoredPiece, Move, Position};
+use std::fmt::{Display, Formatter};
+
+fn unicode_glyph(piece: ColoredPiece) -> char {
+    match (piece.color, piece.piece) {
+        (Color::White, Piece::King) => '♔',
+        (Color::White, Piece::Queen) => '♕',
+        (Color::White, Piece::Rook) => '♖',
+        (Color::White, Piece::Bishop) => '♗',
+        (Color::White, Piece::Knight) => '♘',
+        (Color::White, Piece::Pawn) => '♙',
+        (Color::Black, Piece::King) => '♚',
+        (Color::Black, Piece::Queen) => '♛',
+        (Color::Black, Piece::Rook) => '♜',
+        (Color::Black, Piece::Bishop) => '♝',
+        (Color::Black, Piece::Knight) => '♞',
+        (Color::Black, Piece::Pawn) => '♟',
+    }
+}
+
+// A text diagram of a position: the board with coordinates, the side to move and the FEN.
+// Pieces are shown as FEN letters, or as chess glyphs if unicode is set. The squares of the last
+// move, if it is given, are shown in brackets.
+#[derive(Debug, Clone)]
+pub struct BoardDiagram<'a> {
+    position: &'a Position,
+    last_move: Option<Move>,
+    unicode: bool,
+}
+
+impl<'a> BoardDiagram<'a> {
+    pub fn new(position: &'a Position) -> Self {
+        BoardDiagram {
+            position,
+            last_move: None,
+            unicode: false,
+        }
+    }
+
+    pub fn last_move(mut self, last_move: Option<Move>) -> Self {
+        // A null move doesn't move anything, so there is nothing to show.
+        self.last_move = last_move.filter(|mv| !mv.is_null());
+        self
+    }
+
+    pub fn unicode(mut self, unicode: bool) -> Self {
+        self.unicode = unicode;
+        self
+    }
+
+    fn square_symbol(&self, square: Square) -> char {
+        match self.position.piece_at(square) {
+            Some(piece) if self.unicode => unicode_glyph(piece),
+            Some(piece) => match piece.color {
+                Color::White => piece.piece.to_char(),
+                Color::Black => piece.piece.to_char().to_ascii_lowercase(),
+            },
+            None if self.unicode => '·',
+            None => '.',
+        }
+    }
+
+    fn is_highlighted(&self, square: Square) -> bool {
+        self.last_move
+            .is_some_and(|mv| mv.from == square || mv.to == square)
+    }
+}
+
+impl Display for BoardDiagram<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let border = format!("  +{}+", "-".repeat(24));
+        writeln!(f, "{}", border)?;
+        for rank in (1..=8).rev() {
+            write!(f, "{} |", rank)?;
+            for file in 1..=8 {
+                let square = Square::new(File(file), Rank(rank));
+                let symbol = self.square_symbol(square);
+                if self.is_highlighted(square) {
+                    write!(f, "[{}]", symbol)?;
+                } else {
+                    write!(f, " {} ", symbol)?;
+                }
+            }
+            writeln!(f, "|")?;
+        }
+        writeln!(f, "{}", border)?;
+        writeln!(f, "    a  b  c  d  e  f  g  h")?;
+
+        let side = match self.position.side_to_move() {
+            Color::White => "White",
+            Color::Black => "Black",
+        };
+        writeln!(f, "{} to move", side)?;
+        write!(f, "{}", self.position.to_fen())
+    }
+}
+
+impl Position {
+    pub fn diagram(&self) -> BoardDiagram<'_> {
+        BoardDiagram::new(self)
+    }
+}
+
+// Shows the position as a text diagram. The alternate form ("{:#}") uses chess glyphs.
+impl Display for Position {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.diagram().unicode(f.alternate()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let position = Position::starting();
+        let e4 = position.resolve(&"e4".parse().unwrap()).unwrap();
+        let position = position.play(&e4);
+
+        assert_eq!(
+            "  +------------------------+\n\
+             8 | r  n  b  q  k  b  n  r |\n\
+             7 | p  p  p  p  p  p  p  p |\n\
+             6 | .  .  .  .  .  .  .  . |\n\
+             5 | .  .  .  .  .  .  .  . |\n\
+             4 | .  .  .  .  P  .  .  . |\n\
+             3 | .  .  .  .  .  .  .  . |\n\
+             2 | P  P  P  P  .  P  P  P |\n\
+             1 | R  N  B  Q  K  B  N  R |\n\
+             \x20 +------------------------+\n\
+             \x20   a  b  c  d  e  f  g  h\n\
+             Black to move\n\
+             rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            position.to_string()
+        );
+    }
+
+    #[test]
+    fn test_last_move_and_unicode() {
+        let position = Position::starting();
+        let e4 = position.resolve(&"e4".parse().unwrap()).unwrap();
+        let position = position.play(&e4);
+
+        let diagram = position.diagram().last_move(Some(e4)).to_string();
+        assert!(diagram.contains("4 | .  .  .  . [P] .  .  . |"));
+        assert!(diagram.contains("2 | P  P  P  P [.] P  P  P |"));
+
+        let diagram = format!("{:#}", position);
+        assert!(diagram.contains("8 | ♜  ♞  ♝  ♛  ♚  ♝  ♞  ♜ |"));
+        assert!(diagram.contains("4 | ·  ·  ·  ·  ♙  ·  ·  · |"));
+    }
+}
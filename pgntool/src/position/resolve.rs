@@ -0,0 +1,185 @@
+use crate::pgn_parser::{SanMove, SanMoveDetail, SanMoveType, Square};
+use crate::position::{CastleSide, Move, Position};
+use crate::{PgnError, Result};
+
+fn matches_detail(detail: &SanMoveDetail, mv: &Move) -> bool {
+    mv.castle.is_none()
+        && !mv.drop
+        && mv.piece == detail.piece
+        && mv.to == detail.destination
+        && mv.promotion == detail.promote
+        && detail.from_file.iter().all(|&file| file == mv.from.file)
+        && detail.from_rank.iter().all(|&rank| rank == mv.from.rank)
+}
+
+// Castling is written in coordinates as the king's move, as in UCI, or as the king taking its own
+// rook, as in Chess960 UCI. The king's move is only accepted if the king moves two or more files,
+// since otherwise it is also a plain king move.
+fn matches_castle_coordinates(position: &Position, mv: &Move, to: Square) -> bool {
+    let rook_file = mv.castle.and_then(|side| {
+        position
+            .castling_rights()
+            .rook_file(position.side_to_move(), side)
+    });
+    rook_file == Some(to.file) && mv.from.rank == to.rank
+        || mv.to == to && (mv.to.file.0 as i8 - mv.from.file.0 as i8).abs() >= 2
+}
+
+fn matches(position: &Position, san: &SanMove, mv: &Move) -> bool {
+    match &san.move_type {
+        SanMoveType::ShortCastle => mv.castle == Some(CastleSide::KingSide),
+        SanMoveType::LongCastle => mv.castle == Some(CastleSide::QueenSide),
+        SanMoveType::Move(detail) => matches_detail(detail, mv),
+        SanMoveType::Null => false,
+        SanMoveType::Drop { piece, destination } => {
+            mv.drop && mv.piece == *piece && mv.to == *destination
+        }
+        SanMoveType::Coordinate { .. } if mv.drop => false,
+        SanMoveType::Coordinate { from, to, promote } if mv.castle.is_some() => {
+            mv.from == *from && promote.is_none() && matches_castle_coordinates(position, mv, *to)
+        }
+        SanMoveType::Coordinate { from, to, promote } => {
+            mv.from == *from && mv.to == *to && mv.promotion == *promote
+        }
+    }
+}
+
+impl Position {
+    // Finds the legal move described by the SAN move.
+    //
+    // The capture and check markers are not checked, since they do not change which move is
+    // meant. A null move is allowed unless the side to move is in check, since passing would leave
+    // the opponent to move with the king still attacked.
+    pub fn resolve(&self, san: &SanMove) -> Result<Move> {
+        if san.move_type == SanMoveType::Null {
+            if self.is_check() {
+                return Err(PgnError::IllegalMove(format!(
+                    "{} in check in {}",
+                    san,
+                    self.to_fen()
+                )));
+            }
+            return Ok(Move::null());
+        }
+
+        let mut candidates = self
+            .legal_moves()
+            .into_iter()
+            .filter(|mv| matches(self, san, mv));
+
+        match (candidates.next(), candidates.next()) {
+            (Some(mv), None) => Ok(mv),
+            (None, _) => Err(PgnError::IllegalMove(format!(
+                "{} in {}",
+                san,
+                self.to_fen()
+            ))),
+            (Some(_), Some(_)) => Err(PgnError::AmbiguousMove(format!(
+                "{} in {}",
+                san,
+                self.to_fen()
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pgn_parser::{Piece, SanMove};
+
+    fn san(s: &str) -> SanMove {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_resolve_simple() {
+        let start = Position::starting();
+        let mv = start.resolve(&san("Nf3")).unwrap();
+        assert_eq!("g1f3", mv.to_string());
+
+        let mv = start.resolve(&san("e4")).unwrap();
+        assert_eq!("e2e4", mv.to_string());
+
+        assert!(matches!(
+            start.resolve(&san("e5")),
+            Err(PgnError::IllegalMove(_))
+        ));
+    }
+
+    #[test]
+    fn test_resolve_disambiguation() {
+        let position: Position = "4k3/8/8/8/8/8/4K3/R6R w - - 0 1".parse().unwrap();
+        assert!(matches!(
+            position.resolve(&san("Rd1")),
+            Err(PgnError::AmbiguousMove(_))
+        ));
+        assert_eq!("a1d1", position.resolve(&san("Rad1")).unwrap().to_string());
+        assert_eq!("h1d1", position.resolve(&san("Rhd1")).unwrap().to_string());
+    }
+
+    #[test]
+    fn test_resolve_castle_and_promotion() {
+        let position: Position = "4k3/1P6/8/8/8/8/8/R3K2R w KQ - 0 1".parse().unwrap();
+        let castle = position.resolve(&san("O-O-O")).unwrap();
+        assert_eq!(Some(CastleSide::QueenSide), castle.castle);
+        assert_eq!("e1c1", castle.to_string());
+
+        let promotion = position.resolve(&san("b8=N")).unwrap();
+        assert_eq!(Some(Piece::Knight), promotion.promotion);
+    }
+
+    #[test]
+    fn test_resolve_coordinates() {
+        let position: Position = "4k3/1P6/8/8/8/8/8/R3K2R w KQ - 0 1".parse().unwrap();
+        for coordinates in &["e1c1", "b7b8n", "a1a8"] {
+            let san_move = SanMove {
+                move_type: SanMoveType::Coordinate {
+                    from: coordinates[0..2].parse().unwrap(),
+                    to: coordinates[2..4].parse().unwrap(),
+                    promote: coordinates[4..].chars().next().map(|_| Piece::Knight),
+                },
+                check: crate::pgn_parser::Check::None,
+            };
+            assert_eq!(
+                *coordinates,
+                position.resolve(&san_move).unwrap().to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn test_resolve_chess960_castle() {
+        let position: Position = "4k3/8/8/8/8/8/8/1R4KR w HB - 0 1".parse().unwrap();
+        let castle = position.resolve(&san("O-O")).unwrap();
+        assert_eq!(Some(CastleSide::KingSide), castle.castle);
+        assert_eq!("O-O", position.san(&castle).to_string());
+
+        // As the king taking its own rook, and not as the king's one-square move.
+        let coordinates = |from: &str, to: &str| SanMove {
+            move_type: SanMoveType::Coordinate {
+                from: from.parse().unwrap(),
+                to: to.parse().unwrap(),
+                promote: None,
+            },
+            check: crate::pgn_parser::Check::None,
+        };
+        assert_eq!(castle, position.resolve(&coordinates("g1", "h1")).unwrap());
+        let queen_side = position.resolve(&coordinates("g1", "b1")).unwrap();
+        assert_eq!(Some(CastleSide::QueenSide), queen_side.castle);
+        let king_move = position.resolve(&coordinates("g1", "f1")).unwrap();
+        assert_eq!(None, king_move.castle);
+    }
+
+    #[test]
+    fn test_resolve_null_move() {
+        let start = Position::starting();
+        assert!(start.resolve(&san("--")).unwrap().is_null());
+
+        let check: Position = "4k3/8/8/8/8/8/8/4RK2 b - - 0 1".parse().unwrap();
+        assert!(matches!(
+            check.resolve(&san("--")),
+            Err(PgnError::IllegalMove(_))
+        ));
+    }
+}
@@ -0,0 +1,131 @@
+use crate::pgn_parser::{Check, Piece, SanMove, SanMoveDetail, SanMoveType};
+use crate::position::{CastleSide, Move, Position};
+
+impl Position {
+    // Writes the move in minimal SAN, the reverse of resolve(). The move must be legal in the
+    // position.
+    //
+    // A piece move is disambiguated by its from file if that is enough, then by its from rank,
+    // and otherwise by both. Pawn captures always give the from file.
+    pub fn san(&self, mv: &Move) -> SanMove {
+        let move_type = if mv.is_null() {
+            SanMoveType::Null
+        } else if mv.drop {
+            SanMoveType::Drop {
+                piece: mv.piece,
+                destination: mv.to,
+            }
+        } else {
+            match mv.castle {
+                Some(CastleSide::KingSide) => SanMoveType::ShortCastle,
+                Some(CastleSide::QueenSide) => SanMoveType::LongCastle,
+                None => SanMoveType::Move(self.san_detail(mv)),
+            }
+        };
+
+        let next = self.play(mv);
+        let check = if !next.is_check() {
+            Check::None
+        } else if next.legal_moves().is_empty() {
+            Check::Mate
+        } else {
+            Check::Check
+        };
+
+        SanMove { move_type, check }
+    }
+
+    fn san_detail(&self, mv: &Move) -> SanMoveDetail {
+        let (from_file, from_rank) = if mv.piece == Piece::Pawn {
+            (Some(mv.from.file).filter(|_| mv.is_capture()), None)
+        } else {
+            let rivals: Vec<Move> = self
+                .legal_moves()
+                .into_iter()
+                .filter(|other| {
+                    other.piece == mv.piece
+                        && other.to == mv.to
+                        && other.from != mv.from
+                        && other.castle.is_none()
+                })
+                .collect();
+
+            if rivals.is_empty() {
+                (None, None)
+            } else if rivals.iter().all(|other| other.from.file != mv.from.file) {
+                (Some(mv.from.file), None)
+            } else if rivals.iter().all(|other| other.from.rank != mv.from.rank) {
+                (None, Some(mv.from.rank))
+            } else {
+                (Some(mv.from.file), Some(mv.from.rank))
+            }
+        };
+
+        SanMoveDetail {
+            piece: mv.piece,
+            destination: mv.to,
+            from_file,
+            from_rank,
+            capture: mv.is_capture(),
+            promote: mv.promotion,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Resolves each move in the position, checking that writing it gives the same SAN back.
+    fn assert_round_trip(fen: &str, sans: &[&str]) {
+        let position: Position = fen.parse().unwrap();
+        for san in sans {
+            let mv = position.resolve(&san.parse().unwrap()).unwrap();
+            assert_eq!(*san, position.san(&mv).to_string());
+        }
+    }
+
+    #[test]
+    fn test_simple() {
+        assert_round_trip(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            &["e4", "e3", "Nf3", "Na3"],
+        );
+    }
+
+    #[test]
+    fn test_disambiguation() {
+        // Rooks on a1 and h1: the file is enough.
+        assert_round_trip("4k3/8/8/8/8/8/4K3/R6R w - - 0 1", &["Rad1", "Rhd1", "Ra2"]);
+        // Rooks on a1 and a5: the file isn't, but the rank is.
+        assert_round_trip("4k3/8/8/R7/8/8/4K3/R7 w - - 0 1", &["R1a3", "R5a3", "Rb5"]);
+        // Queens on a1, a3 and c1 all reach b2: a1 needs the full square.
+        assert_round_trip(
+            "4k3/8/8/8/8/Q7/4K3/Q1Q5 w - - 0 1",
+            &["Qa1b2", "Q3b2", "Qcb2"],
+        );
+    }
+
+    #[test]
+    fn test_pawns() {
+        assert_round_trip(
+            "4k3/1P6/8/3pP3/8/8/8/4K3 w - d6 0 1",
+            &["exd6", "e6", "b8=Q+", "b8=N"],
+        );
+    }
+
+    #[test]
+    fn test_castling_and_mate() {
+        assert_round_trip(
+            "3k4/8/8/8/8/8/8/R3K2R w KQ - 0 1",
+            &["O-O", "O-O-O+", "Ra8+"],
+        );
+        assert_round_trip("6k1/5ppp/8/8/8/8/8/R3K3 w Q - 0 1", &["Ra8#", "O-O-O"]);
+    }
+
+    #[test]
+    fn test_null() {
+        let position = Position::starting();
+        assert_eq!("--", position.san(&Move::null()).to_string());
+    }
+}
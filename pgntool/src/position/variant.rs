@@ -0,0 +1,439 @@
+use crate::pgn_parser::{Piece, Rank, Square};
+use crate::position::{CastleSide, Color, ColoredPiece, Move, Position, STARTING_FEN};
+use crate::PgnError;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+const HORDE_FEN: &str =
+    "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1";
+const ANTICHESS_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1";
+
+// The variants Lichess plays. Chess960 only changes the starting position, and is otherwise
+// played by the standard rules.
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash, Default)]
+pub enum Variant {
+    #[default]
+    Standard,
+    Chess960,
+    // Captured pieces go to the capturer's hand, and can be dropped back on the board as a move.
+    Crazyhouse,
+    // Checking the opponent's king three times also wins.
+    ThreeCheck,
+    // Bringing the king to one of the four centre squares also wins.
+    KingOfTheHill,
+    // A capture explodes every piece but pawns next to the capture square, the capturing piece
+    // included, and exploding the opponent's king wins.
+    Atomic,
+    // White has 36 pawns and no king, and Black wins by capturing them all.
+    Horde,
+    // Captures are compulsory and the king is an ordinary piece. Losing every piece, or having no
+    // moves, wins.
+    Antichess,
+}
+
+const VARIANTS: &[Variant] = &[
+    Variant::Standard,
+    Variant::Chess960,
+    Variant::Crazyhouse,
+    Variant::ThreeCheck,
+    Variant::KingOfTheHill,
+    Variant::Atomic,
+    Variant::Horde,
+    Variant::Antichess,
+];
+
+impl Variant {
+    // Whether games are played by the rules of standard chess, as Chess960 games are once they
+    // have started. Opening books and trees and the ECO codes only cover these.
+    pub fn has_standard_rules(self) -> bool {
+        matches!(self, Variant::Standard | Variant::Chess960)
+    }
+
+    // The name of the variant as Lichess writes it in the Variant tag.
+    pub fn name(self) -> &'static str {
+        match self {
+            Variant::Standard => "Standard",
+            Variant::Chess960 => "Chess960",
+            Variant::Crazyhouse => "Crazyhouse",
+            Variant::ThreeCheck => "Three-check",
+            Variant::KingOfTheHill => "King of the Hill",
+            Variant::Atomic => "Atomic",
+            Variant::Horde => "Horde",
+            Variant::Antichess => "Antichess",
+        }
+    }
+
+    // Other names other tools write in the Variant tag, in the form they are compared in.
+    fn aliases(self) -> &'static [&'static str] {
+        match self {
+            Variant::Standard => &["normal", "chess", "fromposition"],
+            Variant::Chess960 => &["fischerandom", "fischerrandom"],
+            Variant::ThreeCheck => &["3check"],
+            Variant::KingOfTheHill => &["koth"],
+            Variant::Antichess => &["giveaway"],
+            _ => &[],
+        }
+    }
+
+    // The position the variant starts from when the game has no FEN tag.
+    pub fn starting_position(self) -> Position {
+        let fen = match self {
+            Variant::Horde => HORDE_FEN,
+            Variant::Antichess => ANTICHESS_FEN,
+            _ => STARTING_FEN,
+        };
+        // unwrap: the starting FENs are known to be valid.
+        fen.parse::<Position>().unwrap().with_variant(self)
+    }
+}
+
+impl Display for Variant {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+// Names are compared ignoring case, spaces and hyphens, so "Three-check", "threecheck" and
+// "King of the hill" are all accepted.
+impl FromStr for Variant {
+    type Err = PgnError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let simplify = |name: &str| -> String {
+            name.chars()
+                .filter(|ch| !matches!(ch, ' ' | '-' | '_'))
+                .map(|ch| ch.to_ascii_lowercase())
+                .collect()
+        };
+        let name = simplify(s);
+        VARIANTS
+            .iter()
+            .copied()
+            .find(|variant| {
+                simplify(variant.name()) == name || variant.aliases().contains(&name.as_str())
+            })
+            .ok_or_else(|| PgnError::UnknownVariant(s.to_string()))
+    }
+}
+
+// The pieces which can be in a Crazyhouse player's hand.
+const POCKET_PIECES: &[Piece] = &[
+    Piece::Queen,
+    Piece::Rook,
+    Piece::Bishop,
+    Piece::Knight,
+    Piece::Pawn,
+];
+
+// The pieces a Crazyhouse player holds, which they can drop on the board instead of moving.
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash, Default)]
+pub struct Pocket {
+    counts: [u8; 5],
+}
+
+impl Pocket {
+    fn index(piece: Piece) -> Option<usize> {
+        POCKET_PIECES
+            .iter()
+            .position(|&pocket_piece| pocket_piece == piece)
+    }
+
+    pub fn count(&self, piece: Piece) -> u8 {
+        Pocket::index(piece).map_or(0, |index| self.counts[index])
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.iter().all(|&count| count == 0)
+    }
+
+    // Each piece in the pocket, as many times as it is held, from the queens to the pawns.
+    pub fn pieces(&self) -> impl Iterator<Item = Piece> + '_ {
+        POCKET_PIECES
+            .iter()
+            .zip(self.counts.iter())
+            .flat_map(|(&piece, &count)| std::iter::repeat_n(piece, usize::from(count)))
+    }
+
+    // Kings can't be held, so adding one does nothing.
+    pub(crate) fn add(&mut self, piece: Piece) {
+        if let Some(index) = Pocket::index(piece) {
+            self.counts[index] = self.counts[index].saturating_add(1);
+        }
+    }
+
+    pub(crate) fn remove(&mut self, piece: Piece) {
+        if let Some(index) = Pocket::index(piece) {
+            self.counts[index] = self.counts[index].saturating_sub(1);
+        }
+    }
+}
+
+impl Position {
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    // Sets the rules the position is played by.
+    pub fn with_variant(mut self, variant: Variant) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    // True if the color's king is on one of the squares it wins on in King of the Hill: d4, e4,
+    // d5 and e5.
+    pub fn king_on_hill(&self, color: Color) -> bool {
+        self.king_square(color).is_some_and(|square| {
+            (4..=5).contains(&square.file.0) && (4..=5).contains(&square.rank.0)
+        })
+    }
+
+    // The pieces the color holds in Crazyhouse.
+    pub fn pocket(&self, color: Color) -> &Pocket {
+        &self.pockets[color.index()]
+    }
+
+    // The number of times the color has given check in Three-check.
+    pub fn checks_given(&self, color: Color) -> u8 {
+        self.checks[color.index()]
+    }
+
+    // True if the piece on the square was promoted, which in Crazyhouse makes it a pawn again
+    // when it is captured.
+    pub fn is_promoted(&self, square: Square) -> bool {
+        self.promoted & (1 << square.index()) != 0
+    }
+
+    pub(crate) fn set_promoted(&mut self, square: Square, promoted: bool) {
+        if promoted {
+            self.promoted |= 1 << square.index();
+        } else {
+            self.promoted &= !(1 << square.index());
+        }
+    }
+
+    pub(crate) fn pocket_mut(&mut self, color: Color) -> &mut Pocket {
+        &mut self.pockets[color.index()]
+    }
+
+    pub(crate) fn add_check(&mut self, color: Color) {
+        let checks = &mut self.checks[color.index()];
+        *checks = checks.saturating_add(1);
+    }
+
+    pub(crate) fn set_checks_given(&mut self, color: Color, checks: u8) {
+        self.checks[color.index()] = checks;
+    }
+
+    // Updates the parts of the position only the variant's rules change, after the move has been
+    // made on the board in `next`.
+    pub(crate) fn play_variant(&self, mv: &Move, next: &mut Position) {
+        let color = self.side_to_move;
+        match self.variant {
+            Variant::Crazyhouse => {
+                if mv.drop {
+                    next.pocket_mut(color).remove(mv.piece);
+                    return;
+                }
+                if let Some(captured) = mv.captured {
+                    let square = if mv.en_passant {
+                        Square::new(mv.to.file, mv.from.rank)
+                    } else {
+                        mv.to
+                    };
+                    let piece = if self.is_promoted(square) {
+                        Piece::Pawn
+                    } else {
+                        captured
+                    };
+                    next.pocket_mut(color).add(piece);
+                    next.set_promoted(square, false);
+                }
+                next.set_promoted(mv.from, false);
+                next.set_promoted(mv.to, mv.promotion.is_some() || self.is_promoted(mv.from));
+            }
+            Variant::Atomic if mv.is_capture() => next.explode(mv.to),
+            _ => {}
+        }
+    }
+
+    // Removes the piece on the square and every piece but pawns around it.
+    fn explode(&mut self, center: Square) {
+        self.set(center, None);
+        for file in -1..=1 {
+            for rank in -1..=1 {
+                if let Some(square) = center.offset(file, rank) {
+                    if self.piece_at(square).map(|piece| piece.piece) != Some(Piece::Pawn) {
+                        self.set(square, None);
+                    }
+                }
+            }
+        }
+
+        // A castling right is lost with its rook, or with the king.
+        for &color in &[Color::White, Color::Black] {
+            for &side in &[CastleSide::KingSide, CastleSide::QueenSide] {
+                let rook = self
+                    .castling
+                    .rook_file(color, side)
+                    .and_then(|file| self.piece_at(Square::new(file, color.back_rank())));
+                if rook != Some(ColoredPiece::new(color, Piece::Rook))
+                    || self.king_square(color).is_none()
+                {
+                    self.castling.set(color, side, None);
+                }
+            }
+        }
+    }
+
+    // The moves dropping a piece from the hand of the side to move on an empty square. Pawns
+    // can't be dropped on the first or last rank.
+    pub(crate) fn drop_moves(&self, moves: &mut Vec<Move>) {
+        let pocket = self.pocket(self.side_to_move);
+        for &piece in POCKET_PIECES
+            .iter()
+            .filter(|&&piece| pocket.count(piece) > 0)
+        {
+            for index in 0..64 {
+                let square = Square::from_index(index);
+                if self.piece_at(square).is_some()
+                    || (piece == Piece::Pawn && matches!(square.rank, Rank(1) | Rank(8)))
+                {
+                    continue;
+                }
+                moves.push(Move::drop_at(piece, square));
+            }
+        }
+    }
+
+    // True if the kings stand next to each other, which in Atomic means neither is in check,
+    // since capturing the other king would explode both.
+    pub(crate) fn kings_touch(&self) -> bool {
+        match (
+            self.king_square(Color::White),
+            self.king_square(Color::Black),
+        ) {
+            (Some(white), Some(black)) => {
+                (white.file.0 as i8 - black.file.0 as i8).abs() <= 1
+                    && (white.rank.0 as i8 - black.rank.0 as i8).abs() <= 1
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::position::CastlingRights;
+
+    fn play(position: &Position, uci: &str) -> Position {
+        let mv = position
+            .legal_moves()
+            .into_iter()
+            .find(|mv| mv.to_string() == uci)
+            .unwrap_or_else(|| panic!("{} is not legal in {}", uci, position.to_fen()));
+        position.play(&mv)
+    }
+
+    fn setup(fen: &str, variant: Variant) -> Position {
+        fen.parse::<Position>().unwrap().with_variant(variant)
+    }
+
+    #[test]
+    fn test_names() {
+        for &variant in VARIANTS {
+            assert_eq!(variant, variant.name().parse().unwrap());
+        }
+        assert_eq!(Variant::ThreeCheck, "threecheck".parse().unwrap());
+        assert_eq!(Variant::KingOfTheHill, "King of the hill".parse().unwrap());
+        assert_eq!(Variant::Standard, "From Position".parse().unwrap());
+        assert!("Racing Kings".parse::<Variant>().is_err());
+    }
+
+    #[test]
+    fn test_starting_positions() {
+        let horde = Variant::Horde.starting_position();
+        let white = horde
+            .pieces()
+            .filter(|(_, piece)| piece.color == Color::White);
+        assert_eq!(36, white.count());
+        assert_eq!(None, horde.king_square(Color::White));
+
+        // Pawns on the first rank can advance two squares, but can't be taken en passant.
+        let horde = setup("4k3/8/8/8/8/8/8/P7 w - - 0 1", Variant::Horde);
+        assert_eq!(None, play(&horde, "a1a3").en_passant());
+
+        let antichess = Variant::Antichess.starting_position();
+        assert_eq!(CastlingRights::default(), antichess.castling_rights());
+    }
+
+    #[test]
+    fn test_crazyhouse() {
+        let mut position = Variant::Crazyhouse.starting_position();
+        for uci in &["e2e4", "d7d5", "e4d5", "d8d5"] {
+            position = play(&position, uci);
+        }
+        assert_eq!(1, position.pocket(Color::White).count(Piece::Pawn));
+        assert_eq!(1, position.pocket(Color::Black).count(Piece::Pawn));
+
+        // A pawn can be dropped on any empty square but those on the first and last ranks.
+        let drops = position.legal_moves().into_iter().filter(|mv| mv.drop);
+        assert_eq!(33, drops.count());
+        let position = play(&position, "P@e6");
+        assert!(position.pocket(Color::White).is_empty());
+        assert_eq!(
+            Some(ColoredPiece::new(Color::White, Piece::Pawn)),
+            position.piece_at("e6".parse().unwrap())
+        );
+
+        // A promoted piece goes back to being a pawn when it is captured.
+        let position = setup("4k3/1P6/8/8/8/8/4K3/1r6 w - - 0 1", Variant::Crazyhouse);
+        let position = play(&position, "b7b8q");
+        assert!(position.is_promoted("b8".parse().unwrap()));
+        let position = play(&position, "b1b8");
+        assert!(!position.is_promoted("b8".parse().unwrap()));
+        assert_eq!(1, position.pocket(Color::Black).count(Piece::Pawn));
+        assert_eq!(0, position.pocket(Color::Black).count(Piece::Queen));
+    }
+
+    #[test]
+    fn test_atomic() {
+        // Nxf7 explodes the knight and the king on e8, but not the pawns next to it.
+        let position = setup("4k3/5pp1/8/6N1/8/8/8/4K3 w - - 0 1", Variant::Atomic);
+        let position = play(&position, "g5f7");
+        assert_eq!(None, position.king_square(Color::Black));
+        assert_eq!(None, position.piece_at("f7".parse().unwrap()));
+        assert!(position.piece_at("g7".parse().unwrap()).is_some());
+
+        // The king can't capture, and a capture can't explode its own king.
+        let position = setup("4k3/8/8/8/8/8/3q4/3QK3 w - - 0 1", Variant::Atomic);
+        assert!(position.legal_moves().iter().all(|mv| !mv.is_capture()));
+
+        // Kings next to each other can't give check.
+        let touching = setup("8/8/8/8/8/3k4/3K4/3r4 w - - 0 1", Variant::Atomic);
+        assert!(!touching.is_check());
+    }
+
+    #[test]
+    fn test_antichess() {
+        // The capture is compulsory.
+        let position = setup("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1", Variant::Antichess);
+        let moves = position.legal_moves();
+        assert_eq!(1, moves.len());
+        assert_eq!("e4d5", moves[0].to_string());
+
+        // Pawns can promote to kings.
+        let position = setup("8/P7/8/8/8/8/8/7k w - - 0 1", Variant::Antichess);
+        assert_eq!(5, position.legal_moves().len());
+    }
+
+    #[test]
+    fn test_three_check() {
+        let mut position = Variant::ThreeCheck.starting_position();
+        for uci in &["e2e4", "f7f6", "d1h5"] {
+            position = play(&position, uci);
+        }
+        assert_eq!(1, position.checks_given(Color::White));
+        assert_eq!(0, position.checks_given(Color::Black));
+    }
+}
@@ -0,0 +1,376 @@
+use crate::compression;
+use crate::encoding::{decode_with, Encoding};
+use crate::pgn_parser::{is_escape_line, parse_pgn_with, ParseOptions, PgnGame};
+use crate::Result;
+use std::collections::VecDeque;
+use std::io::BufRead;
+use std::path::Path;
+
+const TERMINATIONS: &[&str] = &["1-0", "0-1", "1/2-1/2", "*"];
+
+// Removes comments from the line, tracking brace comments which continue across lines.
+fn strip_comments(line: &str, in_brace: &mut bool) -> String {
+    let mut stripped = String::new();
+    for ch in line.chars() {
+        if *in_brace {
+            if ch == '}' {
+                *in_brace = false;
+            }
+            stripped.push(' ');
+        } else if ch == '{' {
+            *in_brace = true;
+            stripped.push(' ');
+        } else if ch == ';' {
+            break;
+        } else {
+            stripped.push(ch);
+        }
+    }
+    stripped
+}
+
+// The text of one game, as read from the input.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct RawGame {
+    // The byte offset of the start of the game in the input, and its length in bytes. The
+    // length differs from that of the text if the input isn't UTF-8.
+    pub offset: u64,
+    pub length: u64,
+    pub text: String,
+}
+
+impl RawGame {
+    fn new(offset: u64) -> Self {
+        RawGame {
+            offset,
+            length: 0,
+            text: String::new(),
+        }
+    }
+
+    fn push_line(&mut self, line: &Line) {
+        self.text.push_str(&line.text);
+        self.length = line.end - self.offset;
+    }
+}
+
+// A line of input, decoded, with the byte offsets of its start and end.
+struct Line {
+    start: u64,
+    end: u64,
+    text: String,
+}
+
+// Reads games one at a time, so that large databases don't have to be held in memory.
+//
+// Games are split on their game termination markers, so a game with a syntax error doesn't
+// affect the games after it. Reading stops at the first I/O or decoding error.
+//
+// Unless an encoding is given, a byte order mark decides it, and otherwise each line is decoded
+// as UTF-8 if it is valid UTF-8 and as Windows-1252 if it isn't.
+pub struct GameReader<R> {
+    reader: R,
+    offset: u64,
+    encoding: Option<Encoding>,
+    // A line read past the end of a game which belongs to the next one.
+    carried_line: Option<Line>,
+    pending: VecDeque<PgnGame>,
+    done: bool,
+    options: ParseOptions,
+    warnings: Vec<String>,
+}
+
+impl GameReader<Box<dyn BufRead>> {
+    // Opens a file, which is decompressed if it is compressed.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(GameReader::new(compression::open(path)?))
+    }
+}
+
+impl<R: BufRead> GameReader<R> {
+    pub fn new(reader: R) -> Self {
+        GameReader {
+            reader,
+            offset: 0,
+            encoding: None,
+            carried_line: None,
+            pending: VecDeque::new(),
+            done: false,
+            options: ParseOptions::default(),
+            warnings: vec![],
+        }
+    }
+
+    pub fn with_options(mut self, options: ParseOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = Some(encoding);
+        self
+    }
+
+    // The encoding given, or found from a byte order mark. None if each line is detected.
+    pub fn encoding(&self) -> Option<Encoding> {
+        self.encoding
+    }
+
+    // Returns the parse warnings for the games read since the last call.
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    fn read_line(&mut self) -> Result<Option<Line>> {
+        if let Some(line) = self.carried_line.take() {
+            return Ok(Some(line));
+        }
+
+        // A byte order mark gives the encoding, and isn't part of the first game.
+        if self.offset == 0 {
+            let marked = Encoding::from_byte_order_mark(self.reader.fill_buf()?);
+            if let Some((encoding, length)) = marked {
+                self.reader.consume(length);
+                self.offset = length as u64;
+                self.encoding.get_or_insert(encoding);
+            }
+        }
+
+        let start = self.offset;
+        let mut bytes = vec![];
+        match self.encoding {
+            Some(Encoding::Utf16Le) | Some(Encoding::Utf16Be) => {
+                self.read_utf16_line(&mut bytes)?
+            }
+            _ => {
+                self.reader.read_until(b'\n', &mut bytes)?;
+            }
+        }
+        if bytes.is_empty() {
+            return Ok(None);
+        }
+        self.offset += bytes.len() as u64;
+
+        Ok(Some(Line {
+            start,
+            end: self.offset,
+            text: decode_with(&bytes, self.encoding)?,
+        }))
+    }
+
+    // Reads up to and including a newline in UTF-16, where each character is two bytes and a
+    // newline byte can be half of another character.
+    fn read_utf16_line(&mut self, bytes: &mut Vec<u8>) -> Result<()> {
+        let newline = match self.encoding {
+            Some(Encoding::Utf16Be) => [0, b'\n'],
+            _ => [b'\n', 0],
+        };
+        loop {
+            if self.reader.read_until(b'\n', bytes)? == 0 {
+                return Ok(());
+            }
+            if !bytes.len().is_multiple_of(2) {
+                let mut next = [0];
+                if self.reader.read(&mut next)? == 0 {
+                    return Ok(());
+                }
+                bytes.push(next[0]);
+            }
+            if bytes.ends_with(&newline) {
+                return Ok(());
+            }
+        }
+    }
+
+    // Reads the text of the next game without parsing it.
+    pub fn next_raw(&mut self) -> Option<Result<RawGame>> {
+        if self.done {
+            return None;
+        }
+
+        let mut game: Option<RawGame> = None;
+        let mut in_brace = false;
+        let mut has_movetext = false;
+        loop {
+            let line = match self.read_line() {
+                Ok(Some(line)) => line,
+                Ok(None) => {
+                    self.done = true;
+                    break;
+                }
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+            };
+
+            // Escape lines are kept with the game they are in or before, and are otherwise
+            // ignored: they don't start or end games, or open comments.
+            if is_escape_line(&line.text) {
+                game.get_or_insert(RawGame::new(line.start))
+                    .push_line(&line);
+                continue;
+            }
+
+            let is_tag = !in_brace && line.text.trim_start().starts_with('[');
+            if is_tag && has_movetext {
+                // The previous game had no termination marker.
+                self.carried_line = Some(line);
+                break;
+            }
+            if game.is_none() && line.text.trim().is_empty() {
+                continue;
+            }
+
+            let stripped = strip_comments(&line.text, &mut in_brace);
+            game.get_or_insert(RawGame::new(line.start))
+                .push_line(&line);
+
+            if !is_tag && !stripped.trim().is_empty() {
+                has_movetext = true;
+                if !in_brace
+                    && stripped
+                        .split_whitespace()
+                        .last()
+                        .iter()
+                        .all(|token| TERMINATIONS.contains(token))
+                {
+                    break;
+                }
+            }
+        }
+
+        game.map(Ok)
+    }
+
+    // Turns the reader into an iterator over the text of the games.
+    pub fn into_raw(mut self) -> impl Iterator<Item = Result<RawGame>> {
+        std::iter::from_fn(move || self.next_raw())
+    }
+}
+
+impl<R: BufRead> Iterator for GameReader<R> {
+    type Item = Result<PgnGame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            let raw = match self.next_raw()? {
+                Ok(raw) => raw,
+                Err(err) => return Some(Err(err)),
+            };
+            match parse_pgn_with(&raw.text, self.options) {
+                Ok((database, warnings)) => {
+                    self.pending.extend(database.into_games());
+                    self.warnings.extend(warnings);
+                }
+                Err(err) => return Some(Err(err)),
+            }
+        }
+        self.pending.pop_front().map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PGN: &str = "[Event \"One\"]\n\n1. e4 {A comment\nwith 1-0 in it} e5 1-0\n\n\
+                       [Event \"Two\"]\n\n1. d4 ; 0-1\nd5\n0-1\n\n\
+                       1. c4 *\n";
+
+    #[test]
+    fn test_next_raw() {
+        let mut reader = GameReader::new(PGN.as_bytes());
+        let first = reader.next_raw().unwrap().unwrap();
+        assert_eq!(0, first.offset);
+        assert!(first.text.ends_with("e5 1-0\n"));
+
+        let second = reader.next_raw().unwrap().unwrap();
+        assert_eq!(PGN.find("[Event \"Two\"]").unwrap() as u64, second.offset);
+        assert!(second.text.ends_with("d5\n0-1\n"));
+
+        let third = reader.next_raw().unwrap().unwrap();
+        assert_eq!("1. c4 *\n", third.text);
+        assert!(reader.next_raw().is_none());
+    }
+
+    #[test]
+    fn test_games() {
+        let games: Vec<PgnGame> = GameReader::new(PGN.as_bytes())
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(3, games.len());
+        assert_eq!(Some("Two"), games[1].tag("Event"));
+    }
+
+    #[test]
+    fn test_options() {
+        let mut reader =
+            GameReader::new("1. e2-e4 *\n".as_bytes()).with_options(ParseOptions::lenient());
+        assert!(reader.next().unwrap().is_ok());
+        assert_eq!(vec!["long algebraic move e2-e4"], reader.take_warnings());
+    }
+
+    #[test]
+    fn test_byte_order_mark_and_escape_lines() {
+        let pgn =
+            "\u{feff}[Event \"One\"]\r\n\r\n1. e4 *\r\n%eval 1-0 {\r\n[Event \"Two\"]\n\n1. d4 *\n";
+        let mut reader = GameReader::new(pgn.as_bytes());
+        let first = reader.next_raw().unwrap().unwrap();
+        assert_eq!(3, first.offset);
+        assert!(first.text.starts_with("[Event"));
+        let second = reader.next_raw().unwrap().unwrap();
+        assert!(second.text.starts_with("%eval 1-0 {\r\n[Event"));
+
+        let options = ParseOptions {
+            keep_escape_lines: true,
+            ..ParseOptions::default()
+        };
+        let games: Vec<PgnGame> = GameReader::new(pgn.as_bytes())
+            .with_options(options)
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(Some("One"), games[0].tag("Event"));
+        assert_eq!(vec!["%eval 1-0 {"], games[1].escape_lines());
+        assert!(games[1]
+            .to_string()
+            .starts_with("%eval 1-0 {\n[Event \"Two\"]"));
+    }
+
+    #[test]
+    fn test_encodings() {
+        let pgn = b"[White \"M\xfcller\"]\n\n1. e4 *\n\n[White \"R\xc3\xa9ti\"]\n\n1. Nf3 *\n";
+        let mut reader = GameReader::new(&pgn[..]);
+        let first = reader.next_raw().unwrap().unwrap();
+        assert!(first.text.contains("Müller"));
+        assert_eq!(first.text.len() as u64 - 1, first.length);
+        let second = reader.next().unwrap().unwrap();
+        assert_eq!(Some("Réti"), second.tag("White"));
+
+        let mut reader = GameReader::new(&pgn[..]).with_encoding(Encoding::Latin1);
+        reader.next_raw();
+        assert_eq!(Some("RÃ©ti"), reader.next().unwrap().unwrap().tag("White"));
+
+        let mut pgn = Encoding::Utf16Le.byte_order_mark().to_vec();
+        pgn.extend(
+            Encoding::Utf16Le
+                .encode("[White \"Müller\"]\n\n1. e4 *\n\n[White \"Ō\"]\n\n1. d4 *\n")
+                .unwrap(),
+        );
+        let games: Vec<PgnGame> = GameReader::new(&pgn[..]).collect::<Result<_>>().unwrap();
+        assert_eq!(2, games.len());
+        assert_eq!(Some("Müller"), games[0].tag("White"));
+        assert_eq!(Some("Ō"), games[1].tag("White"));
+    }
+
+    #[test]
+    fn test_missing_termination() {
+        let pgn = "[Event \"One\"]\n\n1. e4 e5\n\n[Event \"Two\"]\n\n1. d4 *\n";
+        let mut reader = GameReader::new(pgn.as_bytes());
+        // The first game is an error, but the second one is still read.
+        assert!(reader.next().unwrap().is_err());
+        let second = reader.next().unwrap().unwrap();
+        assert_eq!(Some("Two"), second.tag("Event"));
+        assert!(reader.next().is_none());
+    }
+}
@@ -0,0 +1,260 @@
+use crate::pgn_parser::{
+    Element, ElementSequence, PgnGame, SanMove, SanMoveDetail, SanMoveType, SequenceMember,
+};
+use crate::position::{Move, Position, Variant};
+use crate::{PgnError, Result};
+
+// Returns the position a game starts from: the FEN tag if there is one, otherwise the starting
+// position of the game's variant. The Variant tag, as Lichess writes it, gives the rules the game
+// is played by.
+pub fn starting_position(game: &PgnGame) -> Result<Position> {
+    let variant = match game.tag("Variant") {
+        Some(variant) => variant.parse()?,
+        None => Variant::Standard,
+    };
+    match game.tag("FEN") {
+        Some(fen) => Ok(fen.parse::<Position>()?.with_variant(variant)),
+        None => Ok(variant.starting_position()),
+    }
+}
+
+// Returns an error for a game played by rules other than those of standard chess, for the code
+// which only understands those.
+pub fn require_standard_rules(game: &PgnGame) -> Result<()> {
+    let variant = starting_position(game)?.variant();
+    if variant.has_standard_rules() {
+        Ok(())
+    } else {
+        Err(PgnError::UnsupportedVariant(variant.to_string()))
+    }
+}
+
+// The mainline of a game, replayed move by move.
+#[derive(Debug, Clone)]
+pub struct Mainline {
+    // positions[i] is the position before moves[i]. There is one more position than there are
+    // moves: the last one is the position after the final move.
+    positions: Vec<Position>,
+    moves: Vec<Move>,
+}
+
+impl Mainline {
+    pub fn replay(game: &PgnGame) -> Result<Mainline> {
+        Mainline::replay_plies(game, usize::MAX)
+    }
+
+    // Replays at most `plies` half-moves of the mainline. Moves after that are not checked.
+    pub fn replay_plies(game: &PgnGame, plies: usize) -> Result<Mainline> {
+        let mut position = starting_position(game)?;
+        let mut positions = vec![];
+        let mut moves = vec![];
+
+        for san_move in game.movetext().element_sequence().moves().take(plies) {
+            let mv = position.resolve(san_move)?;
+            let next = position.play(&mv);
+            positions.push(position);
+            moves.push(mv);
+            position = next;
+        }
+        positions.push(position);
+
+        Ok(Mainline { positions, moves })
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    pub fn positions(&self) -> &[Position] {
+        &self.positions
+    }
+
+    pub fn final_position(&self) -> &Position {
+        // unwrap: there is always at least the starting position.
+        self.positions.last().unwrap()
+    }
+
+    // Iterates over each move along with the position it was played in.
+    pub fn plies(&self) -> impl Iterator<Item = (&Position, &Move)> {
+        self.positions.iter().zip(self.moves.iter())
+    }
+}
+
+// Rewrites the moves written with their from square, in long algebraic or coordinate notation,
+// in standard SAN, in the mainline and in every variation. Other moves are kept as they were
+// written.
+//
+// The moves before an illegal move are still rewritten, since their SAN means the same move.
+pub fn normalize_moves(game: &mut PgnGame) -> Result<()> {
+    let start = starting_position(game)?;
+    normalize_sequence(game.movetext_mut().element_sequence_mut(), start)
+}
+
+fn normalize_sequence(sequence: &mut ElementSequence, start: Position) -> Result<()> {
+    // A variation replaces the last move, so it is played from the position before it.
+    let mut before = start.clone();
+    let mut position = start;
+    for member in sequence.members_mut() {
+        match member {
+            SequenceMember::Move(Element::Move(san)) => {
+                let mv = position.resolve(san)?;
+                if has_from_square(san) {
+                    *san = position.san(&mv);
+                }
+                let next = position.play(&mv);
+                before = std::mem::replace(&mut position, next);
+            }
+            SequenceMember::Variation(variation) => {
+                normalize_sequence(variation.sequence_mut(), before.clone())?
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn has_from_square(san: &SanMove) -> bool {
+    matches!(
+        san.move_type,
+        SanMoveType::Coordinate { .. }
+            | SanMoveType::Move(SanMoveDetail {
+                from_file: Some(_),
+                from_rank: Some(_),
+                ..
+            })
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::game_tree::GameTree;
+    use crate::pgn_parser::ParseOptions;
+    use crate::{parse_pgn, parse_pgn_with};
+
+    #[test]
+    fn test_replay() {
+        let database = parse_pgn("1. e4 c6 2. d4 ( e5 e6 ) d5 *").unwrap();
+        let mainline = Mainline::replay(&database.games()[0]).unwrap();
+        assert_eq!(4, mainline.moves().len());
+        assert_eq!(5, mainline.positions().len());
+        assert_eq!(
+            "rnbqkbnr/pp2pppp/2p5/3p4/3PP3/8/PPP2PPP/RNBQKBNR w KQkq d6 0 3",
+            mainline.final_position().to_fen()
+        );
+    }
+
+    #[test]
+    fn test_replay_plies() {
+        let database = parse_pgn("1. e4 c6 2. d4 d5 *").unwrap();
+        let mainline = Mainline::replay_plies(&database.games()[0], 2).unwrap();
+        assert_eq!(2, mainline.moves().len());
+    }
+
+    #[test]
+    fn test_replay_from_fen() {
+        let database =
+            parse_pgn("[FEN \"4k3/8/8/8/8/8/8/R3K3 w Q - 0 1\"]\n\n1. O-O-O Kf7 *").unwrap();
+        let mainline = Mainline::replay(&database.games()[0]).unwrap();
+        assert_eq!(
+            "8/5k2/8/8/8/8/8/2KR4 w - - 2 2",
+            mainline.final_position().to_fen()
+        );
+    }
+
+    #[test]
+    fn test_replay_chess960() {
+        let database = parse_pgn(
+            "[Variant \"Chess960\"]\n\
+             [FEN \"bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w HFhf - 0 1\"]\n\n\
+             1. Nd3 Nd6 2. Ne3 Ne6 3. c3 c6 4. Qc2 Qc7 5. O-O-O O-O-O *",
+        )
+        .unwrap();
+        let mainline = Mainline::replay(&database.games()[0]).unwrap();
+        assert_eq!(
+            "bbkr3r/ppqppppp/2pnn3/8/8/2PNN3/PPQPPPPP/BBKR3R w - - 4 6",
+            mainline.final_position().to_fen()
+        );
+    }
+
+    #[test]
+    fn test_replay_variants() {
+        let database = parse_pgn(
+            "[Variant \"Crazyhouse\"]\n\n1. e4 d5 2. exd5 Qxd5 3. Nc3 Qd8 4. P@d5 P@e5 *\n\n\
+             [Variant \"Horde\"]\n\n1. b6 axb6 *\n\n\
+             [Variant \"Racing Kings\"]\n\n1. Ka3 *",
+        )
+        .unwrap();
+        let games = database.games();
+        let mainline = Mainline::replay(&games[0]).unwrap();
+        assert_eq!(
+            "rnbqkbnr/ppp1pppp/8/3Pp3/8/2N5/PPPP1PPP/R1BQKBNR[] w KQkq - 0 5",
+            mainline.final_position().to_fen()
+        );
+        assert_eq!(
+            "P@e5",
+            mainline.positions()[7]
+                .san(&mainline.moves()[7])
+                .to_string()
+        );
+
+        let mainline = Mainline::replay(&games[1]).unwrap();
+        assert_eq!(Variant::Horde, mainline.final_position().variant());
+        assert!(Mainline::replay(&games[2]).is_err());
+    }
+
+    #[test]
+    fn test_replay_null_move() {
+        let database = parse_pgn("1. e4 -- 2. d4 ( 2. Z0 ) e5 *").unwrap();
+        let mainline = Mainline::replay(&database.games()[0]).unwrap();
+        assert!(mainline.moves()[1].is_null());
+        assert_eq!(
+            "rnbqkbnr/pppp1ppp/8/4p3/3PP3/8/PPP2PPP/RNBQKBNR w KQkq e6 0 3",
+            mainline.final_position().to_fen()
+        );
+
+        // A null move inside a variation replays from the variation's own position.
+        let database = parse_pgn("1. e4 e5 ( 1... -- 2. d4 ) 2. Nf3 *").unwrap();
+        let tree = GameTree::from_game(&database.games()[0]).unwrap();
+        let first = tree.mainline()[0];
+        let null = tree.node(first).children()[1];
+        assert!(tree.node(null).san().unwrap().move_type == SanMoveType::Null);
+        let d4 = tree.node(null).children()[0];
+        assert_eq!(
+            "rnbqkbnr/pppppppp/8/8/3PP3/8/PPP2PPP/RNBQKBNR b KQkq d3 0 2",
+            tree.position(d4).unwrap().to_fen()
+        );
+    }
+
+    #[test]
+    fn test_null_move_in_check() {
+        let database = parse_pgn("1. e4 f5 2. Qh5+ -- *").unwrap();
+        assert!(Mainline::replay(&database.games()[0]).is_err());
+    }
+
+    #[test]
+    fn test_normalize_moves() {
+        let text = "1. e2-e4 e7e5 (1... c7c5 2. Ng1-f3) 2. Ng1-f3 Nb8c6 3. Bf1xb5 *";
+        let (database, _) = parse_pgn_with(text, ParseOptions::lenient()).unwrap();
+        let mut game = database.into_games().remove(0);
+        normalize_moves(&mut game).unwrap();
+        assert_eq!(
+            "1. e4 e5 (1... c5 2. Nf3) 2. Nf3 Nc6 3. Bb5 *",
+            game.movetext().to_string().trim_end()
+        );
+
+        // Moves in SAN are kept as written, even when over-disambiguated.
+        let mut game = parse_pgn("1. e4 e5 2. Ngf3 *")
+            .unwrap()
+            .into_games()
+            .remove(0);
+        normalize_moves(&mut game).unwrap();
+        assert_eq!("1. e4 e5 2. Ngf3 *", game.movetext().to_string().trim_end());
+    }
+
+    #[test]
+    fn test_illegal_move() {
+        let database = parse_pgn("1. e4 e4 *").unwrap();
+        assert!(Mainline::replay(&database.games()[0]).is_err());
+    }
+}
@@ -0,0 +1,71 @@
+use crate::pgn_parser::PgnGame;
+use std::cmp::Ordering;
+
+// One period-separated part of a tag value.
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone)]
+enum KeyPart {
+    Number(u64),
+    Text(String),
+}
+
+// A sort key for a tag value, which orders dates ("2021.09.11") and rounds ("3.10") by their
+// numeric parts. Unknown parts like "??" sort after known ones, and missing tags sort last.
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone)]
+pub struct TagKey {
+    missing: bool,
+    parts: Vec<KeyPart>,
+}
+
+impl TagKey {
+    pub fn new(value: Option<&str>) -> Self {
+        TagKey {
+            missing: value.is_none(),
+            parts: value
+                .unwrap_or_default()
+                .split('.')
+                .map(|part| match part.trim().parse() {
+                    Ok(number) => KeyPart::Number(number),
+                    Err(_) => KeyPart::Text(part.trim().to_string()),
+                })
+                .collect(),
+        }
+    }
+}
+
+// Returns the keys for sorting a game by the given tags, in order of priority.
+pub fn sort_key(game: &PgnGame, tags: &[String]) -> Vec<TagKey> {
+    tags.iter().map(|tag| TagKey::new(game.tag(tag))).collect()
+}
+
+pub fn compare_games(a: &PgnGame, b: &PgnGame, tags: &[String]) -> Ordering {
+    sort_key(a, tags).cmp(&sort_key(b, tags))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse_pgn;
+
+    #[test]
+    fn test_tag_key() {
+        assert!(TagKey::new(Some("3.2")) < TagKey::new(Some("3.10")));
+        assert!(TagKey::new(Some("2021.09.11")) < TagKey::new(Some("2021.10.01")));
+        assert!(TagKey::new(Some("2021.09.11")) < TagKey::new(Some("2021.??.??")));
+        assert!(TagKey::new(Some("Zurich")) < TagKey::new(None));
+    }
+
+    #[test]
+    fn test_compare_games() {
+        let database = parse_pgn(
+            "[Date \"2021.01.01\"]\n[Round \"10\"]\n\n*\n\n\
+             [Date \"2021.01.01\"]\n[Round \"9\"]\n\n*",
+        )
+        .unwrap();
+        let games = database.games();
+        let tags = vec!["Date".to_string(), "Round".to_string()];
+        assert_eq!(
+            Ordering::Greater,
+            compare_games(&games[0], &games[1], &tags)
+        );
+    }
+}
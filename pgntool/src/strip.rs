@@ -0,0 +1,175 @@
+use crate::pgn_parser::{Element, ElementSequence, PgnGame, SequenceMember, TagSection};
+
+// The Seven Tag Roster, which every game must have.
+pub const SEVEN_TAG_ROSTER: [&str; 7] =
+    ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+// What to remove from games. The default removes nothing.
+#[derive(Debug, Default, Eq, PartialEq, Copy, Clone)]
+pub struct StripOptions {
+    // The deepest variations to keep: Some(0) removes all of them, Some(1) keeps variations but
+    // not variations inside them, and None keeps everything.
+    pub variation_depth: Option<usize>,
+    pub comments: bool,
+    pub nags: bool,
+    // Removes commands such as "[%clk 0:03:00]" from comments, keeping their text.
+    pub commands: bool,
+    // Removes every tag except the Seven Tag Roster and the FEN, SetUp and Variant tags a game
+    // needs to be replayed.
+    pub tags: bool,
+}
+
+pub fn strip_game(game: &mut PgnGame, options: &StripOptions) {
+    let sequence = game.movetext_mut().element_sequence_mut();
+    if let Some(depth) = options.variation_depth {
+        limit_variations(sequence, depth);
+    }
+    if options.comments {
+        strip_comments(sequence);
+    }
+    if options.commands {
+        strip_commands(sequence);
+    }
+    if options.nags {
+        strip_nags(sequence);
+    }
+    if options.tags {
+        strip_tags(game.tags_mut());
+    }
+}
+
+// Applies `strip` to the sequence and to every variation in it.
+fn strip_recursively(sequence: &mut ElementSequence, strip: &impl Fn(&mut Vec<SequenceMember>)) {
+    let members = sequence.members_mut();
+    strip(members);
+    for member in members {
+        if let SequenceMember::Variation(variation) = member {
+            strip_recursively(variation.sequence_mut(), strip);
+        }
+    }
+}
+
+// Removes variations nested more than `depth` deep.
+pub fn limit_variations(sequence: &mut ElementSequence, depth: usize) {
+    let members = sequence.members_mut();
+    match depth.checked_sub(1) {
+        None => members.retain(|member| !matches!(member, SequenceMember::Variation(_))),
+        Some(inner_depth) => {
+            for member in members {
+                if let SequenceMember::Variation(variation) = member {
+                    limit_variations(variation.sequence_mut(), inner_depth);
+                }
+            }
+        }
+    }
+}
+
+pub fn strip_comments(sequence: &mut ElementSequence) {
+    strip_recursively(sequence, &|members| {
+        members.retain(|member| !matches!(member, SequenceMember::Comment(_)))
+    });
+}
+
+// Removes the commands from comments, and the comments which only had commands.
+pub fn strip_commands(sequence: &mut ElementSequence) {
+    strip_recursively(sequence, &|members| {
+        for member in members.iter_mut() {
+            if let SequenceMember::Comment(comment) = member {
+                *comment = comment.without_commands();
+            }
+        }
+        members.retain(|member| match member {
+            SequenceMember::Comment(comment) => !comment.text().is_empty(),
+            _ => true,
+        })
+    });
+}
+
+pub fn strip_nags(sequence: &mut ElementSequence) {
+    strip_recursively(sequence, &|members| {
+        members.retain(|member| !matches!(member, SequenceMember::Move(Element::Annotation(_))))
+    });
+}
+
+// The tags which give the starting position and the rules of a game.
+const REPLAY_TAGS: [&str; 3] = ["FEN", "SetUp", "Variant"];
+
+pub fn strip_tags(tags: &mut TagSection) {
+    tags.retain(|pair| {
+        SEVEN_TAG_ROSTER.contains(&pair.name()) || REPLAY_TAGS.contains(&pair.name())
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse_pgn;
+
+    const GAME: &str = "[Event \"Club\"]\n[Annotator \"Coach\"]\n[Result \"*\"]\n\n\
+                        1. e4 $1 {Best [%clk 0:05:00]} e5 (1... c5 {Sicilian} (1... e6 $2) 2. Nf3) \
+                        2. Nf3 {[%csl Gf3]} *";
+
+    fn strip(options: StripOptions) -> String {
+        let mut game = parse_pgn(GAME).unwrap().into_games().remove(0);
+        strip_game(&mut game, &options);
+        game.to_string().trim_end().to_string()
+    }
+
+    #[test]
+    fn test_nothing() {
+        let stripped = strip(StripOptions::default());
+        assert!(stripped.contains("[Annotator \"Coach\"]"));
+        assert!(stripped.contains("(1... e6 $2)"));
+    }
+
+    #[test]
+    fn test_variations() {
+        let options = StripOptions {
+            variation_depth: Some(0),
+            ..StripOptions::default()
+        };
+        assert!(strip(options).ends_with("1. e4 $1 {Best [%clk 0:05:00]} e5 2. Nf3 {[%csl Gf3]} *"));
+
+        let options = StripOptions {
+            variation_depth: Some(1),
+            ..StripOptions::default()
+        };
+        assert!(strip(options).contains("e5 (1... c5 {Sicilian} 2. Nf3) 2. Nf3"));
+    }
+
+    #[test]
+    fn test_annotations() {
+        let options = StripOptions {
+            comments: true,
+            nags: true,
+            ..StripOptions::default()
+        };
+        assert!(strip(options).ends_with("1. e4 e5 (1... c5 (1... e6) 2. Nf3) 2. Nf3 *"));
+
+        let options = StripOptions {
+            commands: true,
+            ..StripOptions::default()
+        };
+        assert!(strip(options).contains("1. e4 $1 {Best} e5"));
+        assert!(strip(options).ends_with("2. Nf3) 2. Nf3 *"));
+    }
+
+    #[test]
+    fn test_tags() {
+        let options = StripOptions {
+            tags: true,
+            ..StripOptions::default()
+        };
+        let stripped = strip(options);
+        assert!(stripped.starts_with("[Event \"Club\"]\n[Result \"*\"]\n"));
+        assert!(!stripped.contains("Annotator"));
+
+        let mut game = parse_pgn("[Variant \"Chess960\"]\n[Opening \"?\"]\n\n*")
+            .unwrap()
+            .into_games()
+            .remove(0);
+        strip_tags(game.tags_mut());
+        assert_eq!(Some("Chess960"), game.tag("Variant"));
+        assert_eq!(None, game.tag("Opening"));
+    }
+}
@@ -0,0 +1,474 @@
+use crate::pgn_parser::{Comment, File, Piece, Rank, Square};
+use crate::position::{Color, ColoredPiece, Position};
+use std::fmt::{Display, Formatter};
+
+// The board is drawn in units of squares 45 wide, and scaled to the size asked for.
+const SQUARE_SIZE: f64 = 45.0;
+const BOARD_SIZE: f64 = SQUARE_SIZE * 8.0;
+
+// The outline of each piece within a 45 by 45 square. Each one stands on the same base.
+const BASE: &str = "M 11 39 h 23 v -4 h -23 Z";
+
+fn piece_path(piece: Piece) -> &'static str {
+    match piece {
+        Piece::Pawn => {
+            "M 17.5 12 a 5 5 0 1 1 10 0 a 5 5 0 1 1 -10 0 Z \
+             M 19 17 h 7 l 4 18 h -15 Z"
+        }
+        Piece::Knight => {
+            "M 14 35 c 0 -8 4 -12 8 -14 c -4 0 -7 2 -9 4 l -3 -3 \
+             c 3 -6 8 -12 13 -13 l 1 -3 l 2 3 c 6 2 10 9 9 26 Z"
+        }
+        Piece::Bishop => {
+            "M 20.5 7 a 2 2 0 1 1 4 0 a 2 2 0 1 1 -4 0 Z \
+             M 22.5 9 c -6 5 -9 11 -6 16 h 12 c 3 -5 0 -11 -6 -16 Z \
+             M 16 27 h 13 l 2 8 h -17 Z"
+        }
+        Piece::Rook => {
+            "M 13 9 h 4 v 3 h 3 v -3 h 5 v 3 h 3 v -3 h 4 v 7 h -3 \
+             l 1.5 19 h -17 l 1.5 -19 h -3 Z"
+        }
+        Piece::Queen => {
+            "M 12 30 l -3 -17 l 6 9 l 3 -13 l 4.5 12 l 4.5 -12 l 3 13 l 6 -9 l -3 17 Z \
+             M 12 30 h 21 l 1 5 h -23 Z"
+        }
+        Piece::King => {
+            "M 21 4 h 3 v 3 h 3 v 3 h -3 v 5 h -3 v -5 h -3 v -3 h 3 Z \
+             M 14 32 c -3 -8 2 -16 8.5 -16 c 6.5 0 11.5 8 8.5 16 Z \
+             M 14 32 h 17 v 3 h -17 Z"
+        }
+    }
+}
+
+// The colors used for square highlights and arrows, as in "[%csl Ga4]" and "[%cal Re2e4]".
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum MarkColor {
+    Green,
+    Red,
+    Yellow,
+    Blue,
+}
+
+const MARK_COLORS: &[MarkColor] = &[
+    MarkColor::Green,
+    MarkColor::Red,
+    MarkColor::Yellow,
+    MarkColor::Blue,
+];
+
+impl MarkColor {
+    fn from_char(ch: char) -> Option<MarkColor> {
+        match ch {
+            'G' => Some(MarkColor::Green),
+            'R' => Some(MarkColor::Red),
+            'Y' => Some(MarkColor::Yellow),
+            'B' => Some(MarkColor::Blue),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            MarkColor::Green => "green",
+            MarkColor::Red => "red",
+            MarkColor::Yellow => "yellow",
+            MarkColor::Blue => "blue",
+        }
+    }
+
+    fn rgb(self) -> &'static str {
+        match self {
+            MarkColor::Green => "#15781b",
+            MarkColor::Red => "#882020",
+            MarkColor::Yellow => "#e68f00",
+            MarkColor::Blue => "#003088",
+        }
+    }
+}
+
+// Whether the color can be used for the squares: "#rgb" or "#rrggbb" in hex, or a name such as
+// "tan". The color is written into the SVG as it is, so nothing else is allowed.
+pub fn is_color(color: &str) -> bool {
+    match color.strip_prefix('#') {
+        Some(hex) => {
+            (hex.len() == 3 || hex.len() == 6) && hex.chars().all(|ch| ch.is_ascii_hexdigit())
+        }
+        None => !color.is_empty() && color.chars().all(|ch| ch.is_ascii_alphabetic()),
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct SquareHighlight {
+    pub square: Square,
+    pub color: MarkColor,
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct Arrow {
+    pub from: Square,
+    pub to: Square,
+    pub color: MarkColor,
+}
+
+// The square highlights and arrows to draw on a diagram.
+#[derive(Debug, Default, Eq, PartialEq, Clone)]
+pub struct Marks {
+    pub squares: Vec<SquareHighlight>,
+    pub arrows: Vec<Arrow>,
+}
+
+fn parse_square(s: Option<&str>) -> Option<Square> {
+    s?.parse().ok()
+}
+
+impl Marks {
+    // Reads the "[%csl]" and "[%cal]" commands in the comments. Entries which can't be read are
+    // skipped.
+    pub fn from_comments<'a>(comments: impl IntoIterator<Item = &'a Comment>) -> Marks {
+        let mut marks = Marks::default();
+        for comment in comments {
+            for (name, value) in comment.commands() {
+                for entry in value.split(',').map(str::trim) {
+                    let color = match entry.chars().next().and_then(MarkColor::from_char) {
+                        Some(color) => color,
+                        None => continue,
+                    };
+                    match name {
+                        "csl" => {
+                            if let Some(square) = parse_square(entry.get(1..)) {
+                                marks.squares.push(SquareHighlight { square, color });
+                            }
+                        }
+                        "cal" => {
+                            if let (Some(from), Some(to)) =
+                                (parse_square(entry.get(1..3)), parse_square(entry.get(3..)))
+                            {
+                                marks.arrows.push(Arrow { from, to, color });
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        marks
+    }
+}
+
+// An SVG diagram of a position, with any highlights and arrows drawn over it.
+#[derive(Debug, Clone)]
+pub struct SvgDiagram<'a> {
+    position: &'a Position,
+    marks: Marks,
+    flipped: bool,
+    coordinates: bool,
+    light: String,
+    dark: String,
+    size: u32,
+}
+
+impl<'a> SvgDiagram<'a> {
+    pub fn new(position: &'a Position) -> Self {
+        SvgDiagram {
+            position,
+            marks: Marks::default(),
+            flipped: false,
+            coordinates: true,
+            light: "#f0d9b5".to_string(),
+            dark: "#b58863".to_string(),
+            size: 360,
+        }
+    }
+
+    pub fn marks(mut self, marks: Marks) -> Self {
+        self.marks = marks;
+        self
+    }
+
+    // Shows the board from Black's side.
+    pub fn flipped(mut self, flipped: bool) -> Self {
+        self.flipped = flipped;
+        self
+    }
+
+    pub fn coordinates(mut self, coordinates: bool) -> Self {
+        self.coordinates = coordinates;
+        self
+    }
+
+    // Sets the colors of the light and dark squares, e.g. "#eeeed2". Both must pass is_color().
+    pub fn colors(mut self, light: impl Into<String>, dark: impl Into<String>) -> Self {
+        self.light = light.into();
+        self.dark = dark.into();
+        self
+    }
+
+    // Sets the width and height of the image, in pixels.
+    pub fn size(mut self, size: u32) -> Self {
+        self.size = size;
+        self
+    }
+
+    // The top left corner of the square in the drawing.
+    fn corner(&self, square: Square) -> (f64, f64) {
+        let (column, row) = if self.flipped {
+            (8 - square.file.0, square.rank.0 - 1)
+        } else {
+            (square.file.0 - 1, 8 - square.rank.0)
+        };
+        (column as f64 * SQUARE_SIZE, row as f64 * SQUARE_SIZE)
+    }
+
+    fn center(&self, square: Square) -> (f64, f64) {
+        let (x, y) = self.corner(square);
+        (x + SQUARE_SIZE / 2.0, y + SQUARE_SIZE / 2.0)
+    }
+
+    fn is_light(square: Square) -> bool {
+        (square.file.0 + square.rank.0) % 2 == 1
+    }
+
+    fn squares() -> impl Iterator<Item = Square> {
+        (1..=8).flat_map(|rank| (1..=8).map(move |file| Square::new(File(file), Rank(rank))))
+    }
+
+    fn write_coordinates(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // Files are labeled along the bottom edge, and ranks along the left, each in the color of
+        // the other kind of square so that they stand out.
+        let bottom_rank = Rank(if self.flipped { 8 } else { 1 });
+        let left_file = File(if self.flipped { 8 } else { 1 });
+        for square in SvgDiagram::squares() {
+            let color = if SvgDiagram::is_light(square) {
+                &self.dark
+            } else {
+                &self.light
+            };
+            let (x, y) = self.corner(square);
+            if square.rank == bottom_rank {
+                writeln!(
+                    f,
+                    r#"<text x="{}" y="{}" font-size="9" font-family="sans-serif" text-anchor="end" fill="{}">{}</text>"#,
+                    x + SQUARE_SIZE - 2.0,
+                    y + SQUARE_SIZE - 2.0,
+                    color,
+                    square.file.to_char()
+                )?;
+            }
+            if square.file == left_file {
+                writeln!(
+                    f,
+                    r#"<text x="{}" y="{}" font-size="9" font-family="sans-serif" fill="{}">{}</text>"#,
+                    x + 2.0,
+                    y + 10.0,
+                    color,
+                    square.rank.to_char()
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_piece(
+        &self,
+        f: &mut Formatter<'_>,
+        square: Square,
+        piece: ColoredPiece,
+    ) -> std::fmt::Result {
+        let (x, y) = self.corner(square);
+        let (fill, stroke) = match piece.color {
+            Color::White => ("#ffffff", "#000000"),
+            Color::Black => ("#000000", "#ffffff"),
+        };
+        writeln!(
+            f,
+            r#"<path d="{} {}" transform="translate({} {})" fill="{}" stroke="{}" stroke-width="1.5" stroke-linejoin="round"/>"#,
+            piece_path(piece.piece),
+            BASE,
+            x,
+            y,
+            fill,
+            stroke
+        )
+    }
+
+    fn write_arrow(&self, f: &mut Formatter<'_>, arrow: &Arrow) -> std::fmt::Result {
+        let (x1, y1) = self.center(arrow.from);
+        let (x2, y2) = self.center(arrow.to);
+        // Stop the line short, so that the tip of the arrowhead is at the center of the square.
+        let length = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt();
+        let shorten = if length > 0.0 { 12.0 / length } else { 0.0 };
+        writeln!(
+            f,
+            r#"<line x1="{}" y1="{}" x2="{:.1}" y2="{:.1}" stroke="{}" stroke-width="7" stroke-linecap="round" opacity="0.8" marker-end="url(#arrowhead-{})"/>"#,
+            x1,
+            y1,
+            x2 - (x2 - x1) * shorten,
+            y2 - (y2 - y1) * shorten,
+            arrow.color.rgb(),
+            arrow.color.name()
+        )
+    }
+}
+
+impl Display for SvgDiagram<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{0}" viewBox="0 0 {1} {1}">"#,
+            self.size, BOARD_SIZE
+        )?;
+
+        if !self.marks.arrows.is_empty() {
+            writeln!(f, "<defs>")?;
+            for color in MARK_COLORS {
+                writeln!(
+                    f,
+                    r#"<marker id="arrowhead-{}" viewBox="0 0 10 10" refX="3" refY="5" markerWidth="3" markerHeight="3" orient="auto"><path d="M 0 0 L 10 5 L 0 10 Z" fill="{}"/></marker>"#,
+                    color.name(),
+                    color.rgb()
+                )?;
+            }
+            writeln!(f, "</defs>")?;
+        }
+
+        for square in SvgDiagram::squares() {
+            let (x, y) = self.corner(square);
+            let color = if SvgDiagram::is_light(square) {
+                &self.light
+            } else {
+                &self.dark
+            };
+            writeln!(
+                f,
+                r#"<rect x="{}" y="{}" width="{2}" height="{2}" fill="{3}"/>"#,
+                x, y, SQUARE_SIZE, color
+            )?;
+        }
+
+        for highlight in &self.marks.squares {
+            let (x, y) = self.corner(highlight.square);
+            writeln!(
+                f,
+                r#"<rect x="{}" y="{}" width="{2}" height="{2}" fill="{3}" opacity="0.5"/>"#,
+                x,
+                y,
+                SQUARE_SIZE,
+                highlight.color.rgb()
+            )?;
+        }
+
+        if self.coordinates {
+            self.write_coordinates(f)?;
+        }
+
+        for (square, piece) in self.position.pieces() {
+            self.write_piece(f, square, piece)?;
+        }
+
+        for arrow in &self.marks.arrows {
+            self.write_arrow(f, arrow)?;
+        }
+
+        writeln!(f, "</svg>")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn square(s: &str) -> Square {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_marks() {
+        let comments = vec![
+            Comment::new("[%csl Ga4,Rb5] Strong."),
+            Comment::new("[%cal Ge2e4,Bd1h5,Xa1a2] [%csl Z9]"),
+        ];
+        let marks = Marks::from_comments(&comments);
+        assert_eq!(
+            vec![
+                SquareHighlight {
+                    square: square("a4"),
+                    color: MarkColor::Green
+                },
+                SquareHighlight {
+                    square: square("b5"),
+                    color: MarkColor::Red
+                },
+            ],
+            marks.squares
+        );
+        assert_eq!(
+            vec![
+                Arrow {
+                    from: square("e2"),
+                    to: square("e4"),
+                    color: MarkColor::Green
+                },
+                Arrow {
+                    from: square("d1"),
+                    to: square("h5"),
+                    color: MarkColor::Blue
+                },
+            ],
+            marks.arrows
+        );
+    }
+
+    #[test]
+    fn test_is_color() {
+        for color in &["#f0d9b5", "#FFF", "tan", "DarkSeaGreen"] {
+            assert!(is_color(color), "{}", color);
+        }
+        for color in &[
+            "",
+            "#",
+            "#f0d9b",
+            "#ggg",
+            "red\"",
+            "red\" onload=\"x",
+            "rgb(1,2,3)",
+        ] {
+            assert!(!is_color(color), "{}", color);
+        }
+    }
+
+    #[test]
+    fn test_svg() {
+        let position = Position::starting();
+        let svg = SvgDiagram::new(&position).size(400).to_string();
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="400""#));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(
+            64 + 32,
+            svg.matches("<rect").count() + svg.matches("<path").count()
+        );
+        // a1 is dark, and in the bottom left corner.
+        assert!(svg.contains(r##"<rect x="0" y="315" width="45" height="45" fill="#b58863"/>"##));
+        assert!(!svg.contains("<marker"));
+
+        let flipped = SvgDiagram::new(&position)
+            .flipped(true)
+            .coordinates(false)
+            .to_string();
+        assert!(
+            flipped.contains(r##"<rect x="315" y="0" width="45" height="45" fill="#b58863"/>"##)
+        );
+        assert!(!flipped.contains("<text"));
+    }
+
+    #[test]
+    fn test_svg_marks() {
+        let position = Position::starting();
+        let marks = Marks::from_comments(&[Comment::new("[%csl Ye4] [%cal Re2e4]")]);
+        let svg = SvgDiagram::new(&position).marks(marks).to_string();
+        assert!(svg.contains(
+            r##"<rect x="180" y="180" width="45" height="45" fill="#e68f00" opacity="0.5"/>"##
+        ));
+        assert!(svg.contains(r#"marker-end="url(#arrowhead-red)""#));
+        assert_eq!(4, svg.matches("<marker").count());
+    }
+}
@@ -0,0 +1,527 @@
+use crate::pgn_parser::{
+    Comment, Element, ElementSequence, PgnDatabase, PgnGame, SanMove, SequenceMember,
+};
+use crate::position::{Color, Move, Position};
+use crate::replay::starting_position;
+use crate::Result;
+
+// The width the move list is wrapped to.
+const SCREEN_WIDTH: usize = 72;
+
+const HELP: &str = "h/\u{2190} back  l/\u{2192} forward  j/\u{2193} enter variation  \
+                    k/\u{2191} leave variation  v next variation\n\
+                    g/G start/end  n/p next/previous game  q quit";
+
+// A command for the viewer. Keys are decoded from terminal input, so the viewer can be driven by
+// a script as well as by a person.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Key {
+    Forward,
+    Back,
+    EnterVariation,
+    ExitVariation,
+    NextVariation,
+    Start,
+    End,
+    NextGame,
+    PreviousGame,
+    Quit,
+}
+
+impl Key {
+    fn from_letter(letter: u8) -> Option<Key> {
+        match letter {
+            b'l' | b' ' => Some(Key::Forward),
+            b'h' | 0x7f => Some(Key::Back),
+            b'j' => Some(Key::EnterVariation),
+            b'k' => Some(Key::ExitVariation),
+            b'v' | b'\t' => Some(Key::NextVariation),
+            b'g' => Some(Key::Start),
+            b'G' => Some(Key::End),
+            b'n' => Some(Key::NextGame),
+            b'p' => Some(Key::PreviousGame),
+            b'q' => Some(Key::Quit),
+            _ => None,
+        }
+    }
+
+    // The keys for the escape sequences terminals send, without the leading "ESC [" or "ESC O".
+    fn from_escape(sequence: &[u8]) -> Option<Key> {
+        match sequence {
+            b"A" => Some(Key::ExitVariation),
+            b"B" => Some(Key::EnterVariation),
+            b"C" => Some(Key::Forward),
+            b"D" => Some(Key::Back),
+            b"H" | b"1~" | b"7~" => Some(Key::Start),
+            b"F" | b"4~" | b"8~" => Some(Key::End),
+            b"5~" => Some(Key::PreviousGame),
+            b"6~" => Some(Key::NextGame),
+            _ => None,
+        }
+    }
+
+    // Decodes terminal input: letters, and the escape sequences for the arrow, home, end and page
+    // keys. Anything else is skipped.
+    pub fn decode(input: &[u8]) -> Vec<Key> {
+        let mut keys = vec![];
+        let mut i = 0;
+        while i < input.len() {
+            if input[i] == 0x1b && matches!(input.get(i + 1), Some(b'[') | Some(b'O')) {
+                // The sequence runs up to and including the first byte in '@'..='~'.
+                let start = i + 2;
+                let end = input[start..]
+                    .iter()
+                    .position(|byte| (b'@'..=b'~').contains(byte))
+                    .map_or(input.len(), |offset| start + offset + 1);
+                keys.extend(Key::from_escape(&input[start..end]));
+                i = end;
+            } else {
+                keys.extend(Key::from_letter(input[i]));
+                i += 1;
+            }
+        }
+        keys
+    }
+}
+
+// A move of a line with the annotations after it and the variations that replace it.
+struct LineMove<'a> {
+    san: &'a SanMove,
+    nags: Vec<u8>,
+    comments: Vec<&'a Comment>,
+    variations: Vec<Line<'a>>,
+}
+
+// The moves of an element sequence. Each variation is attached to the move it is an alternative
+// to, which is the move before it in the sequence.
+#[derive(Default)]
+struct Line<'a> {
+    // Comments before the first move.
+    comments: Vec<&'a Comment>,
+    moves: Vec<LineMove<'a>>,
+}
+
+impl<'a> Line<'a> {
+    fn new(sequence: &'a ElementSequence) -> Self {
+        let mut line = Line::default();
+        for member in sequence.members() {
+            let last = line.moves.last_mut();
+            match member {
+                SequenceMember::Move(Element::Move(san)) => line.moves.push(LineMove {
+                    san,
+                    nags: vec![],
+                    comments: vec![],
+                    variations: vec![],
+                }),
+                SequenceMember::Move(Element::Annotation(nag)) => {
+                    if let Some(last) = last {
+                        last.nags.push(nag.0);
+                    }
+                }
+                SequenceMember::Comment(comment) => match last {
+                    Some(last) => last.comments.push(comment),
+                    None => line.comments.push(comment),
+                },
+                SequenceMember::Variation(variation) => {
+                    let variation = Line::new(variation.sequence());
+                    // An empty variation has nothing to step through.
+                    if let Some(last) = last.filter(|_| !variation.moves.is_empty()) {
+                        last.variations.push(variation);
+                    }
+                }
+                SequenceMember::Move(Element::MoveNumber(_)) => {}
+            }
+        }
+        line
+    }
+
+    // Follows a path of variations from this line.
+    fn follow(&self, path: &[(usize, usize)]) -> &Line<'a> {
+        path.iter().fold(self, |line, &(branch, index)| {
+            &line.moves[branch].variations[index]
+        })
+    }
+}
+
+// The symbols for the common move assessment NAGs, and "$n" for the rest.
+fn nag_symbol(nag: u8) -> String {
+    match nag {
+        1 => "!".to_string(),
+        2 => "?".to_string(),
+        3 => "!!".to_string(),
+        4 => "??".to_string(),
+        5 => "!?".to_string(),
+        6 => "?!".to_string(),
+        _ => format!(" ${}", nag),
+    }
+}
+
+// The move number prefix for a ply of a line starting in the given position: "3." for White,
+// "3..." for Black, or nothing if the number isn't needed.
+fn move_number(start: &Position, ply: usize, always: bool) -> Option<String> {
+    match start.move_number_after(ply) {
+        (number, Color::White) => Some(format!("{}.", number)),
+        (number, Color::Black) if always => Some(format!("{}...", number)),
+        _ => None,
+    }
+}
+
+fn wrap(tokens: &[String]) -> String {
+    let mut output = String::new();
+    let mut line_length = 0;
+    for token in tokens {
+        let token_length = token.chars().count();
+        if line_length > 0 && line_length + 1 + token_length > SCREEN_WIDTH {
+            output.push('\n');
+            line_length = 0;
+        }
+        if line_length > 0 {
+            output.push(' ');
+            line_length += 1;
+        }
+        output.push_str(token);
+        line_length += token_length;
+    }
+    output
+}
+
+// Steps through the games of a database, including their variations. Keys move through the game
+// tree and screen() draws the current state, so the viewer doesn't depend on a terminal.
+pub struct Viewer {
+    games: Vec<PgnGame>,
+    game: usize,
+    // The variations entered from the mainline: the index in its parent line of the move each
+    // one replaces, and which of that move's variations it is.
+    path: Vec<(usize, usize)>,
+    // The number of moves of the current line that have been played.
+    ply: usize,
+    unicode: bool,
+    done: bool,
+}
+
+impl Viewer {
+    pub fn new(database: PgnDatabase) -> Self {
+        Viewer {
+            games: database.into_games(),
+            game: 0,
+            path: vec![],
+            ply: 0,
+            unicode: false,
+            done: false,
+        }
+    }
+
+    pub fn unicode(mut self, unicode: bool) -> Self {
+        self.unicode = unicode;
+        self
+    }
+
+    // True once Quit has been pressed.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    pub fn press(&mut self, key: Key) {
+        // The sizes of the parts of the tree around the current move.
+        let (length, alternatives, siblings) = match self.games.get(self.game) {
+            Some(game) => {
+                let root = Line::new(game.movetext().element_sequence());
+                let line = root.follow(&self.path);
+                let alternatives = line
+                    .moves
+                    .get(self.ply)
+                    .map_or(0, |next| next.variations.len());
+                let siblings = match self.path.split_last() {
+                    Some((&(branch, _), parents)) => {
+                        root.follow(parents).moves[branch].variations.len()
+                    }
+                    None => 0,
+                };
+                (line.moves.len(), alternatives, siblings)
+            }
+            None => (0, 0, 0),
+        };
+        // Every variation has at least one move, so the first move of a variation is played on
+        // entering it, and stepping back from there leaves it.
+        let first_ply = if self.path.is_empty() { 0 } else { 1 };
+
+        match key {
+            Key::Forward => self.ply = (self.ply + 1).min(length),
+            Key::Back if self.ply > first_ply => self.ply -= 1,
+            Key::Back | Key::ExitVariation => {
+                if let Some((branch, _)) = self.path.pop() {
+                    self.ply = branch;
+                }
+            }
+            Key::EnterVariation => {
+                if alternatives > 0 {
+                    self.path.push((self.ply, 0));
+                    self.ply = 1;
+                }
+            }
+            Key::NextVariation => {
+                if let Some((_, index)) = self.path.last_mut() {
+                    *index = (*index + 1) % siblings;
+                    self.ply = 1;
+                }
+            }
+            Key::Start => self.ply = first_ply,
+            Key::End => self.ply = length,
+            Key::NextGame => {
+                if self.game + 1 < self.games.len() {
+                    self.select_game(self.game + 1);
+                }
+            }
+            Key::PreviousGame => {
+                if self.game > 0 {
+                    self.select_game(self.game - 1);
+                }
+            }
+            Key::Quit => self.done = true,
+        }
+    }
+
+    fn select_game(&mut self, game: usize) {
+        self.game = game;
+        self.path.clear();
+        self.ply = 0;
+    }
+
+    // Plays the moves leading to the start of the current line.
+    fn line_start(&self, game: &PgnGame, root: &Line) -> Result<Position> {
+        let mut position = starting_position(game)?;
+        let mut line = root;
+        for &(branch, index) in &self.path {
+            for line_move in &line.moves[..branch] {
+                position = position.play(&position.resolve(line_move.san)?);
+            }
+            line = &line.moves[branch].variations[index];
+        }
+        Ok(position)
+    }
+
+    // Plays the current line up to the current move, returning the position and the last move.
+    fn current_position(&self, start: &Position, line: &Line) -> Result<(Position, Option<Move>)> {
+        let mut position = start.clone();
+        let mut last_move = None;
+        for line_move in &line.moves[..self.ply] {
+            let mv = position.resolve(line_move.san)?;
+            position = position.play(&mv);
+            last_move = Some(mv);
+        }
+        Ok((position, last_move))
+    }
+
+    fn header(&self, game: &PgnGame) -> String {
+        let tag = |name| game.tag(name).unwrap_or("?");
+        let line = match self.path.len() {
+            0 => "mainline".to_string(),
+            depth => format!("variation, depth {}", depth),
+        };
+        format!(
+            "Game {} of {}: {} - {} {} ({})",
+            self.game + 1,
+            self.games.len(),
+            tag("White"),
+            tag("Black"),
+            tag("Result"),
+            line
+        )
+    }
+
+    // The moves of the current line, with the current move in brackets. NAGs follow their move
+    // and a move with variations is marked with the number of them.
+    fn move_list(&self, start: &Position, line: &Line) -> String {
+        let mut tokens = vec![];
+        for (i, line_move) in line.moves.iter().enumerate() {
+            tokens.extend(move_number(start, i, i == 0));
+            let mut token = line_move.san.to_string();
+            for &nag in &line_move.nags {
+                token.push_str(&nag_symbol(nag));
+            }
+            if i + 1 == self.ply {
+                token = format!("[{}]", token);
+            }
+            if !line_move.variations.is_empty() {
+                token.push_str(&format!(" (+{})", line_move.variations.len()));
+            }
+            tokens.push(token);
+        }
+        wrap(&tokens)
+    }
+
+    // The variations that can be entered in place of the next move.
+    fn alternatives(&self, start: &Position, line: &Line) -> Option<String> {
+        let next = line.moves.get(self.ply)?;
+        if next.variations.is_empty() {
+            return None;
+        }
+        let alternatives: Vec<String> = next
+            .variations
+            .iter()
+            .map(|variation| {
+                let number = move_number(start, self.ply, true).unwrap_or_default();
+                format!("{} {}", number, variation.moves[0].san)
+            })
+            .collect();
+        Some(alternatives.join(", "))
+    }
+
+    // Draws the game: a header, the board, the moves of the current line, the comments on the
+    // current move and the variations that replace the next one.
+    pub fn screen(&self) -> String {
+        let game = match self.games.get(self.game) {
+            Some(game) => game,
+            None => return format!("No games\n\n{}", HELP),
+        };
+        let root = Line::new(game.movetext().element_sequence());
+        let line = root.follow(&self.path);
+
+        let mut screen = format!("{}\n\n", self.header(game));
+        let start = match self.line_start(game, &root) {
+            Ok(start) => start,
+            Err(error) => return format!("{}Error: {}\n\n{}", screen, error, HELP),
+        };
+        match self.current_position(&start, line) {
+            Ok((position, last_move)) => {
+                let diagram = position
+                    .diagram()
+                    .last_move(last_move)
+                    .unicode(self.unicode);
+                screen.push_str(&format!("{}\n\n", diagram));
+            }
+            Err(error) => screen.push_str(&format!("Error: {}\n\n", error)),
+        }
+
+        screen.push_str(&format!("{}\n", self.move_list(&start, line)));
+        let comments = match self.ply.checked_sub(1) {
+            Some(current) => &line.moves[current].comments,
+            None => &line.comments,
+        };
+        for comment in comments {
+            screen.push_str(&format!("Comment: {}\n", comment.text()));
+        }
+        if let Some(alternatives) = self.alternatives(&start, line) {
+            screen.push_str(&format!("Variations: {}\n", alternatives));
+        }
+        screen.push('\n');
+        screen.push_str(HELP);
+        screen
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse_pgn;
+
+    fn viewer(pgn: &str) -> Viewer {
+        Viewer::new(parse_pgn(pgn).unwrap())
+    }
+
+    fn press(viewer: &mut Viewer, script: &str) {
+        for key in Key::decode(script.as_bytes()) {
+            viewer.press(key);
+        }
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(
+            vec![
+                Key::Forward,
+                Key::Back,
+                Key::ExitVariation,
+                Key::NextGame,
+                Key::Quit
+            ],
+            Key::decode(b"\x1b[C\x1bOD\x1b[A\x1b[6~x\x1b[99Xq")
+        );
+    }
+
+    #[test]
+    fn test_stepping() {
+        let mut viewer = viewer(
+            "[White \"Tal\"]\n[Black \"Smyslov\"]\n[Result \"*\"]\n\n\
+             {Start} 1. e4 $1 e5 {Solid} 2. Nf3 *",
+        );
+        let screen = viewer.screen();
+        assert!(screen.starts_with("Game 1 of 1: Tal - Smyslov * (mainline)"));
+        assert!(screen.contains("White to move"));
+        assert!(screen.contains("Comment: Start"));
+
+        press(&mut viewer, "ll");
+        let screen = viewer.screen();
+        assert!(screen.contains("1. e4! [e5] 2. Nf3"));
+        assert!(screen.contains("5 | .  .  .  . [p] .  .  . |"));
+        assert!(screen.contains("Comment: Solid"));
+
+        // Stepping is clamped at both ends.
+        press(&mut viewer, "llll");
+        assert!(viewer.screen().contains("[Nf3]"));
+        press(&mut viewer, "hhhhh");
+        assert!(viewer.screen().contains("1. e4! e5 2. Nf3\n"));
+        assert!(!viewer.is_done());
+        press(&mut viewer, "q");
+        assert!(viewer.is_done());
+    }
+
+    #[test]
+    fn test_variations() {
+        let mut viewer = viewer("1. e4 e5 (1... c5 2. Nf3 (2. Nc3)) (1... e6) 2. Nf3 *");
+        press(&mut viewer, "l");
+        let screen = viewer.screen();
+        assert!(screen.contains("1. [e4] e5 (+2) 2. Nf3"));
+        assert!(screen.contains("Variations: 1... c5, 1... e6"));
+
+        press(&mut viewer, "j");
+        let screen = viewer.screen();
+        assert!(screen.contains("(variation, depth 1)"));
+        assert!(screen.contains("1... [c5] 2. Nf3 (+1)"));
+        assert!(screen.contains("Variations: 2. Nc3"));
+
+        press(&mut viewer, "j");
+        assert!(viewer.screen().contains("(variation, depth 2)"));
+        assert!(viewer.screen().contains("2. [Nc3]"));
+
+        // Leaving a variation returns to the move it replaces.
+        press(&mut viewer, "k");
+        assert!(viewer.screen().contains("1... [c5] 2. Nf3 (+1)"));
+        press(&mut viewer, "v");
+        assert!(viewer.screen().contains("1... [e6]\n"));
+        press(&mut viewer, "v");
+        assert!(viewer.screen().contains("1... [c5]"));
+
+        // Stepping back from the first move of a variation leaves it too.
+        press(&mut viewer, "h");
+        let screen = viewer.screen();
+        assert!(screen.contains("(mainline)"));
+        assert!(screen.contains("1. [e4] e5 (+2)"));
+    }
+
+    #[test]
+    fn test_games() {
+        let mut viewer = viewer(
+            "[White \"A\"]\n\n1. e4 *\n\n\
+             [White \"B\"]\n[FEN \"4k3/8/8/8/8/8/8/4K2R b K - 0 30\"]\n\n30... Kd7 31. O-O *",
+        );
+        press(&mut viewer, "ln");
+        let screen = viewer.screen();
+        assert!(screen.starts_with("Game 2 of 2: B - ?"));
+        assert!(screen.contains("Black to move"));
+        press(&mut viewer, "G");
+        assert!(viewer.screen().contains("30... Kd7 31. [O-O]"));
+        press(&mut viewer, "nn\x1b[5~");
+        assert!(viewer.screen().starts_with("Game 1 of 2: A - ?"));
+        assert!(viewer.screen().contains("1. e4\n"));
+    }
+
+    #[test]
+    fn test_illegal_move() {
+        let mut viewer = viewer("1. e4 e5 2. Ke3 *");
+        press(&mut viewer, "lll");
+        let screen = viewer.screen();
+        assert!(screen.contains("Error:"));
+        assert!(screen.contains("2. [Ke3]"));
+    }
+}